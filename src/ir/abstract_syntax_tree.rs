@@ -1,24 +1,28 @@
+use std::collections::HashSet;
+
 use sleigh_compile::ldef::SleighLanguage;
 
 use crate::{
     ir::{
         basic_block::{BlockSlot, DestinationKind, NextBlock},
-        expression::{InstructionSize, OpIdx},
-        high_function::CallingConvention,
+        expression::OpIdx,
         type_system::VariableType,
     },
     memory::Memory,
 };
 
 use super::{
-    control_flow_graph::SingleEntrySingleExit,
+    control_flow_graph::{is_ancestor, SingleEntrySingleExit},
     high_function::HighFunction,
+    loops::{InductionVariable, NaturalLoop},
     scope::{Scope, VariableDefinition},
     Address, BasicBlock, Expression, ExpressionOp, VariableSymbol,
 };
 
 pub struct AbstractSyntaxTree {
     pub scope: Scope,
+    /// Blocks drawn somewhere in the tree
+    pub drawn: HashSet<BlockSlot>,
     entry: AstStatement,
 }
 
@@ -40,6 +44,8 @@ pub enum AstStatement {
         destination: DestinationKind,
         params: Vec<Expression>,
         call_from: Address,
+        /// Made by a tail call, so the function returns whatever the callee returns
+        tail: bool,
     },
     If {
         sese: SingleEntrySingleExit<BlockSlot>,
@@ -49,11 +55,40 @@ pub enum AstStatement {
         else_statement: Box<AstStatement>,
         else_branch: BlockSlot,
     },
-    Loop {
+    /// Loop that tests its condition before every iteration
+    While {
+        sese: SingleEntrySingleExit<BlockSlot>,
+        condition: Expression,
+        body: Box<AstStatement>,
+        body_address: BlockSlot,
+    },
+    /// Loop that tests its condition after every iteration
+    DoWhile {
+        sese: SingleEntrySingleExit<BlockSlot>,
+        condition: Expression,
+        body: Box<AstStatement>,
+        body_address: BlockSlot,
+    },
+    /// `while` loop whose condition tests a counter, which is set before the loop and stepped
+    /// at the end of the body
+    For {
         sese: SingleEntrySingleExit<BlockSlot>,
         condition: Expression,
         body: Box<AstStatement>,
         body_address: BlockSlot,
+        induction: InductionVariable,
+    },
+    Break,
+    Continue,
+    /// Target of [`AstStatement::Goto`], in front of the statements of its block
+    Label(BlockSlot),
+    /// Jump to a block that the structured statements can't reach
+    Goto(BlockSlot),
+    Switch {
+        sese: SingleEntrySingleExit<BlockSlot>,
+        selector: Expression,
+        /// Values of the cases that share a body, and the body
+        cases: Vec<(Vec<u64>, Box<AstStatement>)>,
     },
     Return {
         sese: SingleEntrySingleExit<BlockSlot>,
@@ -67,6 +102,68 @@ impl AstStatement {
     pub fn is_nop(&self) -> bool {
         matches!(self, AstStatement::Nop)
     }
+
+    /// Whether control can reach the end of this statement, rather than leaving the function,
+    /// the loop it's in or jumping to a label.
+    pub fn falls_through(&self) -> bool {
+        match self {
+            AstStatement::Block(stmts) => stmts
+                .iter()
+                .rev()
+                .find(|s| {
+                    !matches!(
+                        s,
+                        AstStatement::Nop
+                            | AstStatement::Comment(..)
+                            | AstStatement::MultilineComment(..)
+                    )
+                })
+                .is_none_or(AstStatement::falls_through),
+            AstStatement::If {
+                true_statement,
+                else_statement,
+                ..
+            } => true_statement.falls_through() || else_statement.falls_through(),
+            AstStatement::Return { .. }
+            | AstStatement::Call { tail: true, .. }
+            | AstStatement::Break
+            | AstStatement::Continue
+            | AstStatement::Goto(..) => false,
+            _ => true,
+        }
+    }
+
+    pub fn is_loop(&self) -> bool {
+        matches!(
+            self,
+            AstStatement::While { .. } | AstStatement::DoWhile { .. } | AstStatement::For { .. }
+        )
+    }
+
+    /// Whether this is an assignment to `variable`, a register or a memory read, or the value one
+    /// of them carries into a loop iteration.
+    fn assigns(&self, variable: &Expression) -> bool {
+        let AstStatement::Assignment { destination, .. } = self else {
+            return false;
+        };
+        let location = match variable.root_op() {
+            Some(ExpressionOp::Variable(VariableSymbol::LoopCarried { location, .. })) => {
+                location.as_ref()
+            }
+            Some(ExpressionOp::Variable(symbol)) if variable.len() == 1 => symbol,
+            _ => return destination == variable,
+        };
+        match (destination.root_op(), location) {
+            (Some(ExpressionOp::Dereference(address)), VariableSymbol::Ram(read, _)) => {
+                destination.get_sub_expression(*address) == **read
+            }
+            (
+                Some(ExpressionOp::Variable(VariableSymbol::Varnode(written))),
+                VariableSymbol::Varnode(var_node),
+            ) => written == var_node,
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Debug for AstStatement {
@@ -78,7 +175,14 @@ impl std::fmt::Debug for AstStatement {
             AstStatement::If { .. } => f.write_str("AstNode::If"),
             AstStatement::Comment(..) => f.write_str("AstNode::Comment"),
             AstStatement::MultilineComment(..) => f.write_str("AstNode::MultilineComment"),
-            AstStatement::Loop { .. } => f.write_str("AstNode::Loop"),
+            AstStatement::While { .. } => f.write_str("AstNode::While"),
+            AstStatement::DoWhile { .. } => f.write_str("AstNode::DoWhile"),
+            AstStatement::For { .. } => f.write_str("AstNode::For"),
+            AstStatement::Break => f.write_str("AstNode::Break"),
+            AstStatement::Continue => f.write_str("AstNode::Continue"),
+            AstStatement::Label(..) => f.write_str("AstNode::Label"),
+            AstStatement::Goto(..) => f.write_str("AstNode::Goto"),
+            AstStatement::Switch { .. } => f.write_str("AstNode::Switch"),
             AstStatement::Return { .. } => f.write_str("AstNode::Return"),
             AstStatement::Block(_) => f.write_str("AstNode::Block"),
             AstStatement::Nop => f.write_str("AstNode::Nop"),
@@ -128,13 +232,8 @@ impl AbstractSyntaxTree {
             }
         }
 
-        let body = AstStatement::Block(build_block(
-            &mut scope,
-            hf.cfg.start,
-            hf,
-            &mem.lang,
-            hf.pts.root,
-        ));
+        let (body, drawn) = build_function_body(&mut scope, hf, &mem.lang);
+        let body = AstStatement::Block(body);
 
         let mut statements = Vec::new();
         // statements.push(AstStatement::Comment(format!("Scope:")));
@@ -148,24 +247,27 @@ impl AbstractSyntaxTree {
         // for write in &hf.memory_written {
         //     statements.push(AstStatement::Comment(format!("{write}")));
         // }
-        let mut args = Vec::new();
-        match hf.calling_convention {
-            CallingConvention::Cdecl => {
-                for addr in &hf.memory_read {
-                    if let ExpressionOp::Variable(VariableSymbol::Varnode(r)) =
-                        addr.get(OpIdx::from_idx(0))
-                    {
-                        if r == &mem.lang.sp {
-                            if let ExpressionOp::Value(_) = addr.get(OpIdx::from_idx(1)) {
-                                if let ExpressionOp::Add(_, _, _) = addr.get(OpIdx::from_idx(2)) {
-                                    args.push(VariableSymbol::Ram(Box::new(addr.clone()), 4));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        for variable in hf.stack_frame.variables() {
+            let symbol = variable.symbol(mem.lang.sp);
+            scope.add(
+                hf.pts.root,
+                symbol.clone(),
+                VariableDefinition::new(VariableType::default(), variable.name(), symbol),
+            );
         }
+
+        // stack parameters are named after their stack frame variable
+        let args = hf
+            .parameters
+            .iter()
+            .map(|parameter| match parameter {
+                VariableSymbol::Ram(addr, _) => hf
+                    .stack_frame
+                    .variable_at(addr, mem.lang.sp)
+                    .map_or_else(|| parameter.clone(), |v| v.symbol(mem.lang.sp)),
+                _ => parameter.clone(),
+            })
+            .collect();
         statements.push(AstStatement::Function {
             name: VariableSymbol::Ram(Box::new(Expression::from(hf.start)), 4),
             args,
@@ -173,6 +275,7 @@ impl AbstractSyntaxTree {
         });
         Self {
             scope,
+            drawn,
             entry: AstStatement::Block(statements),
         }
     }
@@ -182,45 +285,320 @@ impl AbstractSyntaxTree {
     }
 }
 
-fn build_block(
+/// The function whose body is being built, and what has been drawn of it so far.
+struct BuildContext<'a> {
+    hf: &'a HighFunction,
+    lang: &'a SleighLanguage,
+    /// Blocks already drawn in the syntax tree
+    drawn: HashSet<BlockSlot>,
+    /// Blocks whose drawing isn't done yet, which can only be reached again through a `goto`
+    unfinished: HashSet<BlockSlot>,
+}
+
+impl<'a> BuildContext<'a> {
+    fn new(hf: &'a HighFunction, lang: &'a SleighLanguage) -> Self {
+        Self {
+            hf,
+            lang,
+            drawn: HashSet::new(),
+            unfinished: HashSet::new(),
+        }
+    }
+}
+
+/// Build the statements of the whole function. Blocks that the structured statements don't
+/// reach are added after them, so that every block is drawn; they're labeled only if a `goto`
+/// jumps to them.
+///
+/// A label goes where its block is first drawn, which is only known once a `goto` to it is
+/// found, so the body is built again until no new labels turn up. Returns the statements and
+/// the blocks drawn in them.
+fn build_function_body(
     scope: &mut Scope,
-    start: BlockSlot,
     hf: &HighFunction,
     lang: &SleighLanguage,
+) -> (Vec<AstStatement>, HashSet<BlockSlot>) {
+    let mut blocks: Vec<BlockSlot> = hf
+        .composed_blocks
+        .iter_function(hf.cfg.start)
+        .filter(|slot| *slot != hf.cfg.single_end())
+        .collect();
+    blocks.sort();
+    loop {
+        let known_labels = scope.labels.len();
+        let mut context = BuildContext::new(hf, lang);
+        let mut body = build_block(scope, &mut context, hf.cfg.start, hf.pts.root, None);
+        for slot in &blocks {
+            if !context.drawn.contains(slot) {
+                body.extend(build_block(scope, &mut context, *slot, hf.pts.root, None));
+            }
+        }
+        if scope.labels.len() == known_labels {
+            return (body, context.drawn);
+        }
+    }
+}
+
+/// The innermost loop whose body is being built. Reaching its follow is a `break`, and going
+/// back to its header a `continue`.
+#[derive(Clone, Copy)]
+struct LoopExits<'l> {
+    natural_loop: &'l NaturalLoop,
+    /// First block after the loop
+    follow: BlockSlot,
+    /// Latch of a `do while`, whose jump ends the body
+    bottom_test: Option<BlockSlot>,
+}
+
+fn build_block(
+    scope: &mut Scope,
+    context: &mut BuildContext<'_>,
+    start: BlockSlot,
     sese: SingleEntrySingleExit<BlockSlot>,
+    exits: Option<LoopExits>,
 ) -> Vec<AstStatement> {
     let mut ast = Vec::new();
-    let mut branch_block_slot = add_assignments(&mut ast, start, hf, lang, sese);
+    let branch_block_slot = add_successor(scope, context, &mut ast, start, sese, exits);
+    add_segments(scope, context, &mut ast, branch_block_slot, sese, exits);
+    ast
+}
 
-    if branch_block_slot == sese.1 {
-        return ast;
+/// Add the loops and the program segments of the child regions of `sese`, from the branch at
+/// `branch_block_slot` up to the end of `sese`.
+fn add_segments(
+    scope: &mut Scope,
+    context: &mut BuildContext<'_>,
+    ast: &mut Vec<AstStatement>,
+    mut branch_block_slot: BlockSlot,
+    sese: SingleEntrySingleExit<BlockSlot>,
+    exits: Option<LoopExits>,
+) {
+    let hf = context.hf;
+    let pts_children = hf.pts.get_children(sese).unwrap_or_default();
+    while branch_block_slot != sese.1
+        && branch_block_slot != hf.cfg.single_end()
+        && exits.and_then(|e| e.bottom_test) != Some(branch_block_slot)
+    {
+        let next = &hf.composed_blocks[branch_block_slot].next;
+        let is_branch = matches!(next, NextBlock::Jump { .. } | NextBlock::Switch { .. })
+            && next.constant_branch().is_none();
+        let next_loop = hf.loops.iter().find(|l| {
+            l.header == branch_block_slot
+                && exits.is_none_or(|e| {
+                    e.natural_loop.header != l.header && e.natural_loop.body.contains(&l.header)
+                })
+        });
+        // loops go back to their headers through `continue`s instead
+        if next_loop.is_none() && !context.unfinished.insert(branch_block_slot) {
+            add_goto(scope, ast, branch_block_slot);
+            break;
+        }
+        let end = if let Some(natural_loop) = next_loop {
+            let region = pts_children
+                .iter()
+                .find(|p| p.0 == branch_block_slot)
+                .unwrap_or(&sese);
+            add_loop(scope, context, ast, natural_loop, *region)
+        } else if let Some(c_pts) = pts_children.iter().find(|p| p.0 == branch_block_slot) {
+            // child block fails out to the same address as parent block - no need to draw else branch.
+            add_program_segment(
+                scope,
+                context,
+                ast,
+                *c_pts,
+                c_pts.1 == sese.1 && c_pts.1 != hf.cfg.single_end(),
+                exits,
+            );
+            c_pts.1
+        } else if is_branch && branch_block_slot != sese.0 {
+            // a test without a region of its own, like one with a branch leaving the loop or
+            // one in a cycle that isn't a natural loop, goes on until the first block every
+            // branch reaches
+            let end = hf
+                .cfg
+                .post_dominators(branch_block_slot)
+                .first()
+                .copied()
+                .unwrap_or(hf.cfg.single_end());
+            let region = SingleEntrySingleExit(branch_block_slot, end);
+            let parents = scope.parents.entry(region).or_default();
+            if !parents.contains(&sese) {
+                parents.push(sese);
+            }
+            add_program_segment(scope, context, ast, region, false, exits);
+            end
+        } else {
+            // the root starting with a branch, or the body of a loop starting with its header
+            if is_branch {
+                add_program_segment(scope, context, ast, sese, false, exits);
+            }
+            sese.1
+        };
+        if next_loop.is_none() {
+            context.unfinished.remove(&branch_block_slot);
+        }
+        if end == sese.1 || end == hf.cfg.single_end() {
+            break;
+        }
+        branch_block_slot = add_successor(scope, context, ast, end, sese, exits);
     }
+}
 
-    if let Some(pts_children) = hf.pts.get_children(sese) {
-        // print
-        if pts_children.len() == 0 && hf.pts.root == sese {
-            add_program_segment(scope, &mut ast, hf, lang, sese, false);
+/// Add the loop starting at the header of `natural_loop`, built in the region `sese`, and
+/// return the first block after it.
+///
+/// The flavour depends on where the loop tests whether to go on:
+/// - at the header, which does nothing else: `while`, or `for` when the test is on a counter
+/// - at the only latch, which post-dominates the header, so every iteration ends with the test:
+///   `do while`
+/// - anywhere else: `while (1)`, leaving through `break`s
+fn add_loop(
+    scope: &mut Scope,
+    context: &mut BuildContext<'_>,
+    ast_block: &mut Vec<AstStatement>,
+    natural_loop: &NaturalLoop,
+    sese: SingleEntrySingleExit<BlockSlot>,
+) -> BlockSlot {
+    let hf = context.hf;
+    let header = natural_loop.header;
+    let follow = hf
+        .cfg
+        .post_dominators(header)
+        .into_iter()
+        .find(|slot| !natural_loop.body.contains(slot))
+        .unwrap_or(hf.cfg.single_end());
+
+    // condition of the jump at `slot` to stay in the loop, and where it goes then, when the
+    // other branch leaves for `follow`
+    let exit_test = |slot: BlockSlot| {
+        let NextBlock::Jump {
+            condition,
+            true_branch,
+            false_branch,
+        } = &hf.composed_blocks[slot].next
+        else {
+            return None;
+        };
+        let true_branch = hf.composed_blocks.slot_by_destination(true_branch)?;
+        let false_branch = hf.composed_blocks.slot_by_destination(false_branch)?;
+        let mut condition = condition.clone();
+        if false_branch == follow && natural_loop.body.contains(&true_branch) {
+            Some((condition, true_branch))
+        } else if true_branch == follow && natural_loop.body.contains(&false_branch) {
+            condition.not();
+            Some((condition, false_branch))
         } else {
-            while let Some(c_pts) = pts_children.iter().find(|p| p.0 == branch_block_slot) {
-                // child block fails out to the same address as parent block - no need to draw else branch.
-                add_program_segment(
-                    scope,
-                    &mut ast,
-                    hf,
-                    lang,
-                    *c_pts,
-                    c_pts.1 == sese.1 && c_pts.1 != hf.cfg.single_end(),
-                );
-                if c_pts.1 != hf.cfg.single_end() {
-                    branch_block_slot = add_assignments(&mut ast, c_pts.1, hf, lang, sese);
-                }
-                if c_pts.1 == sese.1 {
-                    break;
-                }
+            None
+        }
+    };
+    // registers the header sets only feed its test
+    let is_condition_only = hf.composed_blocks[header].memory_writes.is_empty();
+    let bottom_test = match natural_loop.latches[..] {
+        [latch]
+            if is_ancestor(
+                hf.cfg.get_node_idx(latch),
+                hf.cfg.get_node_idx(header),
+                &hf.cfg.pdom,
+            ) =>
+        {
+            exit_test(latch)
+                .filter(|(_, stay)| *stay == header)
+                .map(|(condition, _)| (latch, condition))
+        }
+        _ => None,
+    };
+
+    let mut exits = LoopExits {
+        natural_loop,
+        follow,
+        bottom_test: None,
+    };
+    if let Some((condition, body_start)) = exit_test(header).filter(|_| is_condition_only) {
+        // the header is drawn as the condition, so its label goes in front of the loop
+        if context.drawn.insert(header) && scope.labels.contains(&header) {
+            ast_block.push(AstStatement::Label(header));
+        }
+        let mut body = build_block(scope, context, body_start, sese, Some(exits));
+        // the end of the body goes on with the next iteration anyway
+        if matches!(body.last(), Some(AstStatement::Continue)) {
+            body.pop();
+        }
+        // a `for` steps the counter in its header, so the body has to end with the step, and a
+        // `goto` to the header would run its initialization again
+        let induction = natural_loop
+            .induction_variables
+            .iter()
+            .find(|v| v.condition.as_ref() == Some(&condition))
+            .filter(|induction| {
+                matches!(body.last(), Some(AstStatement::Assignment { destination, value, .. })
+                    if induction.is_step(destination, value))
+            })
+            .filter(|_| !scope.labels.contains(&header));
+        if let Some(induction) = induction {
+            // the counter is drawn in the `for` header instead
+            body.pop();
+            if matches!(ast_block.last(), Some(s @ AstStatement::Assignment { value, .. })
+                if s.assigns(&induction.variable) && *value == induction.init)
+            {
+                ast_block.pop();
             }
+            ast_block.push(AstStatement::For {
+                sese,
+                condition,
+                body: Box::new(AstStatement::Block(body)),
+                body_address: body_start,
+                induction: induction.clone(),
+            });
+        } else {
+            ast_block.push(AstStatement::While {
+                sese,
+                condition,
+                body: Box::new(AstStatement::Block(body)),
+                body_address: body_start,
+            });
         }
+    } else if let Some((latch, condition)) = bottom_test {
+        exits.bottom_test = Some(latch);
+        let mut body = Vec::new();
+        let branch_block_slot =
+            add_assignments(scope, context, &mut body, header, sese, Some(exits));
+        add_segments(
+            scope,
+            context,
+            &mut body,
+            branch_block_slot,
+            sese,
+            Some(exits),
+        );
+        ast_block.push(AstStatement::DoWhile {
+            sese,
+            condition,
+            body: Box::new(AstStatement::Block(body)),
+            body_address: header,
+        });
+    } else {
+        let mut body = Vec::new();
+        let branch_block_slot =
+            add_assignments(scope, context, &mut body, header, sese, Some(exits));
+        add_segments(
+            scope,
+            context,
+            &mut body,
+            branch_block_slot,
+            sese,
+            Some(exits),
+        );
+        if matches!(body.last(), Some(AstStatement::Continue)) {
+            body.pop();
+        }
+        ast_block.push(AstStatement::While {
+            sese,
+            condition: Expression::from(1),
+            body: Box::new(AstStatement::Block(body)),
+            body_address: header,
+        });
     }
-    ast
+    follow
 }
 
 fn define_all_variables(
@@ -258,18 +636,32 @@ fn define_all_variables(
             }
         }
         ExpressionOp::Value(_)
+        | ExpressionOp::FloatValue(_, _)
         | ExpressionOp::DestinationRegister(_)
         | ExpressionOp::Assign(_, _) => (),
         ExpressionOp::Interrupt(l)
         | ExpressionOp::Overflow(l, _)
         | ExpressionOp::CountOnes(l)
+        | ExpressionOp::SignExtend(l, _, _)
+        | ExpressionOp::ZeroExtend(l, _, _)
+        | ExpressionOp::Truncate(l, _, _)
+        | ExpressionOp::FloatUnary(l, _, _)
+        | ExpressionOp::IntToFloat(l, _, _)
+        | ExpressionOp::FloatToFloat(l, _, _)
+        | ExpressionOp::FloatToInt(l, _)
         | ExpressionOp::Not(l) => {
             define_all_variables(scope, sese, expression, *l);
         }
         ExpressionOp::Multiequals(l, r)
+        | ExpressionOp::FloatBinary(l, r, _, _)
+        | ExpressionOp::FloatCompare(l, r, _, _)
         | ExpressionOp::Add(l, r, _)
         | ExpressionOp::Sub(l, r, _)
         | ExpressionOp::Multiply(l, r, _)
+        | ExpressionOp::Divide(l, r, _, _)
+        | ExpressionOp::Remainder(l, r, _, _)
+        | ExpressionOp::Carry(l, r, _, _)
+        | ExpressionOp::Borrow(l, r, _)
         | ExpressionOp::LessOrEquals(l, r, _)
         | ExpressionOp::Less(l, r, _)
         | ExpressionOp::GreaterOrEquals(l, r, _)
@@ -280,7 +672,9 @@ fn define_all_variables(
         | ExpressionOp::BitShiftLeft(l, r, _)
         | ExpressionOp::And(l, r)
         | ExpressionOp::Xor(l, r)
-        | ExpressionOp::Or(l, r) => {
+        | ExpressionOp::Or(l, r)
+        | ExpressionOp::LogicalAnd(l, r)
+        | ExpressionOp::LogicalOr(l, r) => {
             define_all_variables(scope, sese, expression, *l);
             define_all_variables(scope, sese, expression, *r);
         }
@@ -289,12 +683,13 @@ fn define_all_variables(
 
 fn add_program_segment(
     scope: &mut Scope,
+    context: &mut BuildContext<'_>,
     ast_block: &mut Vec<AstStatement>,
-    hf: &HighFunction,
-    lang: &SleighLanguage,
     sese: SingleEntrySingleExit<BlockSlot>,
     is_force_drop_else_branch: bool,
+    exits: Option<LoopExits>,
 ) {
+    let (hf, lang) = (context.hf, context.lang);
     let branch_block = &hf.composed_blocks[sese.0];
     if let NextBlock::Jump {
         condition,
@@ -302,37 +697,94 @@ fn add_program_segment(
         false_branch,
     } = &branch_block.next
     {
-        let true_branch_slot = hf.composed_blocks.slot_by_destination(true_branch).unwrap();
-        let false_branch_slot = hf
-            .composed_blocks
-            .slot_by_destination(false_branch)
-            .unwrap();
+        let true_branch_slot = hf.composed_blocks.slot_by_destination(true_branch);
+        let false_branch_slot = hf.composed_blocks.slot_by_destination(false_branch);
+        let (Some(true_branch_slot), Some(false_branch_slot)) =
+            (true_branch_slot, false_branch_slot)
+        else {
+            // a side we can't follow, like a jump through a register, is only noted
+            let mut jump_to = |slot: Option<BlockSlot>, destination: &DestinationKind| {
+                let mut statements = Vec::new();
+                match slot {
+                    Some(slot) => add_goto(scope, &mut statements, slot),
+                    None => statements.push(AstStatement::Comment(format!(
+                        "unresolved jump to {destination}"
+                    ))),
+                }
+                AstStatement::Block(statements)
+            };
+            ast_block.push(AstStatement::If {
+                sese,
+                condition: condition.clone(),
+                true_statement: Box::new(jump_to(true_branch_slot, true_branch)),
+                true_branch: true_branch_slot.unwrap_or_default(),
+                else_statement: Box::new(jump_to(false_branch_slot, false_branch)),
+                else_branch: false_branch_slot.unwrap_or_default(),
+            });
+            return;
+        };
+        let (condition, true_branch_slot, false_branch_slot, regions, folded) =
+            if true_branch_slot == sese.0 || false_branch_slot == sese.0 {
+                (
+                    condition.clone(),
+                    true_branch_slot,
+                    false_branch_slot,
+                    vec![sese],
+                    Vec::new(),
+                )
+            } else {
+                short_circuit(
+                    hf,
+                    lang,
+                    sese,
+                    condition,
+                    true_branch_slot,
+                    false_branch_slot,
+                    exits,
+                )
+            };
+        // the folded blocks are drawn as part of the condition
+        context.drawn.extend(folded);
+        let condition = &condition;
+        // branches are built in the innermost merged region that holds them
+        let region_of = |slot: BlockSlot| {
+            *regions
+                .iter()
+                .rev()
+                .find(|region| {
+                    is_ancestor(
+                        hf.cfg.get_node_idx(region.0),
+                        hf.cfg.get_node_idx(slot),
+                        &hf.cfg.dom,
+                    )
+                })
+                .unwrap_or(&sese)
+        };
 
-        let true_branch_distance_to_return =
-            *hf.cfg.distance_to_return.get(&true_branch_slot).unwrap();
-        let false_branch_distance_to_return =
-            *hf.cfg.distance_to_return.get(&false_branch_slot).unwrap();
+        // blocks that never reach the return, like those of an endless loop, have no distance
+        let true_branch_distance_to_return = hf
+            .cfg
+            .distance_to_return
+            .get(&true_branch_slot)
+            .copied()
+            .unwrap_or(u32::MAX);
+        let false_branch_distance_to_return = hf
+            .cfg
+            .distance_to_return
+            .get(&false_branch_slot)
+            .copied()
+            .unwrap_or(u32::MAX);
 
         // (true_branch_distance_to_return == 0 && *true_branch != pts.1) ||
         // (false_branch_distance_to_return == 0  && *false_branch != pts.1) ||
-        let (first_branch, else_branch, is_loop, condition) = if false_branch_slot == sese.1 {
-            if true_branch_slot != sese.0 {
-                // if (expr) { do work or return };
-                (true_branch_slot, None, false, condition.clone())
-            } else {
-                // while (expr) { do work };
-                (true_branch_slot, None, true, condition.clone())
-            }
+        let (first_branch, else_branch, condition) = if false_branch_slot == sese.1 {
+            // if (expr) { do work or return };
+            (true_branch_slot, None, condition.clone())
         } else if true_branch_slot == sese.1 {
             let mut condition = condition.clone();
             condition.not();
-            if false_branch_slot != sese.0 {
-                // if (!expr) {do work or return };
-                (false_branch_slot, None, false, condition)
-            } else {
-                // while (expr) { do work };
-                (false_branch_slot, None, true, condition)
-            }
+            // if (!expr) {do work or return };
+            (false_branch_slot, None, condition)
         } else {
             // full if statement
             if false_branch_distance_to_return == 0 {
@@ -346,7 +798,6 @@ fn add_program_segment(
                     } else {
                         Some(true_branch_slot)
                     },
-                    false,
                     condition.clone(),
                 )
             } else {
@@ -357,7 +808,6 @@ fn add_program_segment(
                     } else {
                         Some(true_branch_slot)
                     },
-                    false,
                     condition.clone(),
                 )
             }
@@ -365,10 +815,19 @@ fn add_program_segment(
 
         //define_all_variables(scope, sese, &condition, condition.get_entry_point());
 
-        let block = build_block(scope, first_branch, hf, lang, sese);
+        let block = build_block(scope, context, first_branch, region_of(first_branch), exits);
         if let Some(else_branch) = else_branch {
-            let false_block = build_block(scope, else_branch, hf, lang, sese);
-            if matches!(false_block.last(), Some(AstStatement::Return { .. })) {
+            let false_block =
+                build_block(scope, context, else_branch, region_of(else_branch), exits);
+            if matches!(
+                false_block.last(),
+                Some(
+                    AstStatement::Return { .. }
+                        | AstStatement::Call { tail: true, .. }
+                        | AstStatement::Break
+                        | AstStatement::Continue
+                )
+            ) {
                 // if it's a return block, we don't need to draw else
                 if block.len() > 0 {
                     // check if the IF body has any AST
@@ -397,13 +856,6 @@ fn add_program_segment(
                     });
                 }
             }
-        } else if is_loop {
-            ast_block.push(AstStatement::Loop {
-                sese,
-                condition: condition,
-                body: Box::new(AstStatement::Block(block)),
-                body_address: first_branch,
-            });
         } else {
             if block.len() > 0 {
                 // check if the IF body has any AST
@@ -418,47 +870,253 @@ fn add_program_segment(
                 });
             }
         }
+    } else if let NextBlock::Switch { selector, cases } = &branch_block.next {
+        let mut targets: Vec<BlockSlot> = Vec::new();
+        let mut bodies: Vec<(Vec<u64>, Box<AstStatement>)> = Vec::new();
+        for (value, destination) in cases {
+            let Some(target) = hf.composed_blocks.slot_by_destination(destination) else {
+                continue;
+            };
+            if target == sese.1 {
+                // case leaves the switch without doing anything
+                continue;
+            }
+            if let Some(idx) = targets.iter().position(|t| *t == target) {
+                bodies[idx].0.push(*value);
+            } else {
+                let body = build_block(scope, context, target, sese, exits);
+                targets.push(target);
+                bodies.push((vec![*value], Box::new(AstStatement::Block(body))));
+            }
+        }
+        ast_block.push(AstStatement::Switch {
+            sese,
+            selector: selector.clone(),
+            cases: bodies,
+        });
     } else {
-        panic!("Unexpected start of a program segment.")
+        // the block doesn't branch, but where it goes on to isn't known, like the return
+        // address of a call that isn't a block of this function
+        ast_block.push(AstStatement::Comment(format!(
+            "unresolved successor of {}",
+            branch_block.identifier
+        )));
     }
     // ast_block.push(super::AstStatement::Block(ast.get_entry_point(), count, hf.pts.root));
 }
 
+/// Fold the condition-only blocks that follow the jump at `sese.0` into its `condition`.
+///
+/// Compilers lower `if (a && b)` to a jump on `a` whose true side is a jump on `b`, both
+/// sharing the false side; `a || b` shares the true side instead. Returns the merged condition,
+/// the blocks it leads to, the regions entered along the chain, outermost first, and the blocks
+/// folded into the condition.
+fn short_circuit(
+    hf: &HighFunction,
+    lang: &SleighLanguage,
+    sese: SingleEntrySingleExit<BlockSlot>,
+    condition: &Expression,
+    true_branch: BlockSlot,
+    false_branch: BlockSlot,
+    exits: Option<LoopExits>,
+) -> (
+    Expression,
+    BlockSlot,
+    BlockSlot,
+    Vec<SingleEntrySingleExit<BlockSlot>>,
+    Vec<BlockSlot>,
+) {
+    let mut condition = condition.clone();
+    let (mut true_branch, mut false_branch) = (true_branch, false_branch);
+    let mut regions = vec![sese];
+    let mut folded = Vec::new();
+    let mut last = sese.0;
+
+    // a jump on its own condition, reached only from `last`
+    let condition_only = |next: BlockSlot, last: BlockSlot| {
+        if next == sese.0 || next == sese.1 || next == hf.cfg.start {
+            return None;
+        }
+        // leaving the loop is a `break` of its own
+        if exits.is_some_and(|e| e.follow == next) {
+            return None;
+        }
+        if hf.cfg.predecessors(next) != [last] {
+            return None;
+        }
+        let NextBlock::Jump {
+            condition,
+            true_branch,
+            false_branch,
+        } = &hf.composed_blocks[next].next
+        else {
+            return None;
+        };
+        let mut statements = Vec::new();
+        let scratch = &mut Scope::new();
+        let context = &mut BuildContext::new(hf, lang);
+        if add_assignments(scratch, context, &mut statements, next, sese, None) != next
+            || !statements.is_empty()
+        {
+            return None;
+        }
+        let true_branch = hf.composed_blocks.slot_by_destination(true_branch)?;
+        let false_branch = hf.composed_blocks.slot_by_destination(false_branch)?;
+        if true_branch == sese.0 || false_branch == sese.0 {
+            return None;
+        }
+        Some((condition, true_branch, false_branch))
+    };
+
+    loop {
+        let merged = [false_branch, true_branch].into_iter().find_map(|next| {
+            let (inner, t, f) = condition_only(next, last)?;
+            let mut inner = inner.clone();
+            let (is_or, taken, fallthrough) = if next == false_branch && t == true_branch {
+                (true, true_branch, f)
+            } else if next == false_branch && f == true_branch {
+                inner.not();
+                (true, true_branch, t)
+            } else if next == true_branch && f == false_branch {
+                (false, t, false_branch)
+            } else if next == true_branch && t == false_branch {
+                inner.not();
+                (false, f, false_branch)
+            } else {
+                return None;
+            };
+            Some((next, inner, is_or, taken, fallthrough))
+        });
+        let Some((next, inner, is_or, taken, fallthrough)) = merged else {
+            break;
+        };
+
+        // a region starting at the merged block must leave where the whole `if` does
+        let innermost = *regions.last().unwrap();
+        if let Some(region) = hf
+            .pts
+            .get_children(innermost)
+            .and_then(|children| children.iter().find(|c| c.0 == next))
+        {
+            if region.1 != sese.1 {
+                break;
+            }
+            regions.push(*region);
+        }
+
+        if is_or {
+            condition.logical_or(&inner);
+        } else {
+            condition.logical_and(&inner);
+        }
+        (true_branch, false_branch) = (taken, fallthrough);
+        folded.push(next);
+        last = next;
+    }
+    (condition, true_branch, false_branch, regions, folded)
+}
+
 fn add_return(
     stmts: &mut Vec<AstStatement>,
     block: &BasicBlock,
     hf: &HighFunction,
-    lang: &SleighLanguage,
     sese: SingleEntrySingleExit<BlockSlot>,
 ) {
-    match hf.calling_convention {
-        CallingConvention::Cdecl => {
-            // Try RAX first (x86-64), fallback to EAX (x86-32)
-            let return_reg = lang
-                .sleigh
-                .get_reg("RAX")
-                .or_else(|| lang.sleigh.get_reg("EAX"))
-                .and_then(|r| r.get_var());
-
-            if let Some(reg_var) = return_reg {
-                if let Some(return_value) = block.registers.get(reg_var) {
-                    stmts.push(AstStatement::Return {
-                        sese,
-                        result: return_value.into_owned(),
-                    });
+    if let Some(reg_var) = hf.return_register {
+        if let Some(return_value) = block.registers.get(reg_var) {
+            stmts.push(AstStatement::Return {
+                sese,
+                result: return_value.into_owned(),
+            });
+        }
+    }
+}
+
+/// Add the statements for going on at `slot` from another block: `break` or `continue` when
+/// that leaves the body of the loop in `exits`, nothing when `slot` starts another loop, a
+/// `goto` when it starts an enclosing one, otherwise its assignments.
+fn add_successor(
+    scope: &mut Scope,
+    context: &mut BuildContext<'_>,
+    stmts: &mut Vec<AstStatement>,
+    slot: BlockSlot,
+    sese: SingleEntrySingleExit<BlockSlot>,
+    exits: Option<LoopExits>,
+) -> BlockSlot {
+    let hf = context.hf;
+    if exits.is_some_and(|e| e.follow == slot) {
+        stmts.push(AstStatement::Break);
+        return hf.cfg.single_end();
+    }
+    if slot == sese.1 {
+        return slot;
+    }
+    if hf.loops.iter().any(|l| l.header == slot) {
+        match exits {
+            Some(e) if e.natural_loop.header == slot => {
+                // a `do while` can't skip its test at the end
+                if e.bottom_test.is_none() {
+                    stmts.push(AstStatement::Continue);
                 }
+                return hf.cfg.single_end();
             }
+            Some(e) if !e.natural_loop.body.contains(&slot) => {
+                add_goto(scope, stmts, slot);
+                return hf.cfg.single_end();
+            }
+            _ => return slot,
         }
     }
+    add_assignments(scope, context, stmts, slot, sese, exits)
+}
+
+fn add_goto(scope: &mut Scope, stmts: &mut Vec<AstStatement>, slot: BlockSlot) {
+    scope.labels.insert(slot);
+    stmts.push(AstStatement::Goto(slot));
+}
+
+/// Add the statements of the block at `block_slot`, and of the blocks it goes on to, up to
+/// the next branch, which is returned.
+///
+/// Going back to a block whose drawing isn't done makes a cycle that isn't a natural loop, like
+/// one entered in two places, which is drawn as a `goto`.
+fn add_assignments(
+    scope: &mut Scope,
+    context: &mut BuildContext<'_>,
+    stmts: &mut Vec<AstStatement>,
+    block_slot: BlockSlot,
+    sese: SingleEntrySingleExit<BlockSlot>,
+    exits: Option<LoopExits>,
+) -> BlockSlot {
+    let hf = context.hf;
+    if block_slot == sese.1 {
+        return block_slot;
+    }
+    if context.unfinished.contains(&block_slot) {
+        add_goto(scope, stmts, block_slot);
+        return hf.cfg.single_end();
+    }
+    // a block drawn more than once gets its label the first time
+    let is_labeled = context.drawn.insert(block_slot) && scope.labels.contains(&block_slot);
+    context.unfinished.insert(block_slot);
+    let first = stmts.len();
+    let next = add_block_statements(scope, context, stmts, block_slot, sese, exits);
+    if is_labeled {
+        stmts.insert(first, AstStatement::Label(block_slot));
+    }
+    context.unfinished.remove(&block_slot);
+    next
 }
 
-fn add_assignments<'a>(
+fn add_block_statements(
+    scope: &mut Scope,
+    context: &mut BuildContext<'_>,
     stmts: &mut Vec<AstStatement>,
     block_slot: BlockSlot,
-    hf: &'a HighFunction,
-    lang: &SleighLanguage,
     sese: SingleEntrySingleExit<BlockSlot>,
+    exits: Option<LoopExits>,
 ) -> BlockSlot {
+    let (hf, lang) = (context.hf, context.lang);
     if block_slot != sese.1 {
         let block = &hf.composed_blocks[block_slot];
 
@@ -485,14 +1143,8 @@ fn add_assignments<'a>(
         // If no memory writes but important registers were modified (e.g., return register),
         // generate assignments for those to make optimized code visible in decompilation
         if stmts.is_empty() && !matches!(block.next, NextBlock::Return { .. }) {
-            // Check if RAX/EAX (return register) was modified
-            let return_reg = lang
-                .sleigh
-                .get_reg("RAX")
-                .or_else(|| lang.sleigh.get_reg("EAX"))
-                .and_then(|r| r.get_var());
-
-            if let Some(reg_var) = return_reg {
+            // Check if the return register was modified
+            if let Some(reg_var) = hf.calling_convention.return_register(lang) {
                 if let Some(reg_value) = block.registers.get(reg_var) {
                     // Only add assignment if the value is not just the register itself
                     // (i.e., the register was actually modified, not just passed through)
@@ -520,24 +1172,41 @@ fn add_assignments<'a>(
                 default_return,
             } => {
                 add_call(stmts, block, hf, lang, destination, *origin, sese);
-                if let Some(next_block) = hf.composed_blocks.slot_by_address(*default_return) {
-                    add_assignments(stmts, next_block, hf, lang, sese)
-                } else {
-                    block_slot
+                match default_return.map(|r| hf.composed_blocks.slot_by_address(r)) {
+                    Some(Some(next_block)) => {
+                        add_successor(scope, context, stmts, next_block, sese, exits)
+                    }
+                    Some(None) => block_slot,
+                    // the callee never returns, the call ends the function
+                    None => hf.cfg.single_end(),
                 }
             }
             NextBlock::Return { .. } => {
-                add_return(stmts, block, hf, lang, sese);
+                add_return(stmts, block, hf, sese);
                 hf.cfg.single_end()
             }
-            NextBlock::Follow(dst) => add_assignments(
-                stmts,
-                hf.composed_blocks.slot_by_destination(dst).unwrap(),
-                hf,
-                lang,
-                sese,
-            ),
-            NextBlock::Jump { .. } => block_slot,
+            NextBlock::TailCall {
+                origin,
+                destination,
+            } => {
+                add_call(stmts, block, hf, lang, destination, *origin, sese);
+                hf.cfg.single_end()
+            }
+            NextBlock::Follow(dst) => match hf.composed_blocks.slot_by_destination(dst) {
+                Some(next_block) => add_successor(scope, context, stmts, next_block, sese, exits),
+                None => block_slot,
+            },
+            // a jump that always goes the same way, like `jmp`
+            next @ NextBlock::Jump { .. } if next.constant_branch().is_some() => {
+                match hf
+                    .composed_blocks
+                    .slot_by_destination(next.constant_branch().unwrap())
+                {
+                    Some(taken) => add_successor(scope, context, stmts, taken, sese, exits),
+                    None => block_slot,
+                }
+            }
+            NextBlock::Jump { .. } | NextBlock::Switch { .. } => block_slot,
         }
     } else {
         block_slot
@@ -553,60 +1222,18 @@ fn add_call(
     call_from: Address,
     sese: SingleEntrySingleExit<BlockSlot>,
 ) {
-    let mut params = Vec::new();
-
-    // Check if this is x86-64 (64-bit) by checking if RDI register exists
-    let is_x64 = lang.sleigh.get_reg("RDI").is_some();
-
-    if is_x64 {
-        // x86-64 System V ABI: first 6 integer/pointer params in registers
-        let param_regs = ["RDI", "RSI", "RDX", "RCX", "R8", "R9"];
-
-        for reg_name in &param_regs {
-            if let Some(reg_var) = lang.sleigh.get_reg(reg_name).and_then(|r| r.get_var()) {
-                if let Some(state) = block.registers.get(reg_var) {
-                    // Skip if it's just the register's symbolic value (uninitialized)
-                    if let Some(ExpressionOp::Variable(VariableSymbol::Varnode(r))) =
-                        state.root_op()
-                    {
-                        if *r == reg_var {
-                            break; // Stop at first uninitialized parameter register
-                        }
-                    }
-                    params.push(state.into_owned());
-                } else {
-                    break; // Stop if register state not found
-                }
-            }
-        }
-
-        // TODO: Also check stack for additional parameters beyond first 6
-    } else {
-        // x86-32 (32-bit): parameters on stack
-        if let Some(stack) = block.registers.get(lang.sp) {
-            let mut param_addr = stack.into_owned();
-            loop {
-                param_addr.add_value(4, InstructionSize::U32);
-
-                if let Some(state) = block.get_memory_state_or_none(&param_addr) {
-                    if let Some(ExpressionOp::Variable(VariableSymbol::Varnode(_))) =
-                        state.root_op()
-                    {
-                        break;
-                    } else {
-                        params.push(state.clone())
-                    }
-                } else {
-                    break;
-                }
-            }
-        }
-    }
+    let params = match hf.callee_parameters.get(destination) {
+        Some(parameters) => hf
+            .calling_convention
+            .passed_arguments(block, parameters, lang),
+        None => hf.calling_convention.call_arguments(block, lang),
+    };
 
     stmts.push(AstStatement::Call {
         destination: destination.clone(),
         params,
         call_from,
+        tail: matches!(block.next, NextBlock::TailCall { .. }),
         sese,
     });
 }
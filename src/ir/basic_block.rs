@@ -21,7 +21,8 @@ use pcode::VarNode;
 use smallvec::SmallVec;
 
 use super::{
-    expression::FormatWithSleighLanguage, Address, Expression, ExpressionOp, VariableSymbol,
+    expression::{as_signed, FormatWithSleighLanguage, InstructionSize},
+    Address, Expression, ExpressionOp, VariableSymbol,
 };
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -212,6 +213,30 @@ impl BlockStorage {
         id
     }
 
+    /// Replace the block at `slot` with `head`, and insert `tail` as a new block.
+    ///
+    /// Panics unless `head` and `tail` are physical blocks that together cover the block at `slot`.
+    pub fn split(&mut self, slot: BlockSlot, head: BasicBlock, tail: BasicBlock) -> BlockSlot {
+        let (
+            BlockIdentifier::Physical(interval),
+            BlockIdentifier::Physical(head_interval),
+            BlockIdentifier::Physical(tail_interval),
+        ) = (self[slot].identifier, head.identifier, tail.identifier)
+        else {
+            panic!("Only physical blocks can be split");
+        };
+        assert!(
+            head_interval.start() == interval.start()
+                && head_interval.end().up() == Some(tail_interval.start())
+                && tail_interval.end() == interval.end(),
+            "Split of {interval:?} doesn't cover it: {head_interval:?} and {tail_interval:?}"
+        );
+        _ = self.address_map.remove_overlapping(interval);
+        self.address_map.insert_strict(head_interval, slot).unwrap();
+        self[slot] = head;
+        self.insert(tail)
+    }
+
     /// Iterate over every block in a function, starting from this one.
     pub fn iter_function<'i>(&'i self, start: BlockSlot) -> BlockFunctionIterator<'i> {
         BlockFunctionIterator::new(self, start)
@@ -301,7 +326,18 @@ pub enum NextBlock {
     Call {
         origin: Address,
         destination: DestinationKind,
-        default_return: Address,
+        /// Instruction after the call, `None` once the callee is known to never return
+        default_return: Option<Address>,
+    },
+    /// Jump to another function, which returns straight to our caller.
+    TailCall {
+        origin: Address,
+        destination: DestinationKind,
+    },
+    /// Indirect jump through a jump table, to the destination of the case equal to `selector`.
+    Switch {
+        selector: Expression,
+        cases: Vec<(u64, DestinationKind)>,
     },
     Return,
 }
@@ -312,6 +348,24 @@ impl Default for NextBlock {
     }
 }
 
+impl NextBlock {
+    /// The only branch a jump on a constant condition can take, like the target of `jmp`.
+    pub fn constant_branch(&self) -> Option<&DestinationKind> {
+        match self {
+            NextBlock::Jump {
+                condition,
+                true_branch,
+                false_branch,
+            } => match condition.root_op()? {
+                ExpressionOp::Value(0) => Some(false_branch),
+                ExpressionOp::Value(_) => Some(true_branch),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
 pub enum SpannedItem<T> {
     Item(T, u8),
@@ -397,6 +451,76 @@ impl<T> SpannedStorage<T> {
     }
 }
 
+/// Read `size` bytes starting `offset` bytes into `expression`, which is `stored_size` bytes wide.
+fn read_inner_bytes(expression: &Expression, stored_size: u8, offset: u8, size: u8) -> Expression {
+    if let (1, Some(ExpressionOp::Value(v))) = (expression.len(), expression.root_op()) {
+        let size: InstructionSize = size.into();
+        return Expression::from(size.wrap(v.checked_shr(offset as u32 * 8).unwrap_or(0)));
+    }
+    let mut output = expression.clone();
+    if offset > 0 {
+        output.bit_shift_right(offset as u64 * 8, stored_size);
+    }
+    if stored_size > offset + size {
+        output.truncate(stored_size, size);
+    }
+    output
+}
+
+/// Split `addr` into a base and a constant offset, `ESP + 0x5` into `ESP` and `5`. Concrete
+/// addresses have no base.
+fn split_address(addr: &Expression) -> (Option<Expression>, i64) {
+    match (addr.len(), addr.root_op()) {
+        (1, Some(ExpressionOp::Value(v))) => (None, *v as i64),
+        (_, Some(ExpressionOp::Add(base, offset, size))) => match addr.get(*offset) {
+            ExpressionOp::Value(v) if offset.as_idx() + 1 == addr.len() - 1 => {
+                (Some(addr.get_sub_expression(*base)), as_signed(*v, *size))
+            }
+            _ => (Some(addr.clone()), 0),
+        },
+        _ => (Some(addr.clone()), 0),
+    }
+}
+
+/// Bytes `b` starts after `a`, if both are relative to the same base.
+fn relative_offset(a: &Expression, b: &Expression) -> Option<i64> {
+    let (a_base, a_offset) = split_address(a);
+    let (b_base, b_offset) = split_address(b);
+    (a_base == b_base).then(|| b_offset.wrapping_sub(a_offset))
+}
+
+/// Whether the bytes at `a` and `b` can't be the same, because one is a concrete address and the
+/// other relative to the stack pointer `sp`. Pointers in any other register may point anywhere.
+fn provably_distinct(a: &Expression, b: &Expression, sp: VarNode) -> bool {
+    let is_stack = |base: &Option<Expression>| {
+        matches!(
+            base.as_ref().map(|base| (base.len(), base.root_op())),
+            Some((1, Some(ExpressionOp::Variable(VariableSymbol::Varnode(r))))) if *r == sp
+        )
+    };
+    let (a_base, _) = split_address(a);
+    let (b_base, _) = split_address(b);
+    (a_base.is_none() && is_stack(&b_base)) || (is_stack(&a_base) && b_base.is_none())
+}
+
+/// `addr` moved by `offset` bytes.
+fn address_at(addr: &Expression, offset: i64) -> Expression {
+    let mut output = addr.clone();
+    let size = addr
+        .size_of(addr.get_entry_point())
+        .unwrap_or(InstructionSize::U64);
+    output.add_value(offset as u64, size);
+    output
+}
+
+/// Whether `value` is the value memory had at `addr` before the block, cached by reading it.
+fn is_memory_read(addr: &Expression, value: &Expression) -> bool {
+    matches!(
+        value.root_op(),
+        Some(ExpressionOp::Variable(VariableSymbol::Ram(a, _))) if **a == *addr
+    )
+}
+
 #[derive(Clone, PartialEq)]
 pub struct CpuState {
     state: HashMap<i16, SpannedStorage<Expression>>,
@@ -415,16 +539,13 @@ impl CpuState {
             space.insert(var_node, Expression::from(ExpressionOp::var_reg(var_node)));
         }
 
-        let size_to_mask = |size: u8| (1u64.unbounded_shl(size as u32 * 8).wrapping_sub(1));
-
         // What happens when we have data in EAX, but ask for AH, or AX?
         match &space[var_node.offset] {
             SpannedItem::Item(e, size) if var_node.size >= *size => Cow::Borrowed(e), // bigger sizes are padded with 0s which `Expression` can take care of
             SpannedItem::Item(e, size) => {
                 // asking for a smaller expression than stored (e.g. asking for AX from EAX)
                 let mut output = e.clone();
-                let mask = size_to_mask(*size); // just mask upper bytes away
-                output.and(&Expression::from(mask));
+                output.truncate(*size, var_node.size); // keep the lower bytes
                 Cow::Owned(output)
             }
             SpannedItem::ItemAt(a) => {
@@ -432,12 +553,13 @@ impl CpuState {
                 // example: stored EAX, but reading AH
                 //     var_node.offset -\
                 // [(expression, 4), ItemAt(0), ItemAt(0), ItemAt(0), Empty]
-                if let SpannedItem::Item(e, _size) = &space[*a] {
-                    let mask = size_to_mask(var_node.size)
-                        .unbounded_shl(var_node.offset as u32 - *a as u32); // shift mask to align with the offset
-                    let mut output = e.clone();
-                    output.and(&Expression::from(mask));
-                    Cow::Owned(output)
+                if let SpannedItem::Item(e, size) = &space[*a] {
+                    Cow::Owned(read_inner_bytes(
+                        e,
+                        *size,
+                        var_node.offset - *a,
+                        var_node.size,
+                    ))
                 } else {
                     unreachable!()
                 }
@@ -457,18 +579,19 @@ impl CpuState {
     pub fn get<'e>(&'e self, var_node: VarNode) -> Option<Cow<'e, Expression>> {
         let storage = self.state.get(&var_node.id)?;
         match &storage[var_node.offset] {
-            SpannedItem::Item(e, _) => Some(Cow::Borrowed(e)),
+            SpannedItem::Item(e, size) if var_node.size >= *size => Some(Cow::Borrowed(e)),
+            SpannedItem::Item(e, size) => {
+                let mut modified = e.clone();
+                modified.truncate(*size, var_node.size);
+                Some(Cow::Owned(modified))
+            }
             SpannedItem::ItemAt(a) => match &storage[*a] {
-                SpannedItem::Item(e, _) => {
-                    let size_to_mask =
-                        |size: u8| (1u64.unbounded_shl(size as u32 * 8).wrapping_sub(1));
-
-                    let mask = size_to_mask(var_node.size) << (var_node.offset * 8);
-
-                    let mut modified = e.clone();
-                    modified.and(&Expression::from(mask));
-                    Some(Cow::Owned(modified))
-                }
+                SpannedItem::Item(e, size) => Some(Cow::Owned(read_inner_bytes(
+                    e,
+                    *size,
+                    var_node.offset - *a,
+                    var_node.size,
+                ))),
                 i => panic!("Malformed CpuState storageL Item({a}) points to {i:?}"),
             },
             _ => None,
@@ -490,6 +613,102 @@ impl CpuState {
         let space = self.state.entry(var_node.id).or_default();
         space.insert(var_node, expression);
     }
+
+    /// Join of two states reaching the same block. Registers that differ become
+    /// [`ExpressionOp::Multiequals`], a register set on only one side is merged with its
+    /// symbolic value.
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut merged = CpuState::new();
+        for (ours, theirs) in [(self, other), (other, self)] {
+            for (id, storage) in ours.state.iter() {
+                for offset in 0..storage.0.len() as u8 {
+                    let SpannedItem::Item(e, size) = &storage[offset] else {
+                        continue;
+                    };
+                    let var_node = VarNode {
+                        id: *id,
+                        offset,
+                        size: *size,
+                    };
+                    // the first pass already merged whatever overlaps this register
+                    let is_merged = merged.state.get(id).is_some_and(|space| {
+                        (offset..offset + size).any(|o| !matches!(space[o], SpannedItem::Empty))
+                    });
+                    if is_merged {
+                        continue;
+                    }
+                    let other_value = theirs
+                        .get(var_node)
+                        .map(Cow::into_owned)
+                        .unwrap_or_else(|| Expression::from(ExpressionOp::var_reg(var_node)));
+                    merged.set_state(var_node, join_values(e, &other_value));
+                }
+            }
+        }
+        merged
+    }
+
+    /// Registers whose value in `later` differs from ours, e.g. the registers a loop body changes.
+    pub fn changed_registers(&self, later: &Self) -> Vec<VarNode> {
+        let mut changed = Vec::new();
+        for (id, storage) in later.state.iter() {
+            for offset in 0..storage.0.len() as u8 {
+                let SpannedItem::Item(value, size) = &storage[offset] else {
+                    continue;
+                };
+                let var_node = VarNode {
+                    id: *id,
+                    offset,
+                    size: *size,
+                };
+                if var_node.is_temp() {
+                    continue;
+                }
+                let before = self
+                    .get(var_node)
+                    .map(Cow::into_owned)
+                    .unwrap_or_else(|| Expression::from(ExpressionOp::var_reg(var_node)));
+                if &before != value {
+                    changed.push(var_node);
+                }
+            }
+        }
+        changed
+    }
+}
+
+/// Collect the distinct values a (possibly nested) [`ExpressionOp::Multiequals`] can take.
+fn alternatives(expression: &Expression, output: &mut Vec<Expression>) {
+    match expression.root_op() {
+        Some(ExpressionOp::Multiequals(l, r)) => {
+            alternatives(&expression.get_sub_expression(*l), output);
+            alternatives(&expression.get_sub_expression(*r), output);
+        }
+        _ => {
+            if !output.contains(expression) {
+                output.push(expression.clone());
+            }
+        }
+    }
+}
+
+/// `Multiequals` of every distinct value of `a` and `b`, or just `a` when they're equal.
+///
+/// Joining a value that's already one of the alternatives doesn't change the result, so
+/// repeated joins don't nest.
+pub fn join_values(a: &Expression, b: &Expression) -> Expression {
+    if a == b {
+        return a.clone();
+    }
+    let mut values = Vec::new();
+    alternatives(a, &mut values);
+    alternatives(b, &mut values);
+    let mut values = values.into_iter();
+    let mut output = values.next().unwrap();
+    for value in values {
+        output.multiequals(&value);
+    }
+    output
 }
 
 /// Since PCode instructions may branch inside a single CPU instruction - some basic blocks
@@ -555,6 +774,8 @@ pub struct BasicBlock {
     pub registers: CpuState,
     /// Symbolic state of memory at the end of this block
     pub memory: HashMap<Expression, Expression>,
+    /// Width in bytes of the values in [`Self::memory`], by address. Constants don't carry their own.
+    pub memory_sizes: HashMap<Expression, u8>,
     /// Changes to memory that this block is responsible for. This is a set of addresses that [`Self::memory`] can be indexed by.
     pub memory_writes: HashSet<Expression>,
     /// Key instructions that contributed to this block (e.g. memory writes, calls, jumps)
@@ -566,6 +787,7 @@ impl BasicBlock {
         Self {
             registers: CpuState::new(),
             memory: HashMap::new(),
+            memory_sizes: HashMap::new(),
             identifier: BlockIdentifier::default(),
             next: NextBlock::default(),
             memory_writes: HashSet::new(),
@@ -578,17 +800,20 @@ impl BasicBlock {
         self.registers.len() == 0 && self.memory.len() == 0
     }
 
+    /// Value of `size` bytes at `addr`, read from memory unless the block wrote them.
     pub fn get_memory_state<'a, E: Into<Expression>>(
         &'a mut self,
         addr: E,
         size: u8,
     ) -> Cow<'a, Expression> {
         let addr = addr.into();
-        Cow::Borrowed(
-            self.memory
-                .entry(addr.clone())
-                .or_insert(Expression::from(VariableSymbol::Ram(Box::new(addr), size))),
-        )
+        if let Some(value) = self.read_memory(&addr, size) {
+            return Cow::Owned(value);
+        }
+        let value = Expression::from(VariableSymbol::Ram(Box::new(addr.clone()), size));
+        self.memory_sizes.insert(addr.clone(), size);
+        self.memory.insert(addr, value.clone());
+        Cow::Owned(value)
     }
 
     pub fn get_memory_state_or_none<'e, E: Into<&'e Expression>>(
@@ -600,12 +825,223 @@ impl BasicBlock {
     }
 
     pub fn set_memory_state<E: Into<Expression>>(&mut self, addr: E, state: E) {
-        self.memory.insert(addr.into(), state.into());
+        let addr = addr.into();
+        self.memory_sizes.remove(&addr);
+        self.memory.insert(addr, state.into());
+    }
+
+    /// Width in bytes of the value stored at `addr`.
+    fn memory_size(&self, addr: &Expression, value: &Expression) -> Option<u8> {
+        self.memory_sizes.get(addr).copied().or_else(|| {
+            value
+                .size_of(value.get_entry_point())
+                .map(|size| (size.bits() / 8) as u8)
+        })
+    }
+
+    /// Value of the `size` bytes at `addr` the block knows, taken from the values written at and
+    /// around it.
+    ///
+    /// A value covering the bytes is cut down to them, e.g. the second byte of a 4-byte write for
+    /// a 1-byte read right after its start. Values covering only part of them are combined, with
+    /// the bytes between them read from memory. Only addresses relative to the same base, or two
+    /// concrete addresses, are known to overlap.
+    ///
+    /// # Returns
+    /// `None` if no written value overlaps the bytes.
+    pub fn read_memory(&self, addr: &Expression, size: u8) -> Option<Expression> {
+        if let Some(value) = self.memory.get(addr) {
+            match self.memory_size(addr, value) {
+                None => return Some(value.clone()),
+                Some(stored) if stored >= size => {
+                    return Some(read_inner_bytes(value, stored, 0, size))
+                }
+                Some(_) => (),
+            }
+        }
+        let end = size as i64;
+        let pieces: Vec<(i64, u8, &Expression)> = self
+            .memory
+            .iter()
+            .filter(|(key, value)| !is_memory_read(key, value))
+            .filter_map(|(key, value)| {
+                let offset = relative_offset(addr, key)?;
+                let stored = self.memory_size(key, value)?;
+                (offset < end && offset + stored as i64 > 0).then_some((offset, stored, value))
+            })
+            .collect();
+        if pieces.is_empty() {
+            return None;
+        }
+
+        let mut combined: Option<Expression> = None;
+        let mut position = 0;
+        while position < end {
+            let covering = pieces.iter().find(|(offset, stored, _)| {
+                *offset <= position && position < offset + *stored as i64
+            });
+            let (mut piece, length) = match covering {
+                Some((offset, stored, value)) => {
+                    let length = (offset + *stored as i64).min(end) - position;
+                    let inner = (position - offset) as u8;
+                    (
+                        read_inner_bytes(value, *stored, inner, length as u8),
+                        length,
+                    )
+                }
+                None => {
+                    let next = pieces
+                        .iter()
+                        .map(|(offset, _, _)| *offset)
+                        .filter(|offset| *offset > position)
+                        .min()
+                        .map_or(end, |next| next.min(end));
+                    let gap = Box::new(address_at(addr, position));
+                    let length = next - position;
+                    (
+                        Expression::from(VariableSymbol::Ram(gap, length as u8)),
+                        length,
+                    )
+                }
+            };
+            if length == end {
+                return Some(piece);
+            }
+            piece.zero_extend(length as u8, size);
+            if position > 0 {
+                match piece.root_op() {
+                    Some(ExpressionOp::Value(v)) if piece.len() == 1 => {
+                        let size: InstructionSize = size.into();
+                        piece = Expression::from(size.wrap(v << (position * 8)));
+                    }
+                    _ => piece.bit_shift_left(position as u64 * 8, size),
+                }
+            }
+            combined = Some(match combined {
+                Some(mut combined) => {
+                    combined.or(&piece);
+                    combined
+                }
+                None => piece,
+            });
+            position += length;
+        }
+        combined
+    }
+
+    /// Store `value`, `size` bytes wide, at `addr`.
+    ///
+    /// Written values it partly overwrites keep their other bytes. Values it may overwrite through
+    /// an unrelated pointer are dropped, written or read, as they may be stale now. Only the stack,
+    /// relative to `sp`, and concrete addresses are known not to overlap.
+    pub fn write_memory(&mut self, addr: Expression, value: Expression, size: u8, sp: VarNode) {
+        let keys: Vec<Expression> = self.memory.keys().cloned().collect();
+        for key in keys {
+            let stored_value = &self.memory[&key];
+            let read = is_memory_read(&key, stored_value);
+            let Some(offset) = relative_offset(&addr, &key) else {
+                if !provably_distinct(&addr, &key, sp) {
+                    self.memory.remove(&key);
+                    self.memory_sizes.remove(&key);
+                    self.memory_writes.remove(&key);
+                }
+                continue;
+            };
+            let stored = self.memory_size(&key, stored_value);
+            let overlaps = match stored {
+                Some(stored) => offset < size as i64 && offset + stored as i64 > 0,
+                None => offset == 0,
+            };
+            if !overlaps {
+                continue;
+            }
+            let stored_value = self.memory.remove(&key).unwrap();
+            self.memory_sizes.remove(&key);
+            let was_written = self.memory_writes.remove(&key);
+            let Some(stored) = stored.filter(|_| !read) else {
+                continue;
+            };
+            // bytes before and after the new value
+            let end = offset + stored as i64;
+            let below = (offset < 0).then(|| (key.clone(), 0, -offset));
+            let above = (end > size as i64).then(|| {
+                let above = address_at(&addr, size as i64);
+                (above, size as i64 - offset, end - size as i64)
+            });
+            for (at, inner, length) in below.into_iter().chain(above) {
+                let part = read_inner_bytes(&stored_value, stored, inner as u8, length as u8);
+                if was_written {
+                    self.memory_writes.insert(at.clone());
+                }
+                self.memory_sizes.insert(at.clone(), length as u8);
+                self.memory.insert(at, part);
+            }
+        }
+        self.memory_sizes.insert(addr.clone(), size);
+        self.memory.insert(addr, value);
     }
+
     // pub fn get_interval(&self) -> Interval<Address> {
     //     ie(self.address, self.end)
     // }
 
+    /// Join the register and memory state of `other` into ours, for two paths reaching the same block.
+    ///
+    /// Memory written on only one side is merged with its symbolic value. Its size is taken from
+    /// the written value, and the write is kept as is when the size isn't known.
+    pub fn merge_state(&self, other: &Self) -> Self {
+        let mut memory = HashMap::new();
+        for (ours, theirs) in [(self, other), (other, self)] {
+            for (addr, value) in &ours.memory {
+                if memory.contains_key(addr) {
+                    continue;
+                }
+                let merged = match theirs.memory.get(addr) {
+                    Some(other_value) => join_values(value, other_value),
+                    None => match value.size_of(value.get_entry_point()) {
+                        Some(size) => join_values(
+                            value,
+                            &Expression::from(VariableSymbol::Ram(
+                                Box::new(addr.clone()),
+                                (size.bits() / 8) as u8,
+                            )),
+                        ),
+                        None => value.clone(),
+                    },
+                };
+                memory.insert(addr.clone(), merged);
+            }
+        }
+
+        let mut memory_sizes = other.memory_sizes.clone();
+        memory_sizes.extend(self.memory_sizes.iter().map(|(a, s)| (a.clone(), *s)));
+        memory_sizes.retain(|addr, _| memory.contains_key(addr));
+
+        Self {
+            registers: self.registers.merge(&other.registers),
+            memory,
+            memory_sizes,
+            ..self.clone()
+        }
+    }
+
+    /// Memory addresses whose value in `later` differs from ours.
+    pub fn changed_memory(&self, later: &Self) -> Vec<Expression> {
+        later
+            .memory
+            .iter()
+            .filter(|(addr, value)| match self.memory.get(*addr) {
+                Some(before) => before != *value,
+                // reading memory stores its symbolic value, which isn't a change
+                None => !matches!(
+                    value.root_op(),
+                    Some(ExpressionOp::Variable(VariableSymbol::Ram(a, _))) if **a == **addr
+                ),
+            })
+            .map(|(addr, _)| addr.clone())
+            .collect()
+    }
+
     pub fn clear_temporary_registers(&mut self) {
         self.registers
             .state
@@ -620,45 +1056,68 @@ impl BasicBlock {
     }
 
     /// "execute" this block right after `other` - inheriting `other`'s state and modifying our own state as if the execution continued.
-    pub fn inherit_state_from(&self, other: &Self) -> Self {
+    ///
+    /// `sp` is the stack pointer, see [`Self::write_memory`].
+    pub fn inherit_state_from(&self, other: &Self, sp: VarNode) -> Self {
         let mut registers = other.registers.clone();
-        let mut memory = other.memory.clone();
 
         fn replace<'a>(
             other: &'a BasicBlock,
         ) -> impl Fn(&VariableSymbol) -> Option<Cow<'a, Expression>> {
             |var: &VariableSymbol| match var {
                 VariableSymbol::Varnode(r) => other.registers.get(*r),
-                VariableSymbol::CallResult { .. } => None,
+                // loop-carried values only exist in composed states, never in a block of its own
+                VariableSymbol::CallResult { .. }
+                | VariableSymbol::Clobbered { .. }
+                | VariableSymbol::LoopCarried { .. } => None,
                 VariableSymbol::Ram(d, size) => {
                     let mut r = d.clone();
                     r.replace_variable_with(replace(other));
-                    if let Some(value) = other.memory.get(&r) {
-                        Some(Cow::Borrowed(value))
-                    } else {
-                        //TODO: Check if symbolic expression with size overlaps this expression with size
-                        let e = Expression::from(VariableSymbol::Ram(r, *size));
-                        Some(Cow::Owned(e))
-                    }
+                    let value = other
+                        .read_memory(&r, *size)
+                        .unwrap_or_else(|| Expression::from(VariableSymbol::Ram(r, *size)));
+                    Some(Cow::Owned(value))
                 }
             }
         }
 
-        let mut memory_writes = HashSet::new();
-
+        // the state of memory after `other`, with our writes applied
+        let mut composed = BasicBlock {
+            memory: other.memory.clone(),
+            memory_sizes: other.memory_sizes.clone(),
+            ..BasicBlock::new()
+        };
+        let mut reads = Vec::new();
         for (addr, value) in &self.memory {
             let is_setter = self.memory_writes.contains(addr);
+            let size = self.memory_size(addr, value);
 
             let mut addr = addr.clone();
             addr.replace_variable_with(replace(other));
-
-            if is_setter {
-                memory_writes.insert(addr.clone());
-            }
-
             let mut value = value.clone();
             value.replace_variable_with(replace(other));
-            memory.insert(addr, value);
+
+            match size {
+                Some(size) if is_setter => {
+                    composed.write_memory(addr.clone(), value, size, sp);
+                    composed.memory_writes.insert(addr);
+                }
+                _ if is_setter => {
+                    composed.set_memory_state(addr.clone(), value);
+                    composed.memory_writes.insert(addr);
+                }
+                // values we read are what `other` left in memory
+                _ => reads.push((addr, value, size)),
+            }
+        }
+        for (addr, value, size) in reads {
+            if size.is_some_and(|size| composed.read_memory(&addr, size).is_some()) {
+                continue;
+            }
+            if let Some(size) = size {
+                composed.memory_sizes.insert(addr.clone(), size);
+            }
+            composed.memory.entry(addr).or_insert(value);
         }
 
         let next = match &self.next {
@@ -710,6 +1169,14 @@ impl BasicBlock {
                     default_return: *default_return,
                 }
             }
+            NextBlock::Switch { selector, cases } => {
+                let mut selector = selector.clone();
+                selector.replace_variable_with(replace(other));
+                NextBlock::Switch {
+                    selector,
+                    cases: cases.clone(),
+                }
+            }
             otherwise => otherwise.clone(),
         };
 
@@ -737,10 +1204,11 @@ impl BasicBlock {
 
         Self {
             identifier: self.identifier,
-            memory_writes,
+            memory_writes: composed.memory_writes,
             next,
             registers,
-            memory,
+            memory: composed.memory,
+            memory_sizes: composed.memory_sizes,
             key_instructions,
         }
     }
@@ -827,6 +1295,8 @@ pub struct BlockNeighborsIterator<'i> {
     blocks: &'i BlockStorage,
     yielded_true_branch: bool,
     yielded_all: bool,
+    /// Next case of a [`NextBlock::Switch`] to yield
+    case: usize,
 }
 
 impl<'i> BlockNeighborsIterator<'i> {
@@ -836,6 +1306,7 @@ impl<'i> BlockNeighborsIterator<'i> {
             start,
             yielded_true_branch: false,
             yielded_all: false,
+            case: 0,
         }
     }
 }
@@ -849,6 +1320,11 @@ impl<'i> Iterator for BlockNeighborsIterator<'i> {
             return None;
         }
         match &self.blocks[self.start].next {
+            next @ Jump { .. } if next.constant_branch().is_some() => {
+                // the other branch is never taken, e.g. the instruction after `jmp`
+                self.yielded_all = true;
+                self.blocks.slot_by_destination(next.constant_branch()?)
+            }
             Jump {
                 true_branch,
                 false_branch,
@@ -870,13 +1346,195 @@ impl<'i> Iterator for BlockNeighborsIterator<'i> {
             }
             Call { default_return, .. } => {
                 self.yielded_all = true;
-                self.blocks.slot_by_address(*default_return)
+                default_return.and_then(|address| self.blocks.slot_by_address(address))
             }
             Follow(next) => {
                 self.yielded_all = true;
                 self.blocks.slot_by_destination(next)
             }
-            Return => None,
+            Switch { cases, .. } => {
+                // several cases can share a destination, yield each one once
+                while let Some((_, destination)) = cases.get(self.case) {
+                    let is_repeated = cases[..self.case].iter().any(|(_, d)| d == destination);
+                    self.case += 1;
+                    if is_repeated {
+                        continue;
+                    }
+                    // a case that can't be resolved to a block doesn't hide the ones after it
+                    if let Some(slot) = self.blocks.slot_by_destination(destination) {
+                        return Some(slot);
+                    }
+                }
+                self.yielded_all = true;
+                None
+            }
+            TailCall { .. } | Return => None,
         }
     }
 }
+
+mod test {
+    use pcode::VarNode;
+
+    use nodit::interval::ie;
+
+    use super::{
+        join_values, read_inner_bytes, BasicBlock, BlockIdentifier, BlockStorage, CpuState,
+        DestinationKind, NextBlock,
+    };
+    use crate::ir::{
+        address::Address,
+        expression::{Expression, ExpressionOp, InstructionSize, VariableSymbol},
+    };
+
+    const EAX: VarNode = VarNode {
+        id: 1,
+        offset: 0,
+        size: 4,
+    };
+
+    const EBX: VarNode = VarNode {
+        id: 2,
+        offset: 0,
+        size: 4,
+    };
+
+    const ESP: VarNode = VarNode {
+        id: 3,
+        offset: 0,
+        size: 4,
+    };
+
+    fn stack(offset: u64) -> Expression {
+        let mut addr = Expression::from(ExpressionOp::var_reg(ESP));
+        addr.add_value(offset, InstructionSize::U32);
+        addr
+    }
+
+    #[test]
+    fn test_merge_diverging_states() {
+        let mut then_state = CpuState::new();
+        then_state.set_state(EAX, Expression::from(1));
+        then_state.set_state(EBX, Expression::from(5));
+        let mut else_state = CpuState::new();
+        else_state.set_state(EAX, Expression::from(2));
+
+        let merged = then_state.merge(&else_state);
+        let eax = merged.get(EAX).unwrap();
+        assert!(matches!(eax.root_op(), Some(ExpressionOp::Multiequals(..))));
+        let mut ebx = Expression::from(5);
+        ebx.multiequals(&Expression::from(ExpressionOp::var_reg(EBX)));
+        assert_eq!(merged.get(EBX).unwrap().into_owned(), ebx);
+
+        // joining a state that's already one of the alternatives changes nothing
+        assert!(merged.merge(&else_state) == merged);
+        assert_eq!(join_values(&eax, &Expression::from(1)), eax.into_owned());
+    }
+
+    #[test]
+    fn test_switch_neighbors_skip_unresolved_cases() {
+        let mut blocks = BlockStorage::new();
+        let physical = |start: u64, end: u64| {
+            let mut block = BasicBlock::new();
+            block.identifier = BlockIdentifier::Physical(ie(Address(start), Address(end)));
+            block
+        };
+        let mut dispatch = physical(0x10, 0x20);
+        dispatch.next = NextBlock::Switch {
+            selector: Expression::from(ExpressionOp::var_reg(EAX)),
+            cases: vec![
+                (
+                    0,
+                    DestinationKind::Symbolic(Expression::from(ExpressionOp::var_reg(EBX))),
+                ),
+                (1, DestinationKind::Concrete(Address(0x20))),
+                (2, DestinationKind::Concrete(Address(0x30))),
+                (3, DestinationKind::Concrete(Address(0x20))),
+            ],
+        };
+        let dispatch = blocks.insert(dispatch);
+        let first = blocks.insert(physical(0x20, 0x30));
+        let second = blocks.insert(physical(0x30, 0x40));
+
+        let neighbors: Vec<_> = blocks.iter_neighbors(dispatch).collect();
+        assert_eq!(neighbors, vec![first, second]);
+    }
+
+    #[test]
+    fn test_overlapping_memory_accesses() {
+        let eax = Expression::from(ExpressionOp::var_reg(EAX));
+        let mut block = BasicBlock::new();
+        block.write_memory(stack(4), eax.clone(), 4, ESP);
+        assert_eq!(
+            block.get_memory_state(stack(5), 1).into_owned(),
+            read_inner_bytes(&eax, 4, 1, 1)
+        );
+
+        block.write_memory(stack(8), Expression::from(0x11223344), 4, ESP);
+        assert_eq!(
+            block.read_memory(&stack(9), 2),
+            Some(Expression::from(0x2233))
+        );
+        // the rest of a partly overwritten value stays
+        block.write_memory(stack(9), Expression::from(0xff), 1, ESP);
+        assert_eq!(
+            block.read_memory(&stack(8), 4),
+            Some(Expression::from(0x1122ff44))
+        );
+        assert_eq!(block.read_memory(&stack(12), 4), None);
+    }
+
+    #[test]
+    fn test_store_through_unrelated_pointer() {
+        let mut block = BasicBlock::new();
+        block.get_memory_state(stack(0), 4);
+        block.write_memory(stack(4), Expression::from(1), 4, ESP);
+        let pointer = Expression::from(ExpressionOp::var_reg(EBX));
+        block.write_memory(pointer, Expression::from(2), 4, ESP);
+
+        // the pointer may point into the stack, so neither value is known anymore
+        assert!(!block.memory.contains_key(&stack(0)));
+        assert_eq!(block.read_memory(&stack(4), 4), None);
+        assert!(!block.memory_writes.contains(&stack(4)));
+    }
+
+    #[test]
+    fn test_store_through_pointer_to_global() {
+        let global = Expression::from(0x404000);
+        let mut block = BasicBlock::new();
+        block.write_memory(global.clone(), Expression::from(1), 4, ESP);
+        block.write_memory(stack(4), Expression::from(2), 4, ESP);
+        // the stack and globals don't overlap
+        assert_eq!(block.read_memory(&global, 4), Some(Expression::from(1)));
+
+        let pointer = Expression::from(ExpressionOp::var_reg(EBX));
+        block.write_memory(pointer, Expression::from(3), 4, ESP);
+        assert_eq!(block.read_memory(&global, 4), None);
+        assert_eq!(
+            block.get_memory_state(global.clone(), 4).into_owned(),
+            Expression::from(VariableSymbol::Ram(Box::new(global), 4))
+        );
+    }
+
+    #[test]
+    fn test_compose_overlapping_read() {
+        let eax = Expression::from(ExpressionOp::var_reg(EAX));
+        let mut first = BasicBlock::new();
+        first.write_memory(stack(4), eax.clone(), 4, ESP);
+
+        let bl = VarNode {
+            id: 2,
+            offset: 0,
+            size: 1,
+        };
+        let mut second = BasicBlock::new();
+        let value = second.get_memory_state(stack(5), 1).into_owned();
+        second.registers.set_state(bl, value);
+
+        let composed = second.inherit_state_from(&first, ESP);
+        assert_eq!(
+            composed.registers.get(bl).unwrap().into_owned(),
+            read_inner_bytes(&eax, 4, 1, 1)
+        );
+    }
+}
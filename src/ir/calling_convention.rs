@@ -0,0 +1,364 @@
+//! # Calling conventions
+//!
+//! A [`ConventionModel`] describes where a call's integer arguments and results live, who removes
+//! the arguments from the stack and which registers survive the call. Models are plain data keyed by
+//! register names, so they can come from the built-in [`CallingConvention`] presets as well as from
+//! processor descriptions.
+//!
+//! Stack offsets are relative to the stack pointer right after the call instruction, i.e. with the
+//! return address on top of the stack. That is the stack pointer of a block ending in
+//! [`NextBlock::Call`](super::basic_block::NextBlock::Call).
+//!
+//! Floating point arguments and results are only modelled in registers, by the low quadword of
+//! the XMM registers. Vector arguments, e.g. the full XMM registers of `__vectorcall`, aren't
+//! tracked.
+
+use std::borrow::Cow;
+
+use pcode::VarNode;
+use sleigh_compile::ldef::SleighLanguage;
+
+use super::{
+    basic_block::BasicBlock,
+    expression::{as_signed, OpIdx},
+    Expression, ExpressionOp, VariableSymbol,
+};
+
+/// Built-in calling conventions of x86 and x86-64.
+///
+/// The 32-bit Microsoft conventions are ignored by 64-bit compilers, so on x86-64 they all use the
+/// [`CallingConvention::MicrosoftX64`] model.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CallingConvention {
+    /// Arguments on the stack, removed by the caller
+    Cdecl,
+    /// Arguments on the stack, removed by the callee. Used by the Win32 API
+    Stdcall,
+    /// First two arguments in `ECX` and `EDX`, the rest like [`CallingConvention::Stdcall`]
+    Fastcall,
+    /// `this` in `ECX`, the rest like [`CallingConvention::Stdcall`]
+    Thiscall,
+    /// Integer arguments like [`CallingConvention::Fastcall`], or like
+    /// [`CallingConvention::MicrosoftX64`] on x86-64
+    Vectorcall,
+    /// First four arguments in `RCX`, `RDX`, `R8` and `R9`, with 32 bytes of shadow space reserved
+    /// above the return address
+    MicrosoftX64,
+    /// First six arguments in `RDI`, `RSI`, `RDX`, `RCX`, `R8` and `R9`
+    SystemV,
+}
+
+impl CallingConvention {
+    /// Convention of code compiled for `lang` when nothing else is known: System V on x86-64,
+    /// cdecl everywhere else.
+    pub fn default_for(lang: &SleighLanguage) -> Self {
+        if is_64_bit(lang) {
+            CallingConvention::SystemV
+        } else {
+            CallingConvention::Cdecl
+        }
+    }
+
+    /// Register and stack layout of this convention on `lang`.
+    pub fn model(self, lang: &SleighLanguage) -> ConventionModel {
+        use CallingConvention::*;
+        let x64 = is_64_bit(lang);
+        match self {
+            Cdecl | Stdcall | Fastcall | Thiscall | Vectorcall | MicrosoftX64 if x64 => {
+                ConventionModel {
+                    name: String::from("__fastcall"),
+                    argument_registers: names(&["RCX", "RDX", "R8", "R9"]),
+                    float_argument_registers: xmm(4),
+                    stack_argument_offset: 40,
+                    stack_slot_size: 8,
+                    return_address_size: 8,
+                    return_registers: names(&["RAX"]),
+                    float_return_registers: xmm(1),
+                    callee_cleanup: false,
+                    preserved_registers: names(&[
+                        "RBX", "RBP", "RDI", "RSI", "RSP", "R12", "R13", "R14", "R15",
+                    ]),
+                    killed_registers: [
+                        killed(&["RAX", "RCX", "RDX", "R8", "R9", "R10", "R11"]),
+                        xmm(6),
+                    ]
+                    .concat(),
+                }
+            }
+            SystemV if x64 => ConventionModel {
+                name: String::from("__stdcall"),
+                argument_registers: names(&["RDI", "RSI", "RDX", "RCX", "R8", "R9"]),
+                float_argument_registers: xmm(8),
+                stack_argument_offset: 8,
+                stack_slot_size: 8,
+                return_address_size: 8,
+                return_registers: names(&["RAX", "RDX"]),
+                float_return_registers: xmm(1),
+                callee_cleanup: false,
+                preserved_registers: names(&["RBX", "RBP", "RSP", "R12", "R13", "R14", "R15"]),
+                killed_registers: [
+                    killed(&["RAX", "RCX", "RDX", "RSI", "RDI", "R8", "R9", "R10", "R11"]),
+                    xmm(16),
+                ]
+                .concat(),
+            },
+            Cdecl | MicrosoftX64 | SystemV => x86_model("__cdecl", &[], false),
+            Stdcall => x86_model("__stdcall", &[], true),
+            Fastcall => x86_model("__fastcall", &["ECX", "EDX"], true),
+            Thiscall => x86_model("__thiscall", &["ECX"], true),
+            Vectorcall => x86_model("__vectorcall", &["ECX", "EDX"], true),
+        }
+    }
+}
+
+/// Where a calling convention passes integer arguments and results.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConventionModel {
+    /// Name of the prototype in Ghidra's compiler specifications, e.g. `__stdcall` for the
+    /// System V convention of `x86-64-gcc.cspec`
+    pub name: String,
+    /// Registers of the leading arguments, in order
+    pub argument_registers: Vec<String>,
+    /// Registers of the leading floating point arguments, in order
+    pub float_argument_registers: Vec<String>,
+    /// Offset of the first stack argument from the stack pointer after the call
+    pub stack_argument_offset: u64,
+    /// Size of every stack argument slot
+    pub stack_slot_size: u64,
+    /// Size of the return address pushed by the call
+    pub return_address_size: u64,
+    /// Registers holding the result, the low part first
+    pub return_registers: Vec<String>,
+    /// Registers holding a floating point result
+    pub float_return_registers: Vec<String>,
+    /// Whether the callee removes its stack arguments when returning
+    pub callee_cleanup: bool,
+    /// Registers that have the same value after the call as before it
+    pub preserved_registers: Vec<String>,
+    /// Registers that may hold any value after the call, including the return registers
+    pub killed_registers: Vec<String>,
+}
+
+impl ConventionModel {
+    /// Register holding the (low part of the) result of a call.
+    pub fn return_register(&self, lang: &SleighLanguage) -> Option<VarNode> {
+        self.return_registers
+            .iter()
+            .find_map(|name| register(lang, name))
+    }
+
+    /// Register holding a floating point result of a call.
+    pub fn float_return_register(&self, lang: &SleighLanguage) -> Option<VarNode> {
+        self.float_return_registers
+            .iter()
+            .find_map(|name| register(lang, name))
+    }
+
+    /// Integer or floating point argument registers, in order.
+    pub fn argument_varnodes(&self, lang: &SleighLanguage, float: bool) -> Vec<VarNode> {
+        let names = if float {
+            &self.float_argument_registers
+        } else {
+            &self.argument_registers
+        };
+        names
+            .iter()
+            .filter_map(|name| register(lang, name))
+            .collect()
+    }
+
+    /// Registers that keep their value across a call.
+    pub fn preserved_varnodes(&self, lang: &SleighLanguage) -> Vec<VarNode> {
+        self.preserved_registers
+            .iter()
+            .filter_map(|name| register(lang, name))
+            .collect()
+    }
+
+    /// Whether the register `var` keeps its value across a call.
+    pub fn is_preserved(&self, lang: &SleighLanguage, var: VarNode) -> bool {
+        self.preserved_varnodes(lang).contains(&var)
+    }
+
+    /// Registers that may hold any value after a call, see [`ConventionModel::killed_registers`].
+    pub fn killed_registers<'l>(
+        &'l self,
+        lang: &'l SleighLanguage,
+    ) -> impl Iterator<Item = VarNode> + 'l {
+        self.killed_registers
+            .iter()
+            .filter_map(|name| register(lang, name))
+    }
+
+    /// Arguments set up by `block` for the call it ends with.
+    ///
+    /// Argument registers are taken in order until one still holds its value from the start of the
+    /// function, floating point registers after the integer ones. Stack arguments are only looked
+    /// for once every integer argument register is used.
+    pub fn call_arguments(&self, block: &BasicBlock, lang: &SleighLanguage) -> Vec<Expression> {
+        let (mut arguments, all_used) = register_arguments(&self.argument_registers, block, lang);
+        arguments.extend(register_arguments(&self.float_argument_registers, block, lang).0);
+        if all_used {
+            arguments.extend(self.stack_arguments(block, lang));
+        }
+        arguments
+    }
+
+    /// Values `block` passes for the `parameters` of the function it calls, as recovered by
+    /// [`HighFunction::parameters`](super::high_function::HighFunction::parameters).
+    pub fn passed_arguments(
+        &self,
+        block: &BasicBlock,
+        parameters: &[VariableSymbol],
+        lang: &SleighLanguage,
+    ) -> Vec<Expression> {
+        let state = |var: VarNode| {
+            block
+                .registers
+                .get(var)
+                .map(Cow::into_owned)
+                .unwrap_or_else(|| Expression::from(ExpressionOp::var_reg(var)))
+        };
+        let stack = state(lang.sp);
+        parameters
+            .iter()
+            .filter_map(|parameter| match parameter {
+                VariableSymbol::Varnode(var) => Some(state(*var)),
+                VariableSymbol::Ram(addr, _) => {
+                    let ExpressionOp::Value(offset) = addr.get(OpIdx::from_idx(1)) else {
+                        return None;
+                    };
+                    let mut slot = stack.clone();
+                    slot.add_value(*offset, lang.sp.size);
+                    Some(match block.get_memory_state_or_none(&slot) {
+                        Some(value) => value.clone(),
+                        None => {
+                            slot.dereference();
+                            slot
+                        }
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Values stored in consecutive stack argument slots of the call `block` ends with.
+    ///
+    /// Stops at the first slot that isn't written or still holds a register from the start of the
+    /// function, e.g. a saved frame pointer.
+    pub fn stack_arguments(&self, block: &BasicBlock, lang: &SleighLanguage) -> Vec<Expression> {
+        let mut arguments = Vec::new();
+        let Some(stack) = block.registers.get(lang.sp) else {
+            return arguments;
+        };
+        let size = lang.sp.size;
+        let mut slot = stack.into_owned();
+        slot.add_value(self.stack_argument_offset, size);
+        while let Some(state) = block.get_memory_state_or_none(&slot) {
+            if let Some(ExpressionOp::Variable(VariableSymbol::Varnode(_))) = state.root_op() {
+                break;
+            }
+            arguments.push(state.clone());
+            slot.add_value(self.stack_slot_size, size);
+        }
+        arguments
+    }
+
+    /// Bytes the stack pointer moves up by when a call with `stack_arguments` stack arguments
+    /// returns.
+    pub fn stack_cleanup(&self, stack_arguments: usize) -> u64 {
+        if self.callee_cleanup {
+            self.return_address_size + stack_arguments as u64 * self.stack_slot_size
+        } else {
+            self.return_address_size
+        }
+    }
+
+    /// Whether `addr`, as read in the function body, is a stack argument of the function, i.e.
+    /// initial stack pointer plus at least [`ConventionModel::stack_argument_offset`].
+    pub fn is_stack_argument(&self, addr: &Expression, lang: &SleighLanguage) -> bool {
+        let (
+            ExpressionOp::Variable(VariableSymbol::Varnode(r)),
+            ExpressionOp::Value(offset),
+            ExpressionOp::Add(_, _, _),
+        ) = (
+            addr.get(OpIdx::from_idx(0)),
+            addr.get(OpIdx::from_idx(1)),
+            addr.get(OpIdx::from_idx(2)),
+        )
+        else {
+            return false;
+        };
+        let offset = as_signed(*offset, lang.sp.size.into());
+        r == &lang.sp && addr.len() == 3 && offset >= self.stack_argument_offset as i64
+    }
+}
+
+/// Values of the registers `names` at the end of `block`, up to the first one that still holds its
+/// value from the start of the function, and whether all of them hold a new value.
+fn register_arguments(
+    names: &[String],
+    block: &BasicBlock,
+    lang: &SleighLanguage,
+) -> (Vec<Expression>, bool) {
+    let mut arguments = Vec::new();
+    for name in names {
+        let Some(var) = register(lang, name) else {
+            continue;
+        };
+        match block.registers.get(var) {
+            Some(state)
+                if !matches!(
+                    state.root_op(),
+                    Some(ExpressionOp::Variable(VariableSymbol::Varnode(r))) if *r == var
+                ) =>
+            {
+                arguments.push(state.into_owned())
+            }
+            _ => return (arguments, false),
+        }
+    }
+    (arguments, true)
+}
+
+fn names(registers: &[&str]) -> Vec<String> {
+    registers.iter().map(|name| name.to_string()).collect()
+}
+
+/// `registers` and the status flags, which no convention preserves.
+fn killed(registers: &[&str]) -> Vec<String> {
+    names(registers)
+        .into_iter()
+        .chain(names(&["CF", "PF", "AF", "ZF", "SF", "OF"]))
+        .collect()
+}
+
+/// Low quadwords of the first `count` XMM registers.
+fn xmm(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("XMM{i}_Qa")).collect()
+}
+
+fn x86_model(name: &str, argument_registers: &[&str], callee_cleanup: bool) -> ConventionModel {
+    ConventionModel {
+        name: String::from(name),
+        argument_registers: names(argument_registers),
+        float_argument_registers: Vec::new(),
+        stack_argument_offset: 4,
+        stack_slot_size: 4,
+        return_address_size: 4,
+        return_registers: names(&["EAX", "EDX"]),
+        float_return_registers: Vec::new(),
+        callee_cleanup,
+        preserved_registers: names(&["EBX", "EBP", "ESI", "EDI", "ESP"]),
+        killed_registers: killed(&["EAX", "ECX", "EDX"]),
+    }
+}
+
+fn is_64_bit(lang: &SleighLanguage) -> bool {
+    lang.sp.size == 8
+}
+
+fn register(lang: &SleighLanguage, name: &str) -> Option<VarNode> {
+    lang.sleigh.get_reg(name).and_then(|r| r.get_var())
+}
@@ -0,0 +1,303 @@
+//! # Compiler specifications
+//!
+//! Ghidra describes the conventions of every compiler targeting a language in a `.cspec` file,
+//! which the language's entry in the `.ldefs` file names:
+//!
+//! ```xml
+//! <language id="x86:LE:64:default" ...>
+//!   <compiler name="gcc" spec="x86-64-gcc.cspec" id="gcc"/>
+//! </language>
+//! ```
+//!
+//! Each `<prototype>` of the spec becomes a [`ConventionModel`]:
+//! * integer `<pentry>`s of `<input>` are the argument registers and the stack slots,
+//! * integer `<pentry>`s of `<output>` are the return registers,
+//! * float `<pentry>`s are the floating point argument and return registers,
+//! * `<unaffected>` lists the preserved registers,
+//! * `<killedbycall>` and `<likelytrash>` list registers a call may change, besides the argument
+//!   and return registers,
+//! * `stackshift` is the size of the return address, and an `extrapop` larger than that (or
+//!   `unknown`) means the callee cleans up its stack arguments.
+
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+use thiserror::Error;
+
+use super::calling_convention::ConventionModel;
+
+#[derive(Error, Debug)]
+pub enum CompilerSpecError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("XML error: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("Malformed spec: {0}")]
+    Malformed(String),
+}
+
+/// Calling conventions of a compiler, parsed from its `.cspec` file.
+#[derive(Clone, Debug)]
+pub struct CompilerSpec {
+    /// Name of the prototype used when nothing else is known about a function
+    pub default_prototype: String,
+    pub prototypes: Vec<ConventionModel>,
+}
+
+impl CompilerSpec {
+    /// Load the spec of compiler `compiler_id` for language `language_id` of the `ldefs` file.
+    pub fn load(
+        ldefs: impl AsRef<Path>,
+        language_id: &str,
+        compiler_id: &str,
+    ) -> Result<Self, CompilerSpecError> {
+        let ldefs = ldefs.as_ref();
+        let text = std::fs::read_to_string(ldefs)?;
+        let doc = Document::parse(&text)?;
+        let language = doc
+            .descendants()
+            .find(|n| n.has_tag_name("language") && n.attribute("id") == Some(language_id))
+            .ok_or_else(|| {
+                CompilerSpecError::Malformed(format!("No language {language_id} in {ldefs:?}"))
+            })?;
+        let spec = language
+            .children()
+            .find(|n| n.has_tag_name("compiler") && n.attribute("id") == Some(compiler_id))
+            .and_then(|n| n.attribute("spec"))
+            .ok_or_else(|| {
+                CompilerSpecError::Malformed(format!(
+                    "No compiler {compiler_id} for language {language_id}"
+                ))
+            })?;
+        let path = ldefs.with_file_name(spec);
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse the contents of a `.cspec` file.
+    pub fn parse(text: &str) -> Result<Self, CompilerSpecError> {
+        let doc = Document::parse(text)?;
+        let spec = doc.root_element();
+        // the stack location of the return address, for prototypes without a `stackshift`
+        let return_address_size = child(spec, "returnaddress")
+            .and_then(|n| child(n, "varnode"))
+            .and_then(|n| number(n, "size"));
+
+        let default_prototype = child(spec, "default_proto")
+            .and_then(|n| child(n, "prototype"))
+            .and_then(|n| n.attribute("name"))
+            .ok_or_else(|| CompilerSpecError::Malformed(String::from("No default prototype")))?
+            .to_string();
+        let prototypes = spec
+            .descendants()
+            .filter(|n| n.has_tag_name("prototype"))
+            .map(|n| prototype(n, return_address_size))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            default_prototype,
+            prototypes,
+        })
+    }
+
+    pub fn prototype(&self, name: &str) -> Option<&ConventionModel> {
+        self.prototypes.iter().find(|p| p.name == name)
+    }
+
+    /// Convention of functions nothing else is known about.
+    pub fn default_model(&self) -> &ConventionModel {
+        self.prototype(&self.default_prototype)
+            .expect("The default prototype is one of the prototypes")
+    }
+}
+
+fn prototype(
+    node: Node,
+    return_address_size: Option<u64>,
+) -> Result<ConventionModel, CompilerSpecError> {
+    let name = node.attribute("name").unwrap_or_default().to_string();
+    let return_address_size = number(node, "stackshift")
+        .or(return_address_size)
+        .ok_or_else(|| CompilerSpecError::Malformed(format!("No stackshift for {name}")))?;
+    let callee_cleanup = match node.attribute("extrapop") {
+        Some("unknown") => true,
+        _ => number(node, "extrapop").is_some_and(|pop| pop > return_address_size),
+    };
+
+    let mut argument_registers = Vec::new();
+    let mut stack_argument_offset = return_address_size;
+    let mut stack_slot_size = return_address_size;
+    for entry in entries(node, "input", false) {
+        if let Some(register) = child(entry, "register").and_then(|n| n.attribute("name")) {
+            argument_registers.push(register.to_string());
+        } else if let Some(addr) =
+            child(entry, "addr").filter(|n| n.attribute("space") == Some("stack"))
+        {
+            stack_argument_offset = number(addr, "offset").unwrap_or(stack_argument_offset);
+            stack_slot_size = number(entry, "align").unwrap_or(stack_slot_size);
+        }
+    }
+    let return_registers = registers(entries(node, "output", false));
+    let float_argument_registers = registers(entries(node, "input", true));
+    let float_return_registers = registers(entries(node, "output", true));
+    let preserved_registers: Vec<String> = listed_registers(node, "unaffected").collect();
+    let mut killed_registers: Vec<String> = Vec::new();
+    for register in listed_registers(node, "killedbycall")
+        .chain(listed_registers(node, "likelytrash"))
+        .chain(argument_registers.iter().cloned())
+        .chain(float_argument_registers.iter().cloned())
+        .chain(return_registers.iter().cloned())
+        .chain(float_return_registers.iter().cloned())
+    {
+        if !preserved_registers.contains(&register) && !killed_registers.contains(&register) {
+            killed_registers.push(register);
+        }
+    }
+
+    Ok(ConventionModel {
+        name,
+        argument_registers,
+        float_argument_registers,
+        stack_argument_offset,
+        stack_slot_size,
+        return_address_size,
+        return_registers,
+        float_return_registers,
+        callee_cleanup,
+        preserved_registers,
+        killed_registers,
+    })
+}
+
+/// Names of the `<register>`s listed in the `list` child of a prototype.
+fn listed_registers<'a>(prototype: Node<'a, '_>, list: &str) -> impl Iterator<Item = String> + 'a {
+    child(prototype, list)
+        .into_iter()
+        .flat_map(|n| n.children())
+        .filter(|n| n.has_tag_name("register"))
+        .filter_map(|n| n.attribute("name"))
+        .map(String::from)
+}
+
+/// `<pentry>`s of the `<input>` or `<output>` of a prototype that hold floating point values, or
+/// the ones that don't.
+fn entries<'a, 'input>(
+    prototype: Node<'a, 'input>,
+    list: &str,
+    float: bool,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    child(prototype, list)
+        .into_iter()
+        .flat_map(|n| n.descendants())
+        .filter(move |n| {
+            n.has_tag_name("pentry") && (n.attribute("metatype") == Some("float")) == float
+        })
+}
+
+/// Names of the registers of `entries`, skipping stack entries.
+fn registers<'a, 'input: 'a>(entries: impl Iterator<Item = Node<'a, 'input>>) -> Vec<String> {
+    entries
+        .filter_map(|entry| child(entry, "register")?.attribute("name"))
+        .map(String::from)
+        .collect()
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+/// Decimal or `0x` prefixed hexadecimal attribute.
+fn number(node: Node, attribute: &str) -> Option<u64> {
+    let value = node.attribute(attribute)?;
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+mod test {
+    use super::CompilerSpec;
+
+    const SPEC: &str = r#"
+    <compiler_spec>
+      <returnaddress>
+        <varnode space="stack" offset="0" size="4"/>
+      </returnaddress>
+      <default_proto>
+        <prototype name="__stdcall" extrapop="unknown" stackshift="4">
+          <input>
+            <pentry minsize="1" maxsize="500" align="4">
+              <addr offset="4" space="stack"/>
+            </pentry>
+          </input>
+          <output>
+            <pentry minsize="4" maxsize="10" metatype="float">
+              <register name="ST0"/>
+            </pentry>
+            <pentry minsize="1" maxsize="4">
+              <register name="EAX"/>
+            </pentry>
+          </output>
+          <unaffected>
+            <varnode space="ram" offset="0" size="4"/>
+            <register name="ESP"/>
+            <register name="EBX"/>
+          </unaffected>
+          <killedbycall>
+            <register name="ECX"/>
+            <register name="EDX"/>
+          </killedbycall>
+        </prototype>
+      </default_proto>
+      <prototype name="__fastcall" extrapop="4">
+        <input>
+          <group>
+            <pentry minsize="1" maxsize="4">
+              <register name="ECX"/>
+            </pentry>
+          </group>
+          <pentry minsize="1" maxsize="4">
+            <register name="EDX"/>
+          </pentry>
+          <pentry minsize="1" maxsize="500" align="0x8">
+            <addr offset="0xc" space="stack"/>
+          </pentry>
+        </input>
+      </prototype>
+    </compiler_spec>"#;
+
+    #[test]
+    fn test_parse_prototypes() {
+        let spec = CompilerSpec::parse(SPEC).unwrap();
+        assert_eq!(spec.prototypes.len(), 2);
+
+        let stdcall = spec.default_model();
+        assert_eq!(stdcall.name, "__stdcall");
+        assert!(stdcall.argument_registers.is_empty());
+        assert_eq!(stdcall.stack_argument_offset, 4);
+        assert_eq!(stdcall.return_registers, vec![String::from("EAX")]);
+        assert_eq!(stdcall.float_return_registers, vec![String::from("ST0")]);
+        assert!(stdcall.float_argument_registers.is_empty());
+        assert_eq!(
+            stdcall.preserved_registers,
+            vec![String::from("ESP"), String::from("EBX")]
+        );
+        assert_eq!(
+            stdcall.killed_registers,
+            ["ECX", "EDX", "EAX", "ST0"].map(String::from)
+        );
+        assert!(stdcall.callee_cleanup);
+
+        // stackshift defaults to the size of the return address
+        let fastcall = spec.prototype("__fastcall").unwrap();
+        assert_eq!(fastcall.return_address_size, 4);
+        assert_eq!(
+            fastcall.argument_registers,
+            vec![String::from("ECX"), String::from("EDX")]
+        );
+        assert_eq!(fastcall.stack_argument_offset, 12);
+        assert_eq!(fastcall.stack_slot_size, 8);
+        assert!(!fastcall.callee_cleanup);
+        // argument registers aren't preserved unless the spec says so
+        assert_eq!(fastcall.killed_registers, fastcall.argument_registers);
+    }
+}
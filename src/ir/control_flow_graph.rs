@@ -32,8 +32,11 @@ pub struct SingleEntrySingleExit<N>(pub N, pub N);
 
 #[derive(EnumSetType, Debug)]
 pub enum CFGProperties {
+    /// No RET block is reachable, and every path ends in a call that never returns or loops
+    /// forever, like halts and traps that jump to themselves. Such calls are linked to the fake
+    /// return block like RET blocks.
     NeverReturns,
-    /// If this CFG has multiple RET blocks, all of them are linked to a fake return block at `Address::NULL`
+    /// If this CFG has multiple RET blocks or tail calls, all of them are linked to a fake return block at `Address::NULL`
     MultipleReturns,
 }
 
@@ -42,6 +45,8 @@ pub enum LinkKind {
     Calls(DestinationKind),
     TrueBranch(Expression),
     FalseBranch(Expression),
+    /// Taken when the switch selector equals one of the case values
+    Case(Expression, Vec<u64>),
     Unconditional,
     Return,
 }
@@ -52,6 +57,9 @@ impl std::fmt::Display for LinkKind {
             LinkKind::Calls(expression) => f.write_fmt(format_args!("Calls {expression}")),
             LinkKind::TrueBranch(expression) => f.write_fmt(format_args!("{expression} is true")),
             LinkKind::FalseBranch(expression) => f.write_fmt(format_args!("{expression} is false")),
+            LinkKind::Case(expression, values) => {
+                f.write_fmt(format_args!("{expression} is {}", case_list(values)))
+            }
             LinkKind::Unconditional => f.write_str(""),
             LinkKind::Return => f.write_str("returns"),
         }
@@ -99,6 +107,9 @@ impl Into<EguiGraph> for &ControlFlowGraph {
                 LinkKind::FalseBranch(expression) => {
                     format!("                    {expression} is false")
                 }
+                LinkKind::Case(expression, values) => {
+                    format!("                    {expression} is {}", case_list(values))
+                }
                 LinkKind::Unconditional => String::new(),
                 LinkKind::Return => String::from("returns"),
             };
@@ -108,6 +119,15 @@ impl Into<EguiGraph> for &ControlFlowGraph {
     }
 }
 
+/// Case values joined with commas, e.g. `1, 2, 5`.
+fn case_list(values: &[u64]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Check if `start` is an ancestor of `find` in a `tree`
 pub fn is_ancestor<N>(start: N, find: N, dom: &Dominators<N>) -> bool
 where
@@ -214,6 +234,12 @@ impl ControlFlowGraph {
     pub fn new(start: Address, blocks: &BlockStorage) -> Self {
         let mut graph_map: HashMap<BlockSlot, DefaultIx> = HashMap::new();
         let mut ends = Vec::new();
+        // calls that end the function without returning
+        let mut noreturn_calls = Vec::new();
+        // whether a block leaves the function to where we don't know, like an unresolved `jmp rax`
+        let mut unknown_exit = false;
+        // jumps to another function, which returns in our place
+        let mut tail_calls = Vec::new();
         let mut forward_graph: Graph = Graph::new();
         let mut backward_graph: Graph = Graph::new();
         let start = blocks.slot_by_address(start).expect("No IR at address");
@@ -227,6 +253,22 @@ impl ControlFlowGraph {
             if blocks[block_id].is_return() {
                 ends.push(block_id);
             }
+            match &blocks[block_id].next {
+                NextBlock::Call {
+                    destination,
+                    default_return: None,
+                    ..
+                } => noreturn_calls.push((block_id, destination.clone())),
+                NextBlock::TailCall { destination, .. } => {
+                    tail_calls.push((block_id, destination.clone()))
+                }
+                _ if !blocks[block_id].is_return()
+                    && blocks.iter_neighbors(block_id).next().is_none() =>
+                {
+                    unknown_exit = true
+                }
+                _ => (),
+            }
 
             for nbr in blocks.iter_neighbors(block_id) {
                 let link_kind = match &blocks[block_id].next {
@@ -242,7 +284,17 @@ impl ControlFlowGraph {
                             LinkKind::FalseBranch(condition.clone())
                         }
                     }
-                    NextBlock::Return => LinkKind::Return,
+                    NextBlock::Switch { selector, cases } => LinkKind::Case(
+                        selector.clone(),
+                        cases
+                            .iter()
+                            .filter(|(_, destination)| {
+                                blocks.slot_by_destination(destination) == Some(nbr)
+                            })
+                            .map(|(value, _)| *value)
+                            .collect(),
+                    ),
+                    NextBlock::TailCall { .. } | NextBlock::Return => LinkKind::Return,
                     NextBlock::Follow(_) => LinkKind::Unconditional,
                 };
 
@@ -257,9 +309,10 @@ impl ControlFlowGraph {
 
         let mut properties = EnumSet::new();
         let pdom_end = blocks.next_available_id(); // always add last return block that's unmapped
-        if ends.len() == 0 {
+        let returns = ends.len() + tail_calls.len();
+        if returns == 0 && !unknown_exit {
             properties.insert(CFGProperties::NeverReturns);
-        } else if ends.len() > 1 {
+        } else if returns > 1 {
             properties.insert(CFGProperties::MultipleReturns);
         }
 
@@ -271,6 +324,11 @@ impl ControlFlowGraph {
             forward_graph.add_edge(*graph_map.get(end).unwrap(), return_node, LinkKind::Return);
             backward_graph.add_edge(return_node, *graph_map.get(end).unwrap(), LinkKind::Return);
         }
+        for (call, destination) in noreturn_calls.into_iter().chain(tail_calls) {
+            let call = graph_map[&call];
+            forward_graph.add_edge(call, return_node, LinkKind::Calls(destination.clone()));
+            backward_graph.add_edge(return_node, call, LinkKind::Calls(destination));
+        }
 
         // if this fails at graph_map.get that's because function never returns. What to do in that case?
         let distance_to_return = dijkstra(
@@ -279,7 +337,7 @@ impl ControlFlowGraph {
             None,
             |e| match e.weight() {
                 LinkKind::Calls(_) | LinkKind::Return | LinkKind::Unconditional => 0,
-                LinkKind::TrueBranch(_) | LinkKind::FalseBranch(_) => 1,
+                LinkKind::TrueBranch(_) | LinkKind::FalseBranch(_) | LinkKind::Case(..) => 1,
             },
         )
         .iter()
@@ -316,6 +374,99 @@ impl ControlFlowGraph {
     pub fn get_node_idx(&self, index: BlockSlot) -> DefaultIx {
         self.graph_map[&index]
     }
+
+    /// Outgoing edges of `slot`, with the kind of link leading to each successor.
+    pub fn successors(&self, slot: BlockSlot) -> Vec<(BlockSlot, &LinkKind)> {
+        let slots: HashMap<DefaultIx, BlockSlot> = self
+            .graph_map
+            .iter()
+            .map(|(slot, ix)| (*ix, *slot))
+            .collect();
+        self.forward_graph
+            .edges(self.graph_map[&slot])
+            .filter_map(|edge| Some((*slots.get(&edge.target())?, edge.weight())))
+            .collect()
+    }
+
+    /// Blocks with an edge into `slot`, each listed once.
+    pub fn predecessors(&self, slot: BlockSlot) -> Vec<BlockSlot> {
+        let slots: HashMap<DefaultIx, BlockSlot> = self
+            .graph_map
+            .iter()
+            .map(|(slot, ix)| (*ix, *slot))
+            .collect();
+        let target = self.graph_map[&slot];
+        let mut predecessors: Vec<BlockSlot> = self
+            .forward_graph
+            .edge_references()
+            .filter(|edge| edge.target() == target)
+            .filter_map(|edge| slots.get(&edge.source()).copied())
+            .collect();
+        predecessors.sort();
+        predecessors.dedup();
+        predecessors
+    }
+
+    /// Blocks that every path from `slot` to the return goes through, nearest first. The last
+    /// one is [`Self::single_end`].
+    pub fn post_dominators(&self, slot: BlockSlot) -> Vec<BlockSlot> {
+        let slots: HashMap<DefaultIx, BlockSlot> = self
+            .graph_map
+            .iter()
+            .map(|(slot, ix)| (*ix, *slot))
+            .collect();
+        self.pdom
+            .strict_dominators(self.graph_map[&slot])
+            .into_iter()
+            .flatten()
+            .filter_map(|ix| slots.get(&ix).copied())
+            .collect()
+    }
+
+    /// Edges `(latch, header)` whose target dominates their source. Each one closes a natural loop.
+    pub fn back_edges(&self) -> Vec<(BlockSlot, BlockSlot)> {
+        let slots: HashMap<DefaultIx, BlockSlot> = self
+            .graph_map
+            .iter()
+            .map(|(slot, ix)| (*ix, *slot))
+            .collect();
+        self.forward_graph
+            .edge_references()
+            .filter(|edge| {
+                self.dom
+                    .dominators(edge.source())
+                    .is_some_and(|mut dominators| dominators.any(|d| d == edge.target()))
+            })
+            .map(|edge| (slots[&edge.source()], slots[&edge.target()]))
+            .collect()
+    }
+
+    /// Blocks of the natural loop of `header`: the header itself and every block that reaches
+    /// one of the `latches` without going through the header.
+    pub fn natural_loop(&self, header: BlockSlot, latches: &[BlockSlot]) -> HashSet<BlockSlot> {
+        let slots: HashMap<DefaultIx, BlockSlot> = self
+            .graph_map
+            .iter()
+            .map(|(slot, ix)| (*ix, *slot))
+            .collect();
+        let mut predecessors: HashMap<DefaultIx, Vec<DefaultIx>> = HashMap::new();
+        for edge in self.forward_graph.edge_references() {
+            predecessors
+                .entry(edge.target())
+                .or_default()
+                .push(edge.source());
+        }
+
+        let header = self.graph_map[&header];
+        let mut body = HashSet::from([header]);
+        let mut stack: Vec<DefaultIx> = latches.iter().map(|l| self.graph_map[l]).collect();
+        while let Some(node) = stack.pop() {
+            if body.insert(node) {
+                stack.extend(predecessors.get(&node).into_iter().flatten());
+            }
+        }
+        body.into_iter().map(|ix| slots[&ix]).collect()
+    }
 }
 
 mod test {
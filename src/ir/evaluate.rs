@@ -0,0 +1,462 @@
+//! # Concrete evaluation of expressions
+//!
+//! [`Expression::evaluate`] computes the value of an expression once every symbolic leaf has a
+//! concrete value. The values come from an [`Environment`]:
+//! - `VariableSymbol::Varnode` and `VariableSymbol::CallResult` are looked up directly
+//! - `VariableSymbol::Ram` is looked up directly first, otherwise its address is evaluated and
+//!   the memory at that address is requested from [`Environment::memory`]
+//!
+//! All arithmetic is done on `u128` and wraps around at the [`InstructionSize`] of the operation.
+//! Sizes above 128 bits (YMM/ZMM registers) are evaluated as 128-bit values.
+
+use std::collections::HashMap;
+
+use pcode::VarNode;
+use thiserror::Error;
+
+use super::expression::{
+    Expression, ExpressionOp, FloatPrecision, FloatUnaryOp, InstructionSize, OpIdx,
+    SignedOrUnsiged, VariableSymbol,
+};
+
+/// Source of concrete values for the symbolic leaves of an [`Expression`].
+pub trait Environment {
+    /// Value of a symbolic variable, `None` if it's unknown.
+    fn variable(&self, symbol: &VariableSymbol) -> Option<u128>;
+
+    /// Value of `size` bytes of memory at `address`, `None` if it's unknown.
+    fn memory(&self, _address: u64, _size: u8) -> Option<u128> {
+        None
+    }
+}
+
+impl Environment for HashMap<VariableSymbol, u128> {
+    fn variable(&self, symbol: &VariableSymbol) -> Option<u128> {
+        self.get(symbol).copied()
+    }
+}
+
+/// Assignment of varnodes only, e.g. "what does this function return when EDI=5".
+impl Environment for HashMap<VarNode, u128> {
+    fn variable(&self, symbol: &VariableSymbol) -> Option<u128> {
+        match symbol {
+            VariableSymbol::Varnode(var_node) => self.get(var_node).copied(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum EvaluationError {
+    #[error("No value for {0}")]
+    UnknownVariable(VariableSymbol),
+    #[error("No value for {1} bytes of memory at 0x{0:x}")]
+    UnknownMemory(u64, u8),
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Multiequals has diverging values {0:#x} and {1:#x}")]
+    Diverging(u128, u128),
+    #[error("Can't evaluate {0}")]
+    Unsupported(&'static str),
+}
+
+/// Keep only the lower bits of `value` that fit into `size`.
+fn wrap(value: u128, size: InstructionSize) -> u128 {
+    match size.bits() {
+        bits if bits >= 128 => value,
+        bits => value & ((1u128 << bits) - 1),
+    }
+}
+
+/// Interpret the lower bits of `value` that fit into `size` as a two's complement number.
+fn as_signed(value: u128, size: InstructionSize) -> i128 {
+    let shift = 128u32.saturating_sub(size.bits());
+    (value << shift) as i128 >> shift
+}
+
+fn fits_signed(value: i128, size: InstructionSize) -> bool {
+    match size.bits() {
+        bits if bits >= 128 => true,
+        bits => {
+            let half = 1i128 << (bits - 1);
+            -half <= value && value < half
+        }
+    }
+}
+
+fn float_operand(value: u128, precision: FloatPrecision) -> Result<f64, EvaluationError> {
+    precision
+        .decode(value as u64)
+        .ok_or(EvaluationError::Unsupported("extended precision float"))
+}
+
+fn float_result(value: f64, precision: FloatPrecision) -> Result<u128, EvaluationError> {
+    precision
+        .encode(value)
+        .map(u128::from)
+        .ok_or(EvaluationError::Unsupported(
+            "half or extended precision float",
+        ))
+}
+
+impl Expression {
+    /// Evaluate this expression to a concrete value.
+    ///
+    /// Every symbolic leaf must have a value in `env`. Comparisons and boolean operations
+    /// evaluate to `0` or `1`, floating-point results are returned as their bit pattern.
+    ///
+    /// # Arguments
+    /// * `env` - Concrete values for varnodes, call results and memory
+    ///
+    /// # Returns
+    /// The value of the expression, or the reason it couldn't be evaluated
+    ///
+    /// # Examples
+    /// ```ignore
+    /// let env = HashMap::from([(edi, 5u128)]);
+    /// let result = return_value.evaluate(&env)?;
+    /// ```
+    pub fn evaluate<E: Environment + ?Sized>(&self, env: &E) -> Result<u128, EvaluationError> {
+        if self.len() == 0 {
+            return Err(EvaluationError::Unsupported("empty expression"));
+        }
+        self.evaluate_at(self.get_entry_point(), env)
+    }
+
+    fn evaluate_at<E: Environment + ?Sized>(
+        &self,
+        idx: OpIdx,
+        env: &E,
+    ) -> Result<u128, EvaluationError> {
+        use ExpressionOp::*;
+        let eval = |idx: &OpIdx| self.evaluate_at(*idx, env);
+        let bool_result = |b: bool| Ok(b as u128);
+
+        match self.get(idx) {
+            Value(v) => Ok(*v as u128),
+            FloatValue(bits, _) => Ok(*bits as u128),
+            Variable(symbol) => evaluate_variable(symbol, env),
+            DestinationRegister(_) => Err(EvaluationError::Unsupported("destination register")),
+            Interrupt(_) => Err(EvaluationError::Unsupported("interrupt")),
+            Dereference(_) => Err(EvaluationError::Unsupported("dereference without size")),
+            Overflow(_, _) => Err(EvaluationError::Unsupported("overflow")),
+            Assign(_, r) => eval(r),
+            Multiequals(l, r) => match (eval(l)?, eval(r)?) {
+                (l, r) if l == r => Ok(l),
+                (l, r) => Err(EvaluationError::Diverging(l, r)),
+            },
+
+            Add(l, r, size) => Ok(wrap(eval(l)?.wrapping_add(eval(r)?), *size)),
+            Sub(l, r, size) => Ok(wrap(eval(l)?.wrapping_sub(eval(r)?), *size)),
+            Multiply(l, r, size) => Ok(wrap(eval(l)?.wrapping_mul(eval(r)?), *size)),
+            Divide(l, r, size, sgn) | Remainder(l, r, size, sgn) => {
+                let is_remainder = matches!(self.get(idx), Remainder(..));
+                let (l, r) = (wrap(eval(l)?, *size), wrap(eval(r)?, *size));
+                if r == 0 {
+                    return Err(EvaluationError::DivisionByZero);
+                }
+                let result = match (sgn, is_remainder) {
+                    (SignedOrUnsiged::Unsigned, false) => l / r,
+                    (SignedOrUnsiged::Unsigned, true) => l % r,
+                    (SignedOrUnsiged::Signed, false) => {
+                        as_signed(l, *size).wrapping_div(as_signed(r, *size)) as u128
+                    }
+                    (SignedOrUnsiged::Signed, true) => {
+                        as_signed(l, *size).wrapping_rem(as_signed(r, *size)) as u128
+                    }
+                };
+                Ok(wrap(result, *size))
+            }
+
+            LessOrEquals(l, r, sgn)
+            | Less(l, r, sgn)
+            | GreaterOrEquals(l, r, sgn)
+            | Greater(l, r, sgn)
+            | Equals(l, r, sgn)
+            | NotEquals(l, r, sgn) => {
+                let size = self.comparison_size(*l, *r);
+                let (a, b) = (wrap(eval(l)?, size), wrap(eval(r)?, size));
+                let ordering = match sgn {
+                    SignedOrUnsiged::Unsigned => a.cmp(&b),
+                    SignedOrUnsiged::Signed => as_signed(a, size).cmp(&as_signed(b, size)),
+                };
+                bool_result(match self.get(idx) {
+                    LessOrEquals(..) => ordering.is_le(),
+                    Less(..) => ordering.is_lt(),
+                    GreaterOrEquals(..) => ordering.is_ge(),
+                    Greater(..) => ordering.is_gt(),
+                    Equals(..) => ordering.is_eq(),
+                    _ => ordering.is_ne(),
+                })
+            }
+
+            BitShiftRight(l, r, size) => {
+                let (value, shift) = (wrap(eval(l)?, *size), eval(r)?);
+                Ok(value
+                    .checked_shr(shift.min(u32::MAX as u128) as u32)
+                    .unwrap_or(0))
+            }
+            BitShiftLeft(l, r, size) => {
+                let (value, shift) = (eval(l)?, eval(r)?);
+                let shifted = value
+                    .checked_shl(shift.min(u32::MAX as u128) as u32)
+                    .unwrap_or(0);
+                Ok(wrap(shifted, *size))
+            }
+            And(l, r) => Ok(eval(l)? & eval(r)?),
+            Or(l, r) => Ok(eval(l)? | eval(r)?),
+            Xor(l, r) => Ok(eval(l)? ^ eval(r)?),
+            // the right side isn't evaluated when the left one decides the result
+            LogicalAnd(l, r) => bool_result(eval(l)? != 0 && eval(r)? != 0),
+            LogicalOr(l, r) => bool_result(eval(l)? != 0 || eval(r)? != 0),
+            Not(l) => {
+                let value = eval(l)?;
+                if self.is_boolean(*l) {
+                    bool_result(value == 0)
+                } else {
+                    Ok(wrap(
+                        !value,
+                        self.size_of(*l).unwrap_or(InstructionSize::U64),
+                    ))
+                }
+            }
+            CountOnes(l) => Ok(eval(l)?.count_ones() as u128),
+
+            SignExtend(l, from, to) => Ok(wrap(as_signed(eval(l)?, *from) as u128, *to)),
+            ZeroExtend(l, from, _) => Ok(wrap(eval(l)?, *from)),
+            Truncate(l, _, to) => Ok(wrap(eval(l)?, *to)),
+
+            Carry(l, r, size, sgn) => {
+                let (l, r) = (wrap(eval(l)?, *size), wrap(eval(r)?, *size));
+                bool_result(match sgn {
+                    SignedOrUnsiged::Unsigned => wrap(l.wrapping_add(r), *size) < l,
+                    SignedOrUnsiged::Signed => {
+                        match as_signed(l, *size).checked_add(as_signed(r, *size)) {
+                            Some(sum) => !fits_signed(sum, *size),
+                            None => true,
+                        }
+                    }
+                })
+            }
+            Borrow(l, r, size) => {
+                let (l, r) = (wrap(eval(l)?, *size), wrap(eval(r)?, *size));
+                bool_result(match as_signed(l, *size).checked_sub(as_signed(r, *size)) {
+                    Some(difference) => !fits_signed(difference, *size),
+                    None => true,
+                })
+            }
+
+            FloatBinary(l, r, op, precision) => {
+                let l = float_operand(eval(l)?, *precision)?;
+                let r = float_operand(eval(r)?, *precision)?;
+                float_result(op.apply(l, r), *precision)
+            }
+            FloatCompare(l, r, op, precision) => {
+                let l = float_operand(eval(l)?, *precision)?;
+                let r = float_operand(eval(r)?, *precision)?;
+                bool_result(op.apply(l, r))
+            }
+            FloatUnary(l, FloatUnaryOp::IsNan, precision) => {
+                bool_result(float_operand(eval(l)?, *precision)?.is_nan())
+            }
+            FloatUnary(l, op, precision) => {
+                let value = float_operand(eval(l)?, *precision)?;
+                float_result(op.apply(value), *precision)
+            }
+            IntToFloat(l, sgn, precision) => {
+                let size = self.size_of(*l).unwrap_or(InstructionSize::U64);
+                let value = eval(l)?;
+                let value = match sgn {
+                    SignedOrUnsiged::Signed => as_signed(value, size) as f64,
+                    SignedOrUnsiged::Unsigned => wrap(value, size) as f64,
+                };
+                float_result(value, *precision)
+            }
+            FloatToFloat(l, from, to) => float_result(float_operand(eval(l)?, *from)?, *to),
+            FloatToInt(l, size) => {
+                let precision = match self.get(*l) {
+                    FloatValue(_, precision)
+                    | FloatBinary(_, _, _, precision)
+                    | FloatUnary(_, _, precision)
+                    | IntToFloat(_, _, precision)
+                    | FloatToFloat(_, _, precision) => *precision,
+                    _ => FloatPrecision::Double,
+                };
+                let value = float_operand(eval(l)?, precision)?;
+                Ok(wrap(value as i128 as u128, *size))
+            }
+        }
+    }
+
+    /// Size of the value produced by the operation at `idx`, if it's known.
+    pub(crate) fn size_of(&self, idx: OpIdx) -> Option<InstructionSize> {
+        use ExpressionOp::*;
+        match self.get(idx) {
+            Variable(symbol) => symbol.size().map(Into::into),
+            Add(_, _, size)
+            | Sub(_, _, size)
+            | Multiply(_, _, size)
+            | Divide(_, _, size, _)
+            | Remainder(_, _, size, _)
+            | BitShiftRight(_, _, size)
+            | BitShiftLeft(_, _, size)
+            | SignExtend(_, _, size)
+            | ZeroExtend(_, _, size)
+            | Truncate(_, _, size)
+            | FloatToInt(_, size) => Some(*size),
+            And(l, r) | Or(l, r) | Xor(l, r) => self.size_of(*l).or_else(|| self.size_of(*r)),
+            Not(l) => self.size_of(*l),
+            _ if self.is_boolean(idx) => Some(InstructionSize::U8),
+            _ => None,
+        }
+    }
+
+    /// Width used to compare the operands `l` and `r`, which isn't stored in comparisons.
+    fn comparison_size(&self, l: OpIdx, r: OpIdx) -> InstructionSize {
+        self.size_of(l)
+            .or_else(|| self.size_of(r))
+            .unwrap_or(InstructionSize::U64)
+    }
+
+    /// Whether the operation at `idx` produces a boolean `0` or `1`.
+    fn is_boolean(&self, idx: OpIdx) -> bool {
+        use ExpressionOp::*;
+        match self.get(idx) {
+            LessOrEquals(..)
+            | Less(..)
+            | GreaterOrEquals(..)
+            | Greater(..)
+            | Equals(..)
+            | NotEquals(..)
+            | FloatCompare(..)
+            | FloatUnary(_, FloatUnaryOp::IsNan, _)
+            | Carry(..)
+            | Borrow(..)
+            | Overflow(..)
+            | LogicalAnd(..)
+            | LogicalOr(..) => true,
+            And(l, r) | Or(l, r) | Xor(l, r) => self.is_boolean(*l) && self.is_boolean(*r),
+            Not(l) => self.is_boolean(*l),
+            _ => false,
+        }
+    }
+}
+
+fn evaluate_variable<E: Environment + ?Sized>(
+    symbol: &VariableSymbol,
+    env: &E,
+) -> Result<u128, EvaluationError> {
+    if let Some(value) = env.variable(symbol) {
+        return Ok(value);
+    }
+    match symbol {
+        VariableSymbol::Ram(address, size) => {
+            let address = address.evaluate(env)? as u64;
+            env.memory(address, *size)
+                .ok_or(EvaluationError::UnknownMemory(address, *size))
+        }
+        _ => Err(EvaluationError::UnknownVariable(symbol.clone())),
+    }
+}
+
+mod test {
+    use std::collections::HashMap;
+
+    use pcode::VarNode;
+
+    use super::{Environment, EvaluationError};
+    use crate::ir::expression::{
+        Expression,
+        InstructionSize::{U32, U8},
+        SignedOrUnsiged::{Signed, Unsigned},
+        VariableSymbol,
+    };
+
+    const EAX: VarNode = VarNode {
+        id: 1,
+        offset: 0,
+        size: 4,
+    };
+
+    struct Stack;
+
+    impl Environment for Stack {
+        fn variable(&self, symbol: &VariableSymbol) -> Option<u128> {
+            match symbol {
+                VariableSymbol::Varnode(var_node) if *var_node == EAX => Some(0x1000),
+                _ => None,
+            }
+        }
+
+        fn memory(&self, address: u64, size: u8) -> Option<u128> {
+            (address == 0x1004 && size == 4).then_some(0xfffffffe)
+        }
+    }
+
+    #[test]
+    fn test_evaluate_wraparound() {
+        let eax = Expression::from(VariableSymbol::Varnode(EAX));
+        let env = HashMap::from([(EAX, 0xffffffffu128)]);
+
+        let mut e = eax.clone();
+        e.add_value(2, U32);
+        assert_eq!(e.evaluate(&env), Ok(1));
+
+        let mut e = eax.clone();
+        e.check_less(&Expression::from(0), U32, Signed);
+        assert_eq!(e.evaluate(&env), Ok(1));
+
+        let mut e = eax.clone();
+        e.check_less(&Expression::from(0), U32, Unsigned);
+        assert_eq!(e.evaluate(&env), Ok(0));
+
+        let mut e = eax.clone();
+        e.truncate(U32, U8);
+        e.sign_extend(U8, U32);
+        assert_eq!(e.evaluate(&env), Ok(0xffffffff));
+
+        let mut e = eax.clone();
+        e.divide(&Expression::from(0), U32, Unsigned);
+        assert_eq!(e.evaluate(&env), Err(EvaluationError::DivisionByZero));
+    }
+
+    #[test]
+    fn test_evaluate_short_circuit() {
+        let eax = Expression::from(VariableSymbol::Varnode(EAX));
+        let env = HashMap::from([(EAX, 0xffffffffu128)]);
+
+        let mut never = eax.clone();
+        never.check_less(&Expression::from(0), U32, Unsigned);
+        let mut undefined = eax.clone();
+        undefined.divide(&Expression::from(0), U32, Unsigned);
+
+        // the right side isn't evaluated once the left one decides
+        let mut e = never.clone();
+        e.logical_and(&undefined);
+        assert_eq!(e.evaluate(&env), Ok(0));
+
+        let mut e = never.clone();
+        e.logical_or(&undefined);
+        assert_eq!(e.evaluate(&env), Err(EvaluationError::DivisionByZero));
+
+        let mut e = never.clone();
+        e.not();
+        e.logical_or(&undefined);
+        assert_eq!(e.evaluate(&env), Ok(1));
+    }
+
+    #[test]
+    fn test_evaluate_memory() {
+        let mut address = Expression::from(VariableSymbol::Varnode(EAX));
+        address.add_value(4, U32);
+        let mut e = Expression::from(VariableSymbol::Ram(Box::new(address), 4));
+        e.divide_value(2, U32, Signed);
+        assert_eq!(e.evaluate(&Stack), Ok(0xffffffff));
+
+        let e = Expression::from(VariableSymbol::Ram(Box::new(Expression::from(0x2000)), 4));
+        assert_eq!(
+            e.evaluate(&Stack),
+            Err(EvaluationError::UnknownMemory(0x2000, 4))
+        );
+    }
+}
@@ -85,12 +85,28 @@ pub enum VariableSymbol {
         call_to: Box<DestinationKind>,
     },
 
+    /// Unknown value a call leaves in a register that the callee doesn't preserve.
+    Clobbered {
+        /// Address where the call instruction is located
+        call_from: Address,
+        register: VarNode,
+    },
+
     /// Unknown state of memory (RAM) at a specific address and size.
     ///
     /// The address itself can be an Expression, allowing for complex memory
     /// access patterns like `[ESP + offset]` or `[EBX + ECX*4 + 8]`.
     /// The u8 represents the size of the memory access in bytes.
     Ram(Box<Expression>, u8),
+
+    /// Value of a register or memory location at the start of an iteration of a loop, which
+    /// differs from its value before the loop. See [`crate::ir::loops::LoopCarried`].
+    LoopCarried {
+        /// Address of the loop header
+        header: Address,
+        /// The [`Self::Varnode`] or [`Self::Ram`] that the loop changes
+        location: Box<VariableSymbol>,
+    },
 }
 
 impl std::fmt::Debug for VariableSymbol {
@@ -100,7 +116,14 @@ impl std::fmt::Debug for VariableSymbol {
             Self::CallResult { call_to, .. } => {
                 f.write_fmt(format_args!("call_{call_to:?}_result"))
             }
+            Self::Clobbered {
+                call_from,
+                register,
+            } => f.write_fmt(format_args!("?{register:?}_after_{call_from:?}")),
             Self::Ram(arg0, _) => f.write_fmt(format_args!("[{arg0:?}]")),
+            Self::LoopCarried { header, location } => {
+                f.write_fmt(format_args!("{location:?}_loop_{header:?}"))
+            }
         }
     }
 }
@@ -110,12 +133,30 @@ impl std::fmt::Display for VariableSymbol {
         match self {
             Self::Varnode(arg0) => f.write_fmt(format_args!("?{arg0:?}")),
             Self::CallResult { call_to, .. } => f.write_fmt(format_args!("call_{call_to}_result")),
+            Self::Clobbered {
+                call_from,
+                register,
+            } => f.write_fmt(format_args!("?{register:?}_after_{call_from}")),
             Self::Ram(arg0, _) => f.write_fmt(format_args!("ram[{arg0}]")),
+            Self::LoopCarried { header, location } => {
+                f.write_fmt(format_args!("{location}_loop_{header}"))
+            }
         }
     }
 }
 
 impl VariableSymbol {
+    /// Size of the value in bytes, `None` for call results, which don't carry a size of their own.
+    pub fn size(&self) -> Option<u8> {
+        match self {
+            Self::Varnode(var_node) => Some(var_node.size),
+            Self::CallResult { .. } => None,
+            Self::Clobbered { register, .. } => Some(register.size),
+            Self::Ram(_, size) => Some(*size),
+            Self::LoopCarried { location, .. } => location.size(),
+        }
+    }
+
     // pub fn get_memory_address_or_null(&self) -> Address {
     //     match self {
     //         VariableSymbol::Register(_) |
@@ -145,11 +186,25 @@ impl FormatWithSleighLanguage for VariableSymbol {
                 call_to.display_fmt(lang, f)?;
                 f.write_str("_result")
             }
+            Self::Clobbered {
+                call_from,
+                register,
+            } => {
+                if let Some(name) = lang.and_then(|l| l.sleigh.name_of_varnode(*register)) {
+                    f.write_fmt(format_args!("?{name}_after_{call_from}"))
+                } else {
+                    f.write_fmt(format_args!("?{register:?}_after_{call_from}"))
+                }
+            }
             Self::Ram(arg0, _) => {
                 f.write_str("ram[")?;
                 arg0.display_fmt(lang, f)?;
                 f.write_str("]")
             }
+            Self::LoopCarried { header, location } => {
+                location.display_fmt(lang, f)?;
+                f.write_fmt(format_args!("_loop_{header}"))
+            }
         }
     }
 
@@ -195,6 +250,53 @@ pub enum InstructionSize {
     U512,
 }
 
+impl InstructionSize {
+    /// Width of this size in bits.
+    pub fn bits(&self) -> u32 {
+        match self {
+            InstructionSize::U8 => 8,
+            InstructionSize::U16 => 16,
+            InstructionSize::U32 => 32,
+            InstructionSize::U64 => 64,
+            InstructionSize::U128 => 128,
+            InstructionSize::U256 => 256,
+            InstructionSize::U512 => 512,
+        }
+    }
+
+    /// Name of the C integer type of this size, e.g. `int64_t` or `uint8_t`.
+    pub fn c_type_name(&self, sgn: SignedOrUnsiged) -> &'static str {
+        use SignedOrUnsiged::*;
+        match (self, sgn) {
+            (InstructionSize::U8, Signed) => "int8_t",
+            (InstructionSize::U8, Unsigned) => "uint8_t",
+            (InstructionSize::U16, Signed) => "int16_t",
+            (InstructionSize::U16, Unsigned) => "uint16_t",
+            (InstructionSize::U32, Signed) => "int32_t",
+            (InstructionSize::U32, Unsigned) => "uint32_t",
+            (InstructionSize::U64, Signed) => "int64_t",
+            (InstructionSize::U64, Unsigned) => "uint64_t",
+            (InstructionSize::U128, Signed) => "int128_t",
+            (InstructionSize::U128, Unsigned) => "uint128_t",
+            (InstructionSize::U256, Signed) => "int256_t",
+            (InstructionSize::U256, Unsigned) => "uint256_t",
+            (InstructionSize::U512, Signed) => "int512_t",
+            (InstructionSize::U512, Unsigned) => "uint512_t",
+        }
+    }
+
+    /// Keep only the lower bits of `value` that fit into this size.
+    ///
+    /// Constants are stored as `u64`, so sizes of 64 bits and above keep the value as-is.
+    pub fn wrap(&self, value: u64) -> u64 {
+        if self.bits() >= 64 {
+            value
+        } else {
+            value & ((1u64 << self.bits()) - 1)
+        }
+    }
+}
+
 /// Indicates whether an operation should be interpreted as signed or unsigned.
 ///
 /// This affects comparison operations and overflow detection, as the same
@@ -207,6 +309,206 @@ pub enum SignedOrUnsiged {
     Unsigned,
 }
 
+/// Precision of a floating-point operation, derived from the size of its varnodes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FloatPrecision {
+    /// 16-bit IEEE 754 half precision
+    Half,
+    /// 32-bit IEEE 754 single precision (`float`)
+    Single,
+    /// 64-bit IEEE 754 double precision (`double`)
+    Double,
+    /// 80-bit x87 extended precision (`long double`)
+    Extended,
+}
+
+impl Into<FloatPrecision> for VarSize {
+    fn into(self) -> FloatPrecision {
+        use FloatPrecision::*;
+        match self {
+            2 => Half,
+            4 => Single,
+            8 => Double,
+            10 | 12 | 16 => Extended, // x87 FPU registers, possibly padded
+            _ => {
+                eprintln!(
+                    "Warning: Unexpected float varnode size {}, treating as double",
+                    self
+                );
+                Double
+            }
+        }
+    }
+}
+
+impl FloatPrecision {
+    /// Name of the C type of this precision.
+    pub fn c_type_name(&self) -> &'static str {
+        match self {
+            FloatPrecision::Half => "_Float16",
+            FloatPrecision::Single => "float",
+            FloatPrecision::Double => "double",
+            FloatPrecision::Extended => "long double",
+        }
+    }
+
+    /// Decode a raw bit pattern of this precision.
+    ///
+    /// Returns `None` for [`FloatPrecision::Extended`], whose 80 bits do not fit into a constant.
+    pub fn decode(&self, bits: u64) -> Option<f64> {
+        match self {
+            FloatPrecision::Half => {
+                let exponent = (bits >> 10) & 0x1f;
+                let mantissa = (bits & 0x3ff) as f64;
+                let magnitude = match exponent {
+                    0 => mantissa * 2f64.powi(-24),
+                    0x1f if mantissa == 0.0 => f64::INFINITY,
+                    0x1f => f64::NAN,
+                    e => (1.0 + mantissa / 1024.0) * 2f64.powi(e as i32 - 15),
+                };
+                Some(if bits & 0x8000 != 0 {
+                    -magnitude
+                } else {
+                    magnitude
+                })
+            }
+            FloatPrecision::Single => Some(f32::from_bits(bits as u32) as f64),
+            FloatPrecision::Double => Some(f64::from_bits(bits)),
+            FloatPrecision::Extended => None,
+        }
+    }
+
+    /// Encode `value` as a raw bit pattern of this precision.
+    ///
+    /// Only single and double precision constants are produced, other precisions return `None`.
+    pub fn encode(&self, value: f64) -> Option<u64> {
+        match self {
+            FloatPrecision::Single => Some((value as f32).to_bits() as u64),
+            FloatPrecision::Double => Some(value.to_bits()),
+            FloatPrecision::Half | FloatPrecision::Extended => None,
+        }
+    }
+
+    /// Format a raw bit pattern of this precision as a decimal literal, e.g. `1.5` or `0.1f`.
+    ///
+    /// Patterns that cannot be decoded are printed in hex.
+    pub fn format_constant(&self, bits: u64) -> String {
+        match self.decode(bits) {
+            Some(v) if *self == FloatPrecision::Single => format!("{:?}f", v as f32),
+            Some(v) => format!("{v:?}"),
+            None => format!("0x{bits:x}"),
+        }
+    }
+}
+
+/// Binary floating-point arithmetic operator.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FloatBinaryOp {
+    Add,
+    Sub,
+    Multiply,
+    Divide,
+}
+
+impl FloatBinaryOp {
+    /// Infix symbol of this operator, including the surrounding spaces.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            FloatBinaryOp::Add => " + ",
+            FloatBinaryOp::Sub => " - ",
+            FloatBinaryOp::Multiply => " * ",
+            FloatBinaryOp::Divide => " / ",
+        }
+    }
+
+    pub(crate) fn apply(&self, l: f64, r: f64) -> f64 {
+        match self {
+            FloatBinaryOp::Add => l + r,
+            FloatBinaryOp::Sub => l - r,
+            FloatBinaryOp::Multiply => l * r,
+            FloatBinaryOp::Divide => l / r,
+        }
+    }
+}
+
+/// Unary floating-point operator.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FloatUnaryOp {
+    /// `-operand`
+    Negate,
+    /// `fabs(operand)`
+    Abs,
+    /// `sqrt(operand)`
+    Sqrt,
+    /// `ceil(operand)`
+    Ceiling,
+    /// `floor(operand)`
+    Floor,
+    /// `round(operand)`
+    Round,
+    /// `isnan(operand)`, producing a boolean
+    IsNan,
+}
+
+impl FloatUnaryOp {
+    /// Name of the C function implementing this operator, `None` for prefix negation.
+    pub fn function_name(&self) -> Option<&'static str> {
+        match self {
+            FloatUnaryOp::Negate => None,
+            FloatUnaryOp::Abs => Some("fabs"),
+            FloatUnaryOp::Sqrt => Some("sqrt"),
+            FloatUnaryOp::Ceiling => Some("ceil"),
+            FloatUnaryOp::Floor => Some("floor"),
+            FloatUnaryOp::Round => Some("round"),
+            FloatUnaryOp::IsNan => Some("isnan"),
+        }
+    }
+
+    pub(crate) fn apply(&self, v: f64) -> f64 {
+        match self {
+            FloatUnaryOp::Negate => -v,
+            FloatUnaryOp::Abs => v.abs(),
+            FloatUnaryOp::Sqrt => v.sqrt(),
+            FloatUnaryOp::Ceiling => v.ceil(),
+            FloatUnaryOp::Floor => v.floor(),
+            FloatUnaryOp::Round => v.round(),
+            FloatUnaryOp::IsNan => unreachable!("isnan produces a boolean"),
+        }
+    }
+}
+
+/// Floating-point comparison operator, producing a boolean.
+///
+/// Comparisons involving NaN are false, except for [`FloatCompareOp::NotEquals`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FloatCompareOp {
+    Less,
+    LessOrEquals,
+    Equals,
+    NotEquals,
+}
+
+impl FloatCompareOp {
+    /// Infix symbol of this operator, including the surrounding spaces.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            FloatCompareOp::Less => " < ",
+            FloatCompareOp::LessOrEquals => " <= ",
+            FloatCompareOp::Equals => " == ",
+            FloatCompareOp::NotEquals => " != ",
+        }
+    }
+
+    pub(crate) fn apply(&self, l: f64, r: f64) -> bool {
+        match self {
+            FloatCompareOp::Less => l < r,
+            FloatCompareOp::LessOrEquals => l <= r,
+            FloatCompareOp::Equals => l == r,
+            FloatCompareOp::NotEquals => l != r,
+        }
+    }
+}
+
 impl Into<InstructionSize> for VarSize {
     fn into(self) -> InstructionSize {
         use InstructionSize::*;
@@ -281,6 +583,10 @@ pub enum ExpressionOp {
     Sub(OpIdx, OpIdx, InstructionSize),
     /// Multiplication: `lhs * rhs`
     Multiply(OpIdx, OpIdx, InstructionSize),
+    /// Division: `lhs / rhs`, rounding towards zero
+    Divide(OpIdx, OpIdx, InstructionSize, SignedOrUnsiged),
+    /// Remainder: `lhs % rhs`, taking the sign of `lhs` when signed
+    Remainder(OpIdx, OpIdx, InstructionSize, SignedOrUnsiged),
 
     // === Comparison Operations ===
     /// Less than or equal: `lhs <= rhs`
@@ -310,7 +616,41 @@ pub enum ExpressionOp {
     /// Bitwise XOR: `lhs ^ rhs`
     Xor(OpIdx, OpIdx),
 
+    // === Logical Operations ===
+    /// Short-circuiting AND of two conditions: `lhs && rhs`
+    LogicalAnd(OpIdx, OpIdx),
+    /// Short-circuiting OR of two conditions: `lhs || rhs`
+    LogicalOr(OpIdx, OpIdx),
+
+    // === Width Conversions ===
+    /// Sign extension from the first size to the second: `(intN_t)operand`
+    SignExtend(OpIdx, InstructionSize, InstructionSize),
+    /// Zero extension from the first size to the second: `(uintN_t)operand`
+    ZeroExtend(OpIdx, InstructionSize, InstructionSize),
+    /// Truncation from the first size to the second, keeping the lower bytes: `(uintN_t)operand`
+    Truncate(OpIdx, InstructionSize, InstructionSize),
+
+    // === Floating-Point Operations ===
+    /// A constant floating-point value, stored as its raw bit pattern
+    FloatValue(u64, FloatPrecision),
+    /// Floating-point arithmetic: `lhs op rhs`
+    FloatBinary(OpIdx, OpIdx, FloatBinaryOp, FloatPrecision),
+    /// Floating-point unary operation, e.g. `-operand` or `sqrt(operand)`
+    FloatUnary(OpIdx, FloatUnaryOp, FloatPrecision),
+    /// Floating-point comparison: `lhs op rhs`
+    FloatCompare(OpIdx, OpIdx, FloatCompareOp, FloatPrecision),
+    /// Integer to floating-point conversion: `(float)operand`
+    IntToFloat(OpIdx, SignedOrUnsiged, FloatPrecision),
+    /// Conversion between floating-point precisions, from the first to the second: `(double)operand`
+    FloatToFloat(OpIdx, FloatPrecision, FloatPrecision),
+    /// Floating-point to integer conversion, truncating towards zero: `(intN_t)operand`
+    FloatToInt(OpIdx, InstructionSize),
+
     // === Special Operations ===
+    /// Carry out of the addition `lhs + rhs`: unsigned carry, or signed overflow when signed
+    Carry(OpIdx, OpIdx, InstructionSize, SignedOrUnsiged),
+    /// Signed overflow of the subtraction `lhs - rhs`
+    Borrow(OpIdx, OpIdx, InstructionSize),
     /// Overflow detection for the operand
     Overflow(OpIdx, SignedOrUnsiged),
     /// Population count (number of 1 bits)
@@ -419,7 +759,8 @@ fn remap_operands<T>(
     match &src[pos.as_idx()] {
         e @ ExpressionOp::Variable(_)
         | e @ ExpressionOp::DestinationRegister(_)
-        | e @ ExpressionOp::Value(_) => vec.push(map(e, vec)),
+        | e @ ExpressionOp::Value(_)
+        | e @ ExpressionOp::FloatValue(_, _) => vec.push(map(e, vec)),
         ExpressionOp::Dereference(p) => {
             remap_operands(src, *p, vec, map);
             vec.push(ExpressionOp::Dereference(OpIdx::from_idx(vec.len() - 1)));
@@ -436,6 +777,75 @@ fn remap_operands<T>(
             remap_operands(src, *p, vec, map);
             vec.push(ExpressionOp::CountOnes(OpIdx::from_idx(vec.len() - 1)));
         }
+        ExpressionOp::SignExtend(p, from, to) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::SignExtend(
+                OpIdx::from_idx(vec.len() - 1),
+                *from,
+                *to,
+            ));
+        }
+        ExpressionOp::ZeroExtend(p, from, to) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::ZeroExtend(
+                OpIdx::from_idx(vec.len() - 1),
+                *from,
+                *to,
+            ));
+        }
+        ExpressionOp::Truncate(p, from, to) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::Truncate(
+                OpIdx::from_idx(vec.len() - 1),
+                *from,
+                *to,
+            ));
+        }
+        ExpressionOp::FloatUnary(p, op, precision) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::FloatUnary(
+                OpIdx::from_idx(vec.len() - 1),
+                *op,
+                *precision,
+            ));
+        }
+        ExpressionOp::IntToFloat(p, sgn, precision) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::IntToFloat(
+                OpIdx::from_idx(vec.len() - 1),
+                *sgn,
+                *precision,
+            ));
+        }
+        ExpressionOp::FloatToFloat(p, from, to) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::FloatToFloat(
+                OpIdx::from_idx(vec.len() - 1),
+                *from,
+                *to,
+            ));
+        }
+        ExpressionOp::FloatToInt(p, size) => {
+            remap_operands(src, *p, vec, map);
+            vec.push(ExpressionOp::FloatToInt(
+                OpIdx::from_idx(vec.len() - 1),
+                *size,
+            ));
+        }
+        ExpressionOp::FloatBinary(l, r, op, precision) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::FloatBinary(l, r, *op, *precision));
+        }
+        ExpressionOp::FloatCompare(l, r, op, precision) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::FloatCompare(l, r, *op, *precision));
+        }
         ExpressionOp::Assign(l, r) => {
             remap_operands(src, *l, vec, map);
             let l = OpIdx::from_idx(vec.len() - 1);
@@ -471,6 +881,34 @@ fn remap_operands<T>(
             let r = OpIdx::from_idx(vec.len() - 1);
             vec.push(ExpressionOp::Multiply(l, r, *size));
         }
+        ExpressionOp::Divide(l, r, size, sgn) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::Divide(l, r, *size, *sgn));
+        }
+        ExpressionOp::Remainder(l, r, size, sgn) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::Remainder(l, r, *size, *sgn));
+        }
+        ExpressionOp::Carry(l, r, size, sgn) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::Carry(l, r, *size, *sgn));
+        }
+        ExpressionOp::Borrow(l, r, size) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::Borrow(l, r, *size));
+        }
         ExpressionOp::LessOrEquals(l, r, sgn) => {
             remap_operands(src, *l, vec, map);
             let l = OpIdx::from_idx(vec.len() - 1);
@@ -553,6 +991,96 @@ fn remap_operands<T>(
             let r = OpIdx::from_idx(vec.len() - 1);
             vec.push(ExpressionOp::Xor(l, r));
         }
+        ExpressionOp::LogicalAnd(l, r) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::LogicalAnd(l, r));
+        }
+        ExpressionOp::LogicalOr(l, r) => {
+            remap_operands(src, *l, vec, map);
+            let l = OpIdx::from_idx(vec.len() - 1);
+            remap_operands(src, *r, vec, map);
+            let r = OpIdx::from_idx(vec.len() - 1);
+            vec.push(ExpressionOp::LogicalOr(l, r));
+        }
+    }
+}
+
+/// Interpret the lower bits of `value` that fit into `size` as a two's complement number.
+pub(crate) fn as_signed(value: u64, size: InstructionSize) -> i64 {
+    let shift = 64u32.saturating_sub(size.bits());
+    (value << shift) as i64 >> shift
+}
+
+/// Check whether `value` is representable as a signed number of `size`.
+fn fits_signed(value: i128, size: InstructionSize) -> bool {
+    let half = 1i128 << (size.bits().min(64) - 1);
+    -half <= value && value < half
+}
+
+/// Build `op(lhs, rhs)` from two separate expressions.
+fn binary_op(
+    mut lhs: Expression,
+    rhs: &Expression,
+    op: impl FnOnce(OpIdx, OpIdx) -> ExpressionOp,
+) -> Expression {
+    let l = lhs.get_entry_point();
+    lhs.copy_other_to_end(&rhs.0);
+    let r = lhs.get_entry_point();
+    lhs.0.push(op(l, r));
+    lhs
+}
+
+/// Fold `l / r` (or `l % r` if `is_remainder`) of two constants at the given size.
+///
+/// Returns `None` when the result is undefined: division by zero or signed `MIN / -1`.
+fn fold_division(
+    l: u64,
+    r: u64,
+    size: InstructionSize,
+    sgn: SignedOrUnsiged,
+    is_remainder: bool,
+) -> Option<u64> {
+    use SignedOrUnsiged::*;
+    match (size, sgn, is_remainder) {
+        (InstructionSize::U8, Unsigned, false) => (l as u8).checked_div(r as u8).map(|v| v as u64),
+        (InstructionSize::U8, Unsigned, true) => (l as u8).checked_rem(r as u8).map(|v| v as u64),
+        (InstructionSize::U8, Signed, false) => {
+            (l as i8).checked_div(r as i8).map(|v| v as u8 as u64)
+        }
+        (InstructionSize::U8, Signed, true) => {
+            (l as i8).checked_rem(r as i8).map(|v| v as u8 as u64)
+        }
+        (InstructionSize::U16, Unsigned, false) => {
+            (l as u16).checked_div(r as u16).map(|v| v as u64)
+        }
+        (InstructionSize::U16, Unsigned, true) => {
+            (l as u16).checked_rem(r as u16).map(|v| v as u64)
+        }
+        (InstructionSize::U16, Signed, false) => {
+            (l as i16).checked_div(r as i16).map(|v| v as u16 as u64)
+        }
+        (InstructionSize::U16, Signed, true) => {
+            (l as i16).checked_rem(r as i16).map(|v| v as u16 as u64)
+        }
+        (InstructionSize::U32, Unsigned, false) => {
+            (l as u32).checked_div(r as u32).map(|v| v as u64)
+        }
+        (InstructionSize::U32, Unsigned, true) => {
+            (l as u32).checked_rem(r as u32).map(|v| v as u64)
+        }
+        (InstructionSize::U32, Signed, false) => {
+            (l as i32).checked_div(r as i32).map(|v| v as u32 as u64)
+        }
+        (InstructionSize::U32, Signed, true) => {
+            (l as i32).checked_rem(r as i32).map(|v| v as u32 as u64)
+        }
+        (_, Unsigned, false) => l.checked_div(r),
+        (_, Unsigned, true) => l.checked_rem(r),
+        (_, Signed, false) => (l as i64).checked_div(r as i64).map(|v| v as u64),
+        (_, Signed, true) => (l as i64).checked_rem(r as i64).map(|v| v as u64),
     }
 }
 
@@ -626,6 +1154,26 @@ impl Expression {
         result
     }
 
+    /// Build an expression with `op` as the root, applied to `operands`.
+    ///
+    /// The operand indices stored in `op` are ignored, each operand is a separate expression
+    /// given in the order of [`ExpressionOp::operands`]. This is the inverse of splitting an
+    /// expression with [`Expression::get_sub_expression`].
+    ///
+    /// # Arguments
+    /// * `op` - The root operation
+    /// * `operands` - Sub-expressions used as the operands of `op`
+    pub fn from_operands(op: &ExpressionOp, operands: &[Expression]) -> Expression {
+        let mut result = Expression::new();
+        let mut indices: SmallVec<[OpIdx; 2]> = SmallVec::new();
+        for operand in operands {
+            result.copy_other_to_end(&operand.0);
+            indices.push(result.get_entry_point());
+        }
+        result.0.push(op.with_operands(&indices));
+        result
+    }
+
     /// Multiply this expression by another expression with immediate optimization.
     ///
     /// Performs construction-time optimization when possible:
@@ -1092,6 +1640,127 @@ impl Expression {
             _ => cant_optimize(self, value, expr, size),
         }
     }
+
+    /// Divide this expression by another expression with immediate optimization.
+    ///
+    /// If the divisor is a constant, delegates to [`Self::divide_value`].
+    /// Otherwise creates a divide operation.
+    ///
+    /// # Arguments
+    /// * `other` - The divisor
+    /// * `size` - The instruction size for overflow handling
+    /// * `sgn` - Whether to perform signed or unsigned division
+    pub fn divide<S: Into<InstructionSize>>(
+        &mut self,
+        other: &Self,
+        size: S,
+        sgn: SignedOrUnsiged,
+    ) {
+        if let Some(ExpressionOp::Value(v)) = other.root_op() {
+            self.divide_value(*v, size, sgn);
+        } else {
+            let left = self.get_entry_point();
+            self.copy_other_to_end(&other.0);
+            let right = self.get_entry_point();
+            self.0
+                .push(ExpressionOp::Divide(left, right, size.into(), sgn))
+        }
+    }
+
+    /// Divide this expression by a constant value with immediate optimization.
+    ///
+    /// Performs construction-time optimization:
+    /// - `VAR / 1` remains `VAR` (no operation added)
+    /// - `15 / 3` becomes `5`, wrapped to `size` and respecting `sgn`
+    /// - Division by zero is never folded and keeps the divide operation
+    ///
+    /// # Arguments
+    /// * `value` - The constant divisor
+    /// * `size` - The instruction size for overflow handling
+    /// * `sgn` - Whether to perform signed or unsigned division
+    pub fn divide_value<S: Into<InstructionSize>>(
+        &mut self,
+        value: u64,
+        size: S,
+        sgn: SignedOrUnsiged,
+    ) {
+        if value == 1 {
+            return;
+        }
+        let size = size.into();
+        let left = self.get_entry_point();
+        if let ExpressionOp::Value(v) = self[left] {
+            if let Some(result) = fold_division(v, value, size, sgn, false) {
+                self[left] = ExpressionOp::Value(result);
+                return;
+            }
+        }
+        self.0.push(ExpressionOp::Value(value));
+        let right = self.get_entry_point();
+        self.0.push(ExpressionOp::Divide(left, right, size, sgn));
+    }
+
+    /// Calculate the remainder of dividing this expression by another expression.
+    ///
+    /// If the divisor is a constant, delegates to [`Self::remainder_value`].
+    /// Otherwise creates a remainder operation.
+    ///
+    /// # Arguments
+    /// * `other` - The divisor
+    /// * `size` - The instruction size for overflow handling
+    /// * `sgn` - Whether to perform signed or unsigned division
+    pub fn remainder<S: Into<InstructionSize>>(
+        &mut self,
+        other: &Self,
+        size: S,
+        sgn: SignedOrUnsiged,
+    ) {
+        if let Some(ExpressionOp::Value(v)) = other.root_op() {
+            self.remainder_value(*v, size, sgn);
+        } else {
+            let left = self.get_entry_point();
+            self.copy_other_to_end(&other.0);
+            let right = self.get_entry_point();
+            self.0
+                .push(ExpressionOp::Remainder(left, right, size.into(), sgn))
+        }
+    }
+
+    /// Calculate the remainder of dividing this expression by a constant value.
+    ///
+    /// Performs construction-time optimization:
+    /// - `VAR % 1` becomes `0`
+    /// - `17 % 5` becomes `2`, wrapped to `size` and respecting `sgn`
+    /// - Remainder by zero is never folded and keeps the remainder operation
+    ///
+    /// # Arguments
+    /// * `value` - The constant divisor
+    /// * `size` - The instruction size for overflow handling
+    /// * `sgn` - Whether to perform signed or unsigned division
+    pub fn remainder_value<S: Into<InstructionSize>>(
+        &mut self,
+        value: u64,
+        size: S,
+        sgn: SignedOrUnsiged,
+    ) {
+        if value == 1 {
+            self.0.clear();
+            self.0.push(ExpressionOp::Value(0));
+            return;
+        }
+        let size = size.into();
+        let left = self.get_entry_point();
+        if let ExpressionOp::Value(v) = self[left] {
+            if let Some(result) = fold_division(v, value, size, sgn, true) {
+                self[left] = ExpressionOp::Value(result);
+                return;
+            }
+        }
+        self.0.push(ExpressionOp::Value(value));
+        let right = self.get_entry_point();
+        self.0.push(ExpressionOp::Remainder(left, right, size, sgn));
+    }
+
     /// Add a memory dereference operation to this expression.
     ///
     /// Transforms the expression from `E` to `[E]` (memory contents at address E).
@@ -1108,19 +1777,281 @@ impl Expression {
         self.0.push(ExpressionOp::Dereference(val));
     }
 
-    /// Count the number of 1 bits in this expression (population count).
+    /// Count the number of 1 bits in this expression (population count).
+    ///
+    /// For constant values, this is computed immediately. For complex expressions,
+    /// a CountOnes operation is added to the expression tree.
+    ///
+    /// This corresponds to CPU instructions like `POPCNT` on x86.
+    pub fn count_ones(&mut self) {
+        let val = self.get_entry_point();
+        if let ExpressionOp::Value(v) = &self[val] {
+            self[val] = ExpressionOp::Value(v.count_ones() as u64);
+        } else {
+            self.0.push(ExpressionOp::CountOnes(val));
+        }
+    }
+
+    /// Sign-extend this expression from `from` size to `to` size.
+    ///
+    /// Performs construction-time optimization:
+    /// - Constants are extended immediately, e.g. `0x80` from `U8` to `U32` becomes `0xffffff80`
+    /// - Extending to the same size is a no-op
+    /// - `sext(sext(x))` becomes a single extension
+    ///
+    /// # Arguments
+    /// * `from` - Size of this expression
+    /// * `to` - Size of the extended result
+    pub fn sign_extend<F: Into<InstructionSize>, T: Into<InstructionSize>>(
+        &mut self,
+        from: F,
+        to: T,
+    ) {
+        let (from, to) = (from.into(), to.into());
+        if from == to {
+            return;
+        }
+        let pos = self.get_entry_point();
+        match self[pos] {
+            ExpressionOp::Value(v) => {
+                self[pos] = ExpressionOp::Value(to.wrap(as_signed(v, from) as u64))
+            }
+            ExpressionOp::SignExtend(l, inner, _) => {
+                self[pos] = ExpressionOp::SignExtend(l, inner, to)
+            }
+            _ => self.0.push(ExpressionOp::SignExtend(pos, from, to)),
+        }
+    }
+
+    /// Zero-extend this expression from `from` size to `to` size.
+    ///
+    /// Performs construction-time optimization:
+    /// - Constants are extended immediately, e.g. `0x80` from `U8` to `U32` stays `0x80`
+    /// - Extending to the same size is a no-op
+    /// - `zext(zext(x))` becomes a single extension
+    ///
+    /// # Arguments
+    /// * `from` - Size of this expression
+    /// * `to` - Size of the extended result
+    pub fn zero_extend<F: Into<InstructionSize>, T: Into<InstructionSize>>(
+        &mut self,
+        from: F,
+        to: T,
+    ) {
+        let (from, to) = (from.into(), to.into());
+        if from == to {
+            return;
+        }
+        let pos = self.get_entry_point();
+        match self[pos] {
+            ExpressionOp::Value(v) => self[pos] = ExpressionOp::Value(from.wrap(v)),
+            ExpressionOp::ZeroExtend(l, inner, _) => {
+                self[pos] = ExpressionOp::ZeroExtend(l, inner, to)
+            }
+            _ => self.0.push(ExpressionOp::ZeroExtend(pos, from, to)),
+        }
+    }
+
+    /// Truncate this expression from `from` size to `to` size, keeping the lower bytes.
+    ///
+    /// Performs construction-time optimization:
+    /// - Constants are truncated immediately, e.g. `0x1234` from `U16` to `U8` becomes `0x34`
+    /// - Truncating to the same size is a no-op
+    /// - Truncating an extension back to its original size yields the original expression
+    /// - Truncating an extension to a size still above its original size shortens the extension
+    ///
+    /// # Arguments
+    /// * `from` - Size of this expression
+    /// * `to` - Size of the truncated result
+    pub fn truncate<F: Into<InstructionSize>, T: Into<InstructionSize>>(&mut self, from: F, to: T) {
+        let (from, to) = (from.into(), to.into());
+        if from == to {
+            return;
+        }
+        let pos = self.get_entry_point();
+        match self[pos] {
+            ExpressionOp::Value(v) => self[pos] = ExpressionOp::Value(to.wrap(v)),
+            ExpressionOp::SignExtend(l, inner, _) | ExpressionOp::ZeroExtend(l, inner, _)
+                if inner == to && l.as_idx() + 1 == pos.as_idx() =>
+            {
+                // operand of the extension is stored right before it, drop the extension
+                self.0.pop();
+            }
+            ExpressionOp::SignExtend(l, inner, _) if inner.bits() < to.bits() => {
+                self[pos] = ExpressionOp::SignExtend(l, inner, to)
+            }
+            ExpressionOp::ZeroExtend(l, inner, _) if inner.bits() < to.bits() => {
+                self[pos] = ExpressionOp::ZeroExtend(l, inner, to)
+            }
+            ExpressionOp::Truncate(l, inner, _) => self[pos] = ExpressionOp::Truncate(l, inner, to),
+            _ => self.0.push(ExpressionOp::Truncate(pos, from, to)),
+        }
+    }
+
+    /// Reinterpret a constant integer root as the bit pattern of a float constant.
+    ///
+    /// P-code hands float constants over as raw integer bits, this makes them print as decimals.
+    fn as_float_constant(&mut self, precision: FloatPrecision) {
+        if let [ExpressionOp::Value(v)] = self.0[..] {
+            self.0[0] = ExpressionOp::FloatValue(v, precision);
+        }
+    }
+
+    /// Decode this expression as a float constant, if it is one.
+    fn float_constant(&self) -> Option<f64> {
+        match self.0[..] {
+            [ExpressionOp::FloatValue(bits, precision)] => precision.decode(bits),
+            _ => None,
+        }
+    }
+
+    /// Apply a floating-point arithmetic operation: `this op other`.
+    ///
+    /// Integer constant operands are reinterpreted as float bit patterns of `precision`.
+    /// If both operands are constants of single or double precision, the result is computed immediately.
+    ///
+    /// # Arguments
+    /// * `other` - Right-hand side of the operation
+    /// * `op` - The arithmetic operator
+    /// * `precision` - Precision of the operands and the result
+    pub fn float_binary<P: Into<FloatPrecision>>(
+        &mut self,
+        other: &Self,
+        op: FloatBinaryOp,
+        precision: P,
+    ) {
+        let precision = precision.into();
+        let mut other = other.clone();
+        self.as_float_constant(precision);
+        other.as_float_constant(precision);
+        if let (Some(l), Some(r)) = (self.float_constant(), other.float_constant()) {
+            if let Some(bits) = precision.encode(op.apply(l, r)) {
+                self.0[0] = ExpressionOp::FloatValue(bits, precision);
+                return;
+            }
+        }
+        let left = self.get_entry_point();
+        self.copy_other_to_end(&other.0);
+        let right = self.get_entry_point();
+        self.0
+            .push(ExpressionOp::FloatBinary(left, right, op, precision));
+    }
+
+    /// Apply a unary floating-point operation, e.g. `-this` or `sqrt(this)`.
+    ///
+    /// Constant operands of single or double precision are computed immediately,
+    /// [`FloatUnaryOp::IsNan`] of a constant becomes an integer `0` or `1`.
+    ///
+    /// # Arguments
+    /// * `op` - The unary operator
+    /// * `precision` - Precision of the operand
+    pub fn float_unary<P: Into<FloatPrecision>>(&mut self, op: FloatUnaryOp, precision: P) {
+        let precision = precision.into();
+        self.as_float_constant(precision);
+        if let Some(v) = self.float_constant() {
+            if op == FloatUnaryOp::IsNan {
+                self.0[0] = ExpressionOp::Value(v.is_nan() as u64);
+                return;
+            }
+            if let Some(bits) = precision.encode(op.apply(v)) {
+                self.0[0] = ExpressionOp::FloatValue(bits, precision);
+                return;
+            }
+        }
+        let pos = self.get_entry_point();
+        self.0.push(ExpressionOp::FloatUnary(pos, op, precision));
+    }
+
+    /// Create a floating-point comparison: `this op other`.
+    ///
+    /// Comparisons of two constants are evaluated immediately into an integer `0` or `1`.
+    ///
+    /// # Arguments
+    /// * `other` - Right-hand side of the comparison
+    /// * `op` - The comparison operator
+    /// * `precision` - Precision of both operands
+    pub fn float_compare<P: Into<FloatPrecision>>(
+        &mut self,
+        other: &Self,
+        op: FloatCompareOp,
+        precision: P,
+    ) {
+        let precision = precision.into();
+        let mut other = other.clone();
+        self.as_float_constant(precision);
+        other.as_float_constant(precision);
+        if let (Some(l), Some(r)) = (self.float_constant(), other.float_constant()) {
+            self.0[0] = ExpressionOp::Value(op.apply(l, r) as u64);
+            return;
+        }
+        let left = self.get_entry_point();
+        self.copy_other_to_end(&other.0);
+        let right = self.get_entry_point();
+        self.0
+            .push(ExpressionOp::FloatCompare(left, right, op, precision));
+    }
+
+    /// Convert this integer expression of `size` to a float of `precision`.
+    ///
+    /// Constants are converted immediately when the result is single or double precision.
+    pub fn int_to_float<S: Into<InstructionSize>, P: Into<FloatPrecision>>(
+        &mut self,
+        size: S,
+        sgn: SignedOrUnsiged,
+        precision: P,
+    ) {
+        let (size, precision) = (size.into(), precision.into());
+        let pos = self.get_entry_point();
+        if let ExpressionOp::Value(v) = self[pos] {
+            let value = match sgn {
+                SignedOrUnsiged::Signed => as_signed(v, size) as f64,
+                SignedOrUnsiged::Unsigned => size.wrap(v) as f64,
+            };
+            if let Some(bits) = precision.encode(value) {
+                self[pos] = ExpressionOp::FloatValue(bits, precision);
+                return;
+            }
+        }
+        self.0.push(ExpressionOp::IntToFloat(pos, sgn, precision));
+    }
+
+    /// Convert this float expression from `from` precision to `to` precision.
     ///
-    /// For constant values, this is computed immediately. For complex expressions,
-    /// a CountOnes operation is added to the expression tree.
+    /// Converting to the same precision is a no-op, constants are converted immediately when possible.
+    pub fn float_to_float<F: Into<FloatPrecision>, T: Into<FloatPrecision>>(
+        &mut self,
+        from: F,
+        to: T,
+    ) {
+        let (from, to) = (from.into(), to.into());
+        self.as_float_constant(from);
+        if from == to {
+            return;
+        }
+        if let Some(bits) = self.float_constant().and_then(|v| to.encode(v)) {
+            self.0[0] = ExpressionOp::FloatValue(bits, to);
+            return;
+        }
+        let pos = self.get_entry_point();
+        self.0.push(ExpressionOp::FloatToFloat(pos, from, to));
+    }
+
+    /// Convert this float expression of `precision` to a signed integer of `size`, truncating towards zero.
     ///
-    /// This corresponds to CPU instructions like `POPCNT` on x86.
-    pub fn count_ones(&mut self) {
-        let val = self.get_entry_point();
-        if let ExpressionOp::Value(v) = &self[val] {
-            self[val] = ExpressionOp::Value(v.count_ones() as u64);
-        } else {
-            self.0.push(ExpressionOp::CountOnes(val));
+    /// Constants are converted immediately.
+    pub fn float_to_int<P: Into<FloatPrecision>, S: Into<InstructionSize>>(
+        &mut self,
+        precision: P,
+        size: S,
+    ) {
+        let (precision, size) = (precision.into(), size.into());
+        self.as_float_constant(precision);
+        if let Some(v) = self.float_constant() {
+            self.0[0] = ExpressionOp::Value(size.wrap(v as i64 as u64));
+            return;
         }
+        let pos = self.get_entry_point();
+        self.0.push(ExpressionOp::FloatToInt(pos, size));
     }
 
     /// Add an overflow check for this expression.
@@ -1136,6 +2067,80 @@ impl Expression {
         self.0.push(ExpressionOp::Overflow(val, sgn));
     }
 
+    /// Compute the carry out of the addition `this + other`.
+    ///
+    /// For [`SignedOrUnsiged::Unsigned`] this is the carry flag of the addition,
+    /// for [`SignedOrUnsiged::Signed`] it is the signed overflow flag.
+    ///
+    /// Performs construction-time optimization:
+    /// - Constant operands are evaluated immediately
+    /// - Adding `0` never carries
+    /// - The unsigned carry of `x + c` becomes the comparison `x > ~c`
+    ///
+    /// # Arguments
+    /// * `other` - Right-hand side of the addition
+    /// * `size` - Instruction size of the addition
+    /// * `sgn` - Whether to compute the unsigned carry or the signed overflow
+    pub fn carry<S: Into<InstructionSize>>(
+        &mut self,
+        other: &Expression,
+        size: S,
+        sgn: SignedOrUnsiged,
+    ) {
+        let size = size.into();
+        match (self.root_op(), other.root_op()) {
+            (Some(ExpressionOp::Value(l)), Some(ExpressionOp::Value(r))) => {
+                let carried = match sgn {
+                    SignedOrUnsiged::Unsigned => size.wrap(l.wrapping_add(*r)) < size.wrap(*l),
+                    SignedOrUnsiged::Signed => !fits_signed(
+                        as_signed(*l, size) as i128 + as_signed(*r, size) as i128,
+                        size,
+                    ),
+                };
+                *self = Expression::from(carried as u64);
+            }
+            (_, Some(ExpressionOp::Value(0))) => *self = Expression::from(0),
+            (_, Some(ExpressionOp::Value(c))) if sgn == SignedOrUnsiged::Unsigned => {
+                let limit = Expression::from(size.wrap(!*c));
+                *self = binary_op(self.clone(), &limit, |l, r| {
+                    ExpressionOp::Greater(l, r, SignedOrUnsiged::Unsigned)
+                });
+            }
+            _ => {
+                *self = binary_op(self.clone(), other, |l, r| {
+                    ExpressionOp::Carry(l, r, size, sgn)
+                })
+            }
+        }
+    }
+
+    /// Compute the signed overflow of the subtraction `this - other`.
+    ///
+    /// Performs construction-time optimization:
+    /// - Constant operands are evaluated immediately
+    /// - Subtracting `0` never overflows
+    ///
+    /// Comparing the result against the sign of `this - other` is recognised by
+    /// [`Expression::check_equals`] and [`Expression::check_not_equals`] as a signed comparison.
+    ///
+    /// # Arguments
+    /// * `other` - Right-hand side of the subtraction
+    /// * `size` - Instruction size of the subtraction
+    pub fn signed_borrow<S: Into<InstructionSize>>(&mut self, other: &Expression, size: S) {
+        let size = size.into();
+        match (self.root_op(), other.root_op()) {
+            (Some(ExpressionOp::Value(l)), Some(ExpressionOp::Value(r))) => {
+                let borrowed = !fits_signed(
+                    as_signed(*l, size) as i128 - as_signed(*r, size) as i128,
+                    size,
+                );
+                *self = Expression::from(borrowed as u64);
+            }
+            (_, Some(ExpressionOp::Value(0))) => *self = Expression::from(0),
+            _ => *self = binary_op(self.clone(), other, |l, r| ExpressionOp::Borrow(l, r, size)),
+        }
+    }
+
     /// Perform a right bit shift by a constant value.
     ///
     /// Shifts this expression right by `value` bits. This is equivalent to
@@ -1187,6 +2192,11 @@ impl Expression {
                 *self = simplified;
                 return;
             }
+            // Pattern: (x != y) & (x >= y) → x > y
+            if let Some(merged) = Self::try_merge_comparisons(self, other, false) {
+                *self = merged;
+                return;
+            }
 
             let left = self.get_entry_point();
             if let Some(ExpressionOp::Value(v)) = other.root_op() {
@@ -1267,6 +2277,74 @@ impl Expression {
         None
     }
 
+    /// Try to merge a (in)equality with an ordered comparison of the same operands.
+    ///
+    /// Flag-based conditions combine the zero flag with a carry or sign test, e.g. `ja`
+    /// reads as `!CF & !ZF`. Recognized patterns:
+    /// - `(x != y) & (x >= y)` → `x > y` and `(x != y) & (x <= y)` → `x < y`
+    /// - `(x == y) | (x < y)` → `x <= y` and `(x == y) | (x > y)` → `x >= y`
+    ///
+    /// # Arguments
+    /// * `lhs` - Left side of the operation
+    /// * `rhs` - Right side of the operation
+    /// * `is_or` - Whether the operation is OR, otherwise it's AND
+    fn try_merge_comparisons(
+        lhs: &Expression,
+        rhs: &Expression,
+        is_or: bool,
+    ) -> Option<Expression> {
+        type Comparison = fn(OpIdx, OpIdx, SignedOrUnsiged) -> ExpressionOp;
+        for (eq, cmp) in [(lhs, rhs), (rhs, lhs)] {
+            let (eq_a, eq_b) = match (eq.root_op()?, is_or) {
+                (ExpressionOp::NotEquals(a, b, _), false)
+                | (ExpressionOp::Equals(a, b, _), true) => (*a, *b),
+                _ => continue,
+            };
+            let (cmp_a, cmp_b, sgn, merged): (_, _, _, Comparison) = match (cmp.root_op()?, is_or) {
+                (ExpressionOp::GreaterOrEquals(a, b, sgn), false) => {
+                    (a, b, sgn, ExpressionOp::Greater)
+                }
+                (ExpressionOp::LessOrEquals(a, b, sgn), false) => (a, b, sgn, ExpressionOp::Less),
+                (ExpressionOp::Less(a, b, sgn), true) => (a, b, sgn, ExpressionOp::LessOrEquals),
+                (ExpressionOp::Greater(a, b, sgn), true) => {
+                    (a, b, sgn, ExpressionOp::GreaterOrEquals)
+                }
+                _ => continue,
+            };
+            let a = cmp.get_sub_expression(*cmp_a);
+            let b = cmp.get_sub_expression(*cmp_b);
+            if a == eq.get_sub_expression(eq_a) && b == eq.get_sub_expression(eq_b) {
+                let sgn = *sgn;
+                return Some(binary_op(a, &b, |l, r| merged(l, r, sgn)));
+            }
+        }
+        None
+    }
+
+    /// Match the overflow flag of a subtraction against its sign flag.
+    ///
+    /// Recognizes `sborrow(x, y)` on one side and `x < y` (signed), which is how the sign of
+    /// `x - y` reads, on the other side. Returns `(x, y)` if the pattern matches.
+    fn match_borrow_and_sign(
+        lhs: &Expression,
+        rhs: &Expression,
+    ) -> Option<(Expression, Expression)> {
+        for (borrow, sign) in [(lhs, rhs), (rhs, lhs)] {
+            if let (
+                Some(ExpressionOp::Borrow(borrow_a, borrow_b, _)),
+                Some(ExpressionOp::Less(sign_a, sign_b, SignedOrUnsiged::Signed)),
+            ) = (borrow.root_op(), sign.root_op())
+            {
+                let a = borrow.get_sub_expression(*borrow_a);
+                let b = borrow.get_sub_expression(*borrow_b);
+                if a == sign.get_sub_expression(*sign_a) && b == sign.get_sub_expression(*sign_b) {
+                    return Some((a, b));
+                }
+            }
+        }
+        None
+    }
+
     /// Perform bitwise OR with another expression.
     ///
     /// If both expressions are constant values, the OR is computed immediately.
@@ -1275,6 +2353,11 @@ impl Expression {
     /// # Arguments
     /// * `other` - The expression to OR with this one
     pub fn or(&mut self, other: &Expression) {
+        // Pattern: (x == y) | (x < y) → x <= y
+        if let Some(merged) = Self::try_merge_comparisons(self, other, true) {
+            *self = merged;
+            return;
+        }
         let left = self.get_entry_point();
         if let Some(ExpressionOp::Value(v)) = other.root_op() {
             if let ExpressionOp::Value(me) = &self[left] {
@@ -1294,6 +2377,11 @@ impl Expression {
         if self.eq(&other) {
             self.0.clear();
             self.0.push(ExpressionOp::Value(0))
+        } else if let Some((a, b)) = Self::match_borrow_and_sign(self, other) {
+            // `OF ^ SF` after `a - b` is the signed `a < b`
+            *self = binary_op(a, &b, |l, r| {
+                ExpressionOp::Less(l, r, SignedOrUnsiged::Signed)
+            });
         } else {
             let left = self.get_entry_point();
             self.copy_other_to_end(&other.0);
@@ -1302,6 +2390,43 @@ impl Expression {
         }
     }
 
+    /// Combine two conditions with a short-circuiting AND: `self && other`.
+    ///
+    /// A constant operand decides the result (false) or drops out (true), and
+    /// `A && A` stays `A`.
+    pub fn logical_and(&mut self, other: &Expression) {
+        self.logical_op(other, false, ExpressionOp::LogicalAnd);
+    }
+
+    /// Combine two conditions with a short-circuiting OR: `self || other`.
+    ///
+    /// A constant operand decides the result (true) or drops out (false), and
+    /// `A || A` stays `A`.
+    pub fn logical_or(&mut self, other: &Expression) {
+        self.logical_op(other, true, ExpressionOp::LogicalOr);
+    }
+
+    /// `absorbing` is the truth value that decides `op` on its own.
+    fn logical_op(
+        &mut self,
+        other: &Expression,
+        absorbing: bool,
+        op: impl FnOnce(OpIdx, OpIdx) -> ExpressionOp,
+    ) {
+        let constant = |e: &Expression| match e.root_op() {
+            Some(ExpressionOp::Value(v)) => Some(*v != 0),
+            _ => None,
+        };
+        match (constant(self), constant(other)) {
+            (Some(me), _) if me == absorbing => {}
+            (Some(_), _) => *self = other.clone(),
+            (_, Some(v)) if v == absorbing => *self = Expression::from(absorbing as u64),
+            (_, Some(_)) => {}
+            _ if self == other => {}
+            _ => *self = binary_op(std::mem::take(self), other, op),
+        }
+    }
+
     /// Remove the most recent dereference operation from this expression.
     ///
     /// This undoes a `dereference()` call, transforming `[E]` back to `E`.
@@ -1351,7 +2476,9 @@ impl Expression {
     /// Create an equality comparison: `this == other`.
     ///
     /// If the other expression is a constant, delegates to `check_equals_value`
-    /// for potential optimization. Otherwise creates an Equals operation.
+    /// for potential optimization. Comparing the overflow flag of `a - b` with its
+    /// sign flag, `sborrow(a, b) == (a < b)`, becomes the signed `a >= b`.
+    /// Otherwise creates an Equals operation.
     ///
     /// # Arguments
     /// * `other` - Expression to compare with
@@ -1363,7 +2490,11 @@ impl Expression {
         size: S,
         sgn: SignedOrUnsiged,
     ) {
-        if let Some(ExpressionOp::Value(v)) = other.root_op() {
+        if let Some((a, b)) = Self::match_borrow_and_sign(self, other) {
+            *self = binary_op(a, &b, |l, r| {
+                ExpressionOp::GreaterOrEquals(l, r, SignedOrUnsiged::Signed)
+            });
+        } else if let Some(ExpressionOp::Value(v)) = other.root_op() {
             self.check_equals_value(*v, size, sgn);
         } else if let Some(ExpressionOp::Value(v)) = self.root_op() {
             // self is a simple value but other is not - swap for better optimization
@@ -1412,6 +2543,41 @@ impl Expression {
         }
     }
 
+    /// Create an inequality comparison: `this != other`.
+    ///
+    /// If either expression is a constant, delegates to `check_not_equals_value`
+    /// for potential optimization. Comparing the overflow flag of `a - b` with its
+    /// sign flag, `sborrow(a, b) != (a < b)`, becomes the signed `a < b`.
+    /// Otherwise creates a NotEquals operation.
+    ///
+    /// # Arguments
+    /// * `other` - Expression to compare with
+    /// * `size` - Instruction size for the comparison
+    /// * `sgn` - Whether to treat operands as signed or unsigned
+    pub fn check_not_equals<S: Into<InstructionSize>>(
+        &mut self,
+        other: &Expression,
+        size: S,
+        sgn: SignedOrUnsiged,
+    ) {
+        if let Some((a, b)) = Self::match_borrow_and_sign(self, other) {
+            *self = binary_op(a, &b, |l, r| {
+                ExpressionOp::Less(l, r, SignedOrUnsiged::Signed)
+            });
+        } else if let Some(ExpressionOp::Value(v)) = other.root_op() {
+            self.check_not_equals_value(*v, size.into(), sgn);
+        } else if let Some(ExpressionOp::Value(v)) = self.root_op() {
+            let temp_v = *v;
+            self.0.clear();
+            self.copy_other_to_end(&other.0);
+            self.check_not_equals_value(temp_v, size.into(), sgn);
+        } else {
+            *self = binary_op(self.clone(), other, |l, r| {
+                ExpressionOp::NotEquals(l, r, sgn)
+            });
+        }
+    }
+
     /// Create an inequality comparison with a constant: `this != value`.
     ///
     /// Uses algebraic optimization by transforming to `(this - value) != 0`,
@@ -1483,8 +2649,9 @@ impl Expression {
 
     /// Create a less-than comparison with a constant: `this < value`.
     ///
-    /// Uses algebraic optimization by transforming to `(this - value) < 0`,
-    /// which is equivalent to `(this - value) >= 0` for the reverse comparison.
+    /// Uses algebraic optimization by folding `value` into `this`: if `this - value`
+    /// becomes `l - r`, the result is `l < r`. This is also how the sign flag of a
+    /// subtraction, `(l - r) < 0`, reads as `l < r`.
     /// If this results in a constant expression, the comparison is evaluated immediately.
     ///
     /// # Arguments
@@ -1500,7 +2667,7 @@ impl Expression {
         self.sub_value(value, size);
         let left = self.get_entry_point();
         match self[left] {
-            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::GreaterOrEquals(l, r, sgn),
+            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::Less(l, r, sgn),
             ExpressionOp::Value(v) => self[left] = ExpressionOp::Value(((v as i64) < 0) as u64),
             _ => {
                 self.0.push(ExpressionOp::Value(value));
@@ -1515,8 +2682,8 @@ impl Expression {
 
     /// Create a greater-than comparison with a constant: `this > value`.
     ///
-    /// Uses algebraic optimization by transforming to `(this - value) > 0`,
-    /// which is equivalent to `(this - value) <= 0` for the reverse comparison.
+    /// Uses algebraic optimization by folding `value` into `this`: if `this - value`
+    /// becomes `l - r`, the result is `l > r`.
     /// If this results in a constant expression, the comparison is evaluated immediately.
     ///
     /// # Arguments
@@ -1527,7 +2694,7 @@ impl Expression {
         self.sub_value(value, size);
         let left = self.get_entry_point();
         match self[left] {
-            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::LessOrEquals(l, r, sgn),
+            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::Greater(l, r, sgn),
             ExpressionOp::Value(v) => self[left] = ExpressionOp::Value((v > 0) as u64),
             _ => {
                 self.0.push(ExpressionOp::Value(value));
@@ -1542,8 +2709,8 @@ impl Expression {
 
     /// Create a less-than-or-equal comparison with a constant: `this <= value`.
     ///
-    /// Uses algebraic optimization by transforming to `(this - value) <= 0`,
-    /// which is equivalent to `(this - value) > 0` for the reverse comparison.
+    /// Uses algebraic optimization by folding `value` into `this`: if `this - value`
+    /// becomes `l - r`, the result is `l <= r`.
     /// If this results in a constant expression, the comparison is evaluated immediately.
     ///
     /// # Arguments
@@ -1559,7 +2726,7 @@ impl Expression {
         self.sub_value(value, size);
         let left = self.get_entry_point();
         match self[left] {
-            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::Greater(l, r, sgn),
+            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::LessOrEquals(l, r, sgn),
             ExpressionOp::Value(v) => self[left] = ExpressionOp::Value(((v as i64) <= 0) as u64),
             _ => {
                 self.0.push(ExpressionOp::Value(value));
@@ -1574,8 +2741,8 @@ impl Expression {
 
     /// Create a greater-than-or-equal comparison with a constant: `this >= value`.
     ///
-    /// Uses algebraic optimization by transforming to `(this - value) >= 0`,
-    /// which is equivalent to `(this - value) < 0` for the reverse comparison.
+    /// Uses algebraic optimization by folding `value` into `this`: if `this - value`
+    /// becomes `l - r`, the result is `l >= r`.
     /// If this results in a constant expression, the comparison is evaluated immediately.
     ///
     /// # Arguments
@@ -1591,7 +2758,7 @@ impl Expression {
         self.sub_value(value, size);
         let left = self.get_entry_point();
         match self[left] {
-            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::Less(l, r, sgn),
+            ExpressionOp::Sub(l, r, _size) => self[left] = ExpressionOp::GreaterOrEquals(l, r, sgn),
             ExpressionOp::Value(v) => self[left] = ExpressionOp::Value(((v as i64) >= 0) as u64),
             _ => {
                 self.0.push(ExpressionOp::Value(value));
@@ -1633,6 +2800,27 @@ impl Expression {
             ExpressionOp::NotEquals(l, r, sgn) => {
                 self[pos] = ExpressionOp::Equals(l, r, sgn);
             }
+            // ordered float comparisons are false on NaN, so only (in)equality can be inverted
+            ExpressionOp::FloatCompare(l, r, FloatCompareOp::Equals, precision) => {
+                self[pos] = ExpressionOp::FloatCompare(l, r, FloatCompareOp::NotEquals, precision);
+            }
+            ExpressionOp::FloatCompare(l, r, FloatCompareOp::NotEquals, precision) => {
+                self[pos] = ExpressionOp::FloatCompare(l, r, FloatCompareOp::Equals, precision);
+            }
+            // De Morgan: `!(A && B)` is `!A || !B` and `!(A || B)` is `!A && !B`
+            ExpressionOp::LogicalAnd(l, r) | ExpressionOp::LogicalOr(l, r) => {
+                let is_and = matches!(self[pos], ExpressionOp::LogicalAnd(..));
+                let mut lhs = self.get_sub_expression(l);
+                let mut rhs = self.get_sub_expression(r);
+                lhs.not();
+                rhs.not();
+                if is_and {
+                    lhs.logical_or(&rhs);
+                } else {
+                    lhs.logical_and(&rhs);
+                }
+                *self = lhs;
+            }
 
             _ => self.0.push(ExpressionOp::Not(pos)),
         }
@@ -1645,10 +2833,30 @@ impl Expression {
         lang: Option<&SleighLanguage>,
     ) -> std::fmt::Result {
         let my_p = self.get_precesense(idx);
-        let is_draw_paren = self.has_higher_precedence(idx, my_p);
+        // a call like `sqrt(x)` is delimited by its own parentheses
+        let is_call = matches!(
+            &self[idx],
+            ExpressionOp::FloatUnary(_, op, _) if op.function_name().is_some()
+        );
+        let is_draw_paren = !is_call && self.has_higher_precedence(idx, my_p);
         if is_draw_paren {
             f.write_str("(")?;
         }
+        self.print_operation(idx, f, lang)?;
+        if is_draw_paren {
+            f.write_str(")")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Print the operation at `idx` without the parentheses around it.
+    fn print_operation(
+        &self,
+        idx: OpIdx,
+        f: &mut std::fmt::Formatter<'_>,
+        lang: Option<&SleighLanguage>,
+    ) -> std::fmt::Result {
         match &self[idx] {
             ExpressionOp::Variable(variable) => variable.display_fmt(lang, f),
             ExpressionOp::DestinationRegister(register) => {
@@ -1675,6 +2883,23 @@ impl Expression {
                 self.recursive_print(*idx, f, lang)?;
                 f.write_str(")")
             }
+            ExpressionOp::Carry(l_idx, r_idx, _, sgn) => {
+                f.write_str(match sgn {
+                    SignedOrUnsiged::Signed => "scarry(",
+                    SignedOrUnsiged::Unsigned => "carry(",
+                })?;
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(", ")?;
+                self.recursive_print(*r_idx, f, lang)?;
+                f.write_str(")")
+            }
+            ExpressionOp::Borrow(l_idx, r_idx, _) => {
+                f.write_str("sborrow(")?;
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(", ")?;
+                self.recursive_print(*r_idx, f, lang)?;
+                f.write_str(")")
+            }
             ExpressionOp::Overflow(idx, _) => {
                 f.write_str("overflow(")?;
                 self.recursive_print(*idx, f, lang)?;
@@ -1685,6 +2910,56 @@ impl Expression {
                 self.recursive_print(*idx, f, lang)?;
                 f.write_str(")")
             }
+            ExpressionOp::SignExtend(idx, _, to) => {
+                f.write_fmt(format_args!(
+                    "({})",
+                    to.c_type_name(SignedOrUnsiged::Signed)
+                ))?;
+                self.recursive_print(*idx, f, lang)
+            }
+            ExpressionOp::ZeroExtend(idx, _, to) | ExpressionOp::Truncate(idx, _, to) => {
+                f.write_fmt(format_args!(
+                    "({})",
+                    to.c_type_name(SignedOrUnsiged::Unsigned)
+                ))?;
+                self.recursive_print(*idx, f, lang)
+            }
+            ExpressionOp::FloatValue(bits, precision) => {
+                f.write_str(&precision.format_constant(*bits))
+            }
+            ExpressionOp::FloatBinary(l_idx, r_idx, op, _) => {
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(op.symbol())?;
+                self.recursive_print(*r_idx, f, lang)
+            }
+            ExpressionOp::FloatCompare(l_idx, r_idx, op, _) => {
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(op.symbol())?;
+                self.recursive_print(*r_idx, f, lang)
+            }
+            ExpressionOp::FloatUnary(idx, op, _) => match op.function_name() {
+                Some(name) => {
+                    f.write_fmt(format_args!("{name}("))?;
+                    self.print_operation(*idx, f, lang)?;
+                    f.write_str(")")
+                }
+                None => {
+                    f.write_str("-")?;
+                    self.recursive_print(*idx, f, lang)
+                }
+            },
+            ExpressionOp::IntToFloat(idx, _, precision)
+            | ExpressionOp::FloatToFloat(idx, _, precision) => {
+                f.write_fmt(format_args!("({})", precision.c_type_name()))?;
+                self.recursive_print(*idx, f, lang)
+            }
+            ExpressionOp::FloatToInt(idx, size) => {
+                f.write_fmt(format_args!(
+                    "({})",
+                    size.c_type_name(SignedOrUnsiged::Signed)
+                ))?;
+                self.recursive_print(*idx, f, lang)
+            }
             ExpressionOp::Assign(l_idx, r_idx) => {
                 self.recursive_print(*l_idx, f, lang)?;
                 f.write_str(" := ")?;
@@ -1721,6 +2996,16 @@ impl Expression {
                 f.write_str(" * ")?;
                 self.recursive_print(*r_idx, f, lang)
             }
+            ExpressionOp::Divide(l_idx, r_idx, _, _) => {
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(" / ")?;
+                self.recursive_print(*r_idx, f, lang)
+            }
+            ExpressionOp::Remainder(l_idx, r_idx, _, _) => {
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(" % ")?;
+                self.recursive_print(*r_idx, f, lang)
+            }
             ExpressionOp::LessOrEquals(l_idx, r_idx, _) => {
                 self.recursive_print(*l_idx, f, lang)?;
                 f.write_str(" <= ")?;
@@ -1771,11 +3056,16 @@ impl Expression {
                 f.write_str(" | ")?;
                 self.recursive_print(*r_idx, f, lang)
             }
-        }?;
-        if is_draw_paren {
-            f.write_str(")")
-        } else {
-            Ok(())
+            ExpressionOp::LogicalAnd(l_idx, r_idx) => {
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(" && ")?;
+                self.recursive_print(*r_idx, f, lang)
+            }
+            ExpressionOp::LogicalOr(l_idx, r_idx) => {
+                self.recursive_print(*l_idx, f, lang)?;
+                f.write_str(" || ")?;
+                self.recursive_print(*r_idx, f, lang)
+            }
         }
     }
 
@@ -1784,14 +3074,29 @@ impl Expression {
             ExpressionOp::DestinationRegister(_)
             | ExpressionOp::Value(_)
             | ExpressionOp::Overflow(_, _)
+            | ExpressionOp::Carry(_, _, _, _)
+            | ExpressionOp::Borrow(_, _, _)
             | ExpressionOp::CountOnes(_)
+            | ExpressionOp::SignExtend(_, _, _)
+            | ExpressionOp::ZeroExtend(_, _, _)
+            | ExpressionOp::Truncate(_, _, _)
+            | ExpressionOp::FloatValue(_, _)
+            | ExpressionOp::FloatUnary(_, _, _)
+            | ExpressionOp::IntToFloat(_, _, _)
+            | ExpressionOp::FloatToFloat(_, _, _)
+            | ExpressionOp::FloatToInt(_, _)
             | ExpressionOp::Assign(_, _)
             | ExpressionOp::Dereference(_)
             | ExpressionOp::Interrupt(_)
             | ExpressionOp::Variable(_) => 0,
             ExpressionOp::Multiequals(_, _) => 10,
-            ExpressionOp::Add(_, _, _) | ExpressionOp::Sub(_, _, _) => 1,
-            ExpressionOp::Multiply(_, _, _) => 2,
+            ExpressionOp::Add(_, _, _)
+            | ExpressionOp::Sub(_, _, _)
+            | ExpressionOp::FloatBinary(_, _, FloatBinaryOp::Add | FloatBinaryOp::Sub, _) => 1,
+            ExpressionOp::Multiply(_, _, _)
+            | ExpressionOp::Divide(_, _, _, _)
+            | ExpressionOp::Remainder(_, _, _, _)
+            | ExpressionOp::FloatBinary(_, _, _, _) => 2,
             ExpressionOp::Less(_, _, _)
             | ExpressionOp::GreaterOrEquals(_, _, _)
             | ExpressionOp::Greater(_, _, _)
@@ -1799,11 +3104,14 @@ impl Expression {
             | ExpressionOp::NotEquals(_, _, _)
             | ExpressionOp::BitShiftRight(_, _, _)
             | ExpressionOp::BitShiftLeft(_, _, _)
+            | ExpressionOp::FloatCompare(_, _, _, _)
             | ExpressionOp::LessOrEquals(_, _, _) => 3,
             ExpressionOp::Or(_, _)
             | ExpressionOp::And(_, _)
             | ExpressionOp::Not(_)
             | ExpressionOp::Xor(_, _) => 4,
+            ExpressionOp::LogicalAnd(_, _) => 5,
+            ExpressionOp::LogicalOr(_, _) => 6,
         }
     }
 
@@ -1815,16 +3123,30 @@ impl Expression {
             match &self[start] {
                 ExpressionOp::DestinationRegister(_)
                 | ExpressionOp::Value(_)
+                | ExpressionOp::FloatValue(_, _)
                 | ExpressionOp::Variable(_) => false,
                 ExpressionOp::Dereference(l)
                 | ExpressionOp::Interrupt(l)
                 | ExpressionOp::CountOnes(l)
                 | ExpressionOp::Overflow(l, _)
+                | ExpressionOp::SignExtend(l, _, _)
+                | ExpressionOp::ZeroExtend(l, _, _)
+                | ExpressionOp::Truncate(l, _, _)
+                | ExpressionOp::FloatUnary(l, _, _)
+                | ExpressionOp::IntToFloat(l, _, _)
+                | ExpressionOp::FloatToFloat(l, _, _)
+                | ExpressionOp::FloatToInt(l, _)
                 | ExpressionOp::Not(l) => self.has_higher_precedence(*l, my_p),
                 ExpressionOp::Multiequals(l, r)
+                | ExpressionOp::FloatBinary(l, r, _, _)
+                | ExpressionOp::FloatCompare(l, r, _, _)
                 | ExpressionOp::Assign(l, r)
                 | ExpressionOp::Add(l, r, _)
                 | ExpressionOp::Multiply(l, r, _)
+                | ExpressionOp::Divide(l, r, _, _)
+                | ExpressionOp::Remainder(l, r, _, _)
+                | ExpressionOp::Carry(l, r, _, _)
+                | ExpressionOp::Borrow(l, r, _)
                 | ExpressionOp::Less(l, r, _)
                 | ExpressionOp::GreaterOrEquals(l, r, _)
                 | ExpressionOp::Greater(l, r, _)
@@ -1836,6 +3158,8 @@ impl Expression {
                 | ExpressionOp::Or(l, r)
                 | ExpressionOp::And(l, r)
                 | ExpressionOp::Xor(l, r)
+                | ExpressionOp::LogicalAnd(l, r)
+                | ExpressionOp::LogicalOr(l, r)
                 | ExpressionOp::Sub(l, r, _) => {
                     self.has_higher_precedence(*l, my_p) || self.has_higher_precedence(*r, my_p)
                 }
@@ -1863,11 +3187,24 @@ impl Expression {
                 Interrupt(p) => Interrupt(s(p)),
                 Overflow(p, sgn) => Overflow(s(p), *sgn),
                 CountOnes(p) => CountOnes(s(p)),
+                SignExtend(p, from, to) => SignExtend(s(p), *from, *to),
+                ZeroExtend(p, from, to) => ZeroExtend(s(p), *from, *to),
+                Truncate(p, from, to) => Truncate(s(p), *from, *to),
+                FloatUnary(p, op, precision) => FloatUnary(s(p), *op, *precision),
+                IntToFloat(p, sgn, precision) => IntToFloat(s(p), *sgn, *precision),
+                FloatToFloat(p, from, to) => FloatToFloat(s(p), *from, *to),
+                FloatToInt(p, size) => FloatToInt(s(p), *size),
+                FloatBinary(l, r, op, precision) => FloatBinary(s(l), s(r), *op, *precision),
+                FloatCompare(l, r, op, precision) => FloatCompare(s(l), s(r), *op, *precision),
                 Assign(l, r) => Assign(s(l), s(r)),
                 Multiequals(l, r) => Multiequals(s(l), s(r)),
                 Add(l, r, size) => Add(s(l), s(r), *size),
                 Sub(l, r, size) => Sub(s(l), s(r), *size),
                 Multiply(l, r, size) => Multiply(s(l), s(r), *size),
+                Divide(l, r, size, sgn) => Divide(s(l), s(r), *size, *sgn),
+                Remainder(l, r, size, sgn) => Remainder(s(l), s(r), *size, *sgn),
+                Carry(l, r, size, sgn) => Carry(s(l), s(r), *size, *sgn),
+                Borrow(l, r, size) => Borrow(s(l), s(r), *size),
                 LessOrEquals(l, r, sgn) => LessOrEquals(s(l), s(r), *sgn),
                 Less(l, r, sgn) => Less(s(l), s(r), *sgn),
                 GreaterOrEquals(l, r, sgn) => GreaterOrEquals(s(l), s(r), *sgn),
@@ -1880,7 +3217,12 @@ impl Expression {
                 Or(l, r) => Or(s(l), s(r)),
                 Not(l) => Not(s(l)),
                 Xor(l, r) => Xor(s(l), s(r)),
-                a @ Variable(_) | a @ DestinationRegister(_) | a @ Value(_) => a.clone(),
+                LogicalAnd(l, r) => LogicalAnd(s(l), s(r)),
+                LogicalOr(l, r) => LogicalOr(s(l), s(r)),
+                a @ Variable(_)
+                | a @ DestinationRegister(_)
+                | a @ Value(_)
+                | a @ FloatValue(_, _) => a.clone(),
             })
         }
     }
@@ -2087,16 +3429,29 @@ impl Expression {
                 | ExpressionOp::Overflow(l, _)
                 | ExpressionOp::Interrupt(l)
                 | ExpressionOp::Not(l)
+                | ExpressionOp::SignExtend(l, _, _)
+                | ExpressionOp::ZeroExtend(l, _, _)
+                | ExpressionOp::Truncate(l, _, _)
+                | ExpressionOp::FloatUnary(l, _, _)
+                | ExpressionOp::IntToFloat(l, _, _)
+                | ExpressionOp::FloatToFloat(l, _, _)
+                | ExpressionOp::FloatToInt(l, _)
                 | ExpressionOp::CountOnes(l) => {
                     if l.as_idx() >= from {
                         *l = OpIdx::from_idx(l.as_idx() - 1);
                     }
                 }
                 ExpressionOp::Assign(l, r)
+                | ExpressionOp::FloatBinary(l, r, _, _)
+                | ExpressionOp::FloatCompare(l, r, _, _)
                 | ExpressionOp::Multiequals(l, r)
                 | ExpressionOp::Add(l, r, _)
                 | ExpressionOp::Sub(l, r, _)
                 | ExpressionOp::Multiply(l, r, _)
+                | ExpressionOp::Divide(l, r, _, _)
+                | ExpressionOp::Remainder(l, r, _, _)
+                | ExpressionOp::Carry(l, r, _, _)
+                | ExpressionOp::Borrow(l, r, _)
                 | ExpressionOp::LessOrEquals(l, r, _)
                 | ExpressionOp::Less(l, r, _)
                 | ExpressionOp::GreaterOrEquals(l, r, _)
@@ -2107,6 +3462,8 @@ impl Expression {
                 | ExpressionOp::And(l, r)
                 | ExpressionOp::Or(l, r)
                 | ExpressionOp::Xor(l, r)
+                | ExpressionOp::LogicalAnd(l, r)
+                | ExpressionOp::LogicalOr(l, r)
                 | ExpressionOp::NotEquals(l, r, _) => {
                     if l.as_idx() >= from {
                         *l = OpIdx::from_idx(l.as_idx() - 1);
@@ -2117,7 +3474,8 @@ impl Expression {
                 }
                 ExpressionOp::Variable(_)
                 | ExpressionOp::DestinationRegister(_)
-                | ExpressionOp::Value(_) => (),
+                | ExpressionOp::Value(_)
+                | ExpressionOp::FloatValue(_, _) => (),
             }
         }
     }
@@ -2129,16 +3487,29 @@ impl Expression {
                 | ExpressionOp::Overflow(l, _)
                 | ExpressionOp::Not(l)
                 | ExpressionOp::Interrupt(l)
+                | ExpressionOp::SignExtend(l, _, _)
+                | ExpressionOp::ZeroExtend(l, _, _)
+                | ExpressionOp::Truncate(l, _, _)
+                | ExpressionOp::FloatUnary(l, _, _)
+                | ExpressionOp::IntToFloat(l, _, _)
+                | ExpressionOp::FloatToFloat(l, _, _)
+                | ExpressionOp::FloatToInt(l, _)
                 | ExpressionOp::CountOnes(l) => {
                     if *l == original {
                         *l = new
                     }
                 }
                 ExpressionOp::Assign(l, r)
+                | ExpressionOp::FloatBinary(l, r, _, _)
+                | ExpressionOp::FloatCompare(l, r, _, _)
                 | ExpressionOp::Multiequals(l, r)
                 | ExpressionOp::Add(l, r, _)
                 | ExpressionOp::Sub(l, r, _)
                 | ExpressionOp::Multiply(l, r, _)
+                | ExpressionOp::Divide(l, r, _, _)
+                | ExpressionOp::Remainder(l, r, _, _)
+                | ExpressionOp::Carry(l, r, _, _)
+                | ExpressionOp::Borrow(l, r, _)
                 | ExpressionOp::LessOrEquals(l, r, _)
                 | ExpressionOp::Less(l, r, _)
                 | ExpressionOp::GreaterOrEquals(l, r, _)
@@ -2149,6 +3520,8 @@ impl Expression {
                 | ExpressionOp::And(l, r)
                 | ExpressionOp::Or(l, r)
                 | ExpressionOp::Xor(l, r)
+                | ExpressionOp::LogicalAnd(l, r)
+                | ExpressionOp::LogicalOr(l, r)
                 | ExpressionOp::NotEquals(l, r, _) => {
                     if *l == original {
                         *l = new;
@@ -2159,7 +3532,8 @@ impl Expression {
                 }
                 ExpressionOp::Variable(_)
                 | ExpressionOp::DestinationRegister(_)
-                | ExpressionOp::Value(_) => (),
+                | ExpressionOp::Value(_)
+                | ExpressionOp::FloatValue(_, _) => (),
             }
         }
     }
@@ -2301,6 +3675,43 @@ impl Expression {
                 Interrupt(p) => self.0.push(Interrupt(s(p, ignore_under, new_pos))),
                 Overflow(p, sgn) => self.0.push(Overflow(s(p, ignore_under, new_pos), *sgn)),
                 CountOnes(p) => self.0.push(CountOnes(s(p, ignore_under, new_pos))),
+                SignExtend(p, from, to) => {
+                    self.0
+                        .push(SignExtend(s(p, ignore_under, new_pos), *from, *to))
+                }
+                ZeroExtend(p, from, to) => {
+                    self.0
+                        .push(ZeroExtend(s(p, ignore_under, new_pos), *from, *to))
+                }
+                Truncate(p, from, to) => {
+                    self.0
+                        .push(Truncate(s(p, ignore_under, new_pos), *from, *to))
+                }
+                FloatUnary(p, op, precision) => {
+                    self.0
+                        .push(FloatUnary(s(p, ignore_under, new_pos), *op, *precision))
+                }
+                IntToFloat(p, sgn, precision) => {
+                    self.0
+                        .push(IntToFloat(s(p, ignore_under, new_pos), *sgn, *precision))
+                }
+                FloatToFloat(p, from, to) => {
+                    self.0
+                        .push(FloatToFloat(s(p, ignore_under, new_pos), *from, *to))
+                }
+                FloatToInt(p, size) => self.0.push(FloatToInt(s(p, ignore_under, new_pos), *size)),
+                FloatBinary(l, r, op, precision) => self.0.push(FloatBinary(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                    *op,
+                    *precision,
+                )),
+                FloatCompare(l, r, op, precision) => self.0.push(FloatCompare(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                    *op,
+                    *precision,
+                )),
                 Assign(l, r) => self.0.push(Assign(
                     s(l, ignore_under, new_pos),
                     s(r, ignore_under, new_pos),
@@ -2345,6 +3756,29 @@ impl Expression {
                     total_saved += saved;
                     new_pos = new_pos as i32 + saved;
                 }
+                Divide(l, r, size, sgn) => self.0.push(Divide(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                    *size,
+                    *sgn,
+                )),
+                Remainder(l, r, size, sgn) => self.0.push(Remainder(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                    *size,
+                    *sgn,
+                )),
+                Carry(l, r, size, sgn) => self.0.push(Carry(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                    *size,
+                    *sgn,
+                )),
+                Borrow(l, r, size) => self.0.push(Borrow(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                    *size,
+                )),
                 LessOrEquals(l, r, sgn) => self.0.push(LessOrEquals(
                     s(l, ignore_under, new_pos),
                     s(r, ignore_under, new_pos),
@@ -2397,9 +3831,18 @@ impl Expression {
                 Or(l, r) => self
                     .0
                     .push(Or(s(l, ignore_under, new_pos), s(r, ignore_under, new_pos))),
-                a @ Variable(_) | a @ DestinationRegister(_) | a @ Value(_) => {
-                    self.0.push(a.clone())
-                }
+                LogicalAnd(l, r) => self.0.push(LogicalAnd(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                )),
+                LogicalOr(l, r) => self.0.push(LogicalOr(
+                    s(l, ignore_under, new_pos),
+                    s(r, ignore_under, new_pos),
+                )),
+                a @ Variable(_)
+                | a @ DestinationRegister(_)
+                | a @ Value(_)
+                | a @ FloatValue(_, _) => self.0.push(a.clone()),
             }
         }
         total_saved
@@ -2467,6 +3910,109 @@ impl ExpressionOp {
     pub fn var_reg(var_node: VarNode) -> Self {
         Self::Variable(VariableSymbol::Varnode(var_node))
     }
+
+    /// Operands of this operation, in order.
+    pub fn operands(&self) -> SmallVec<[OpIdx; 2]> {
+        match self {
+            Self::Variable(_)
+            | Self::DestinationRegister(_)
+            | Self::Value(_)
+            | Self::FloatValue(_, _) => SmallVec::new(),
+            Self::Dereference(p)
+            | Self::Interrupt(p)
+            | Self::Not(p)
+            | Self::CountOnes(p)
+            | Self::Overflow(p, _)
+            | Self::SignExtend(p, _, _)
+            | Self::ZeroExtend(p, _, _)
+            | Self::Truncate(p, _, _)
+            | Self::FloatUnary(p, _, _)
+            | Self::IntToFloat(p, _, _)
+            | Self::FloatToFloat(p, _, _)
+            | Self::FloatToInt(p, _) => smallvec![*p],
+            Self::Assign(l, r)
+            | Self::Multiequals(l, r)
+            | Self::Add(l, r, _)
+            | Self::Sub(l, r, _)
+            | Self::Multiply(l, r, _)
+            | Self::Divide(l, r, _, _)
+            | Self::Remainder(l, r, _, _)
+            | Self::LessOrEquals(l, r, _)
+            | Self::Less(l, r, _)
+            | Self::GreaterOrEquals(l, r, _)
+            | Self::Greater(l, r, _)
+            | Self::Equals(l, r, _)
+            | Self::NotEquals(l, r, _)
+            | Self::BitShiftRight(l, r, _)
+            | Self::BitShiftLeft(l, r, _)
+            | Self::And(l, r)
+            | Self::Or(l, r)
+            | Self::Xor(l, r)
+            | Self::LogicalAnd(l, r)
+            | Self::LogicalOr(l, r)
+            | Self::FloatBinary(l, r, _, _)
+            | Self::FloatCompare(l, r, _, _)
+            | Self::Carry(l, r, _, _)
+            | Self::Borrow(l, r, _) => smallvec![*l, *r],
+        }
+    }
+
+    /// Copy of this operation with its operands replaced by `operands`, in the order of [`ExpressionOp::operands`].
+    ///
+    /// # Panics
+    /// Panics if fewer operands are given than this operation takes.
+    pub fn with_operands(&self, operands: &[OpIdx]) -> Self {
+        match self {
+            Self::Variable(_)
+            | Self::DestinationRegister(_)
+            | Self::Value(_)
+            | Self::FloatValue(_, _) => self.clone(),
+            Self::Dereference(_) => Self::Dereference(operands[0]),
+            Self::Interrupt(_) => Self::Interrupt(operands[0]),
+            Self::Not(_) => Self::Not(operands[0]),
+            Self::CountOnes(_) => Self::CountOnes(operands[0]),
+            Self::Overflow(_, sgn) => Self::Overflow(operands[0], *sgn),
+            Self::SignExtend(_, from, to) => Self::SignExtend(operands[0], *from, *to),
+            Self::ZeroExtend(_, from, to) => Self::ZeroExtend(operands[0], *from, *to),
+            Self::Truncate(_, from, to) => Self::Truncate(operands[0], *from, *to),
+            Self::FloatUnary(_, op, precision) => Self::FloatUnary(operands[0], *op, *precision),
+            Self::IntToFloat(_, sgn, precision) => Self::IntToFloat(operands[0], *sgn, *precision),
+            Self::FloatToFloat(_, from, to) => Self::FloatToFloat(operands[0], *from, *to),
+            Self::FloatToInt(_, size) => Self::FloatToInt(operands[0], *size),
+            Self::Assign(_, _) => Self::Assign(operands[0], operands[1]),
+            Self::Multiequals(_, _) => Self::Multiequals(operands[0], operands[1]),
+            Self::Add(_, _, size) => Self::Add(operands[0], operands[1], *size),
+            Self::Sub(_, _, size) => Self::Sub(operands[0], operands[1], *size),
+            Self::Multiply(_, _, size) => Self::Multiply(operands[0], operands[1], *size),
+            Self::Divide(_, _, size, sgn) => Self::Divide(operands[0], operands[1], *size, *sgn),
+            Self::Remainder(_, _, size, sgn) => {
+                Self::Remainder(operands[0], operands[1], *size, *sgn)
+            }
+            Self::LessOrEquals(_, _, sgn) => Self::LessOrEquals(operands[0], operands[1], *sgn),
+            Self::Less(_, _, sgn) => Self::Less(operands[0], operands[1], *sgn),
+            Self::GreaterOrEquals(_, _, sgn) => {
+                Self::GreaterOrEquals(operands[0], operands[1], *sgn)
+            }
+            Self::Greater(_, _, sgn) => Self::Greater(operands[0], operands[1], *sgn),
+            Self::Equals(_, _, sgn) => Self::Equals(operands[0], operands[1], *sgn),
+            Self::NotEquals(_, _, sgn) => Self::NotEquals(operands[0], operands[1], *sgn),
+            Self::BitShiftRight(_, _, size) => Self::BitShiftRight(operands[0], operands[1], *size),
+            Self::BitShiftLeft(_, _, size) => Self::BitShiftLeft(operands[0], operands[1], *size),
+            Self::And(_, _) => Self::And(operands[0], operands[1]),
+            Self::Or(_, _) => Self::Or(operands[0], operands[1]),
+            Self::Xor(_, _) => Self::Xor(operands[0], operands[1]),
+            Self::LogicalAnd(_, _) => Self::LogicalAnd(operands[0], operands[1]),
+            Self::LogicalOr(_, _) => Self::LogicalOr(operands[0], operands[1]),
+            Self::FloatBinary(_, _, op, precision) => {
+                Self::FloatBinary(operands[0], operands[1], *op, *precision)
+            }
+            Self::FloatCompare(_, _, op, precision) => {
+                Self::FloatCompare(operands[0], operands[1], *op, *precision)
+            }
+            Self::Carry(_, _, size, sgn) => Self::Carry(operands[0], operands[1], *size, *sgn),
+            Self::Borrow(_, _, size) => Self::Borrow(operands[0], operands[1], *size),
+        }
+    }
 }
 
 impl FormatWithSleighLanguage for Expression {
@@ -2565,7 +4111,9 @@ mod test {
 
     use crate::ir::expression::OpIdx;
 
-    use super::{Expression, ExpressionOp, InstructionSize::U32, VariableSymbol, SMALLVEC_SIZE};
+    use super::{
+        binary_op, Expression, ExpressionOp, InstructionSize::U32, VariableSymbol, SMALLVEC_SIZE,
+    };
 
     #[inline]
     fn var_reg(r: VarNode) -> ExpressionOp {
@@ -2899,6 +4447,44 @@ mod test {
         assert_eq!(e.0, result);
     }
 
+    #[test]
+    fn test_check_value_of_variable() {
+        use super::SignedOrUnsiged::Signed;
+        use ExpressionOp::{Greater, GreaterOrEquals, Less, LessOrEquals, Value};
+
+        // `this - value` becomes `eax - 5`, which used to be compared the other way round,
+        // so `eax < 5` came out as `eax >= 5`
+        let checked = |check: fn(&mut Expression)| {
+            let mut e = Expression::from(VariableSymbol::Varnode(mk_eax()));
+            check(&mut e);
+            e.0
+        };
+        let compared = |op: fn(OpIdx, OpIdx, _) -> ExpressionOp| {
+            let result: SmallVec<[ExpressionOp; SMALLVEC_SIZE]> = smallvec![
+                var_reg(mk_eax()),
+                Value(5),
+                op(OpIdx::from_idx(0), OpIdx::from_idx(1), Signed)
+            ];
+            result
+        };
+        assert_eq!(
+            checked(|e| e.check_less_value(5, U32, Signed)),
+            compared(Less)
+        );
+        assert_eq!(
+            checked(|e| e.check_greater_value(5, U32, Signed)),
+            compared(Greater)
+        );
+        assert_eq!(
+            checked(|e| e.check_less_or_equals_value(5, U32, Signed)),
+            compared(LessOrEquals)
+        );
+        assert_eq!(
+            checked(|e| e.check_greater_or_equals_value(5, U32, Signed)),
+            compared(GreaterOrEquals)
+        );
+    }
+
     #[test]
     fn test_replace_var_with_value() {
         use ExpressionOp::{Add, Dereference, Sub, Value};
@@ -2955,4 +4541,222 @@ mod test {
         ];
         assert_eq!(expression.0, result);
     }
+
+    #[test]
+    fn test_divide_fold() {
+        use super::SignedOrUnsiged::{Signed, Unsigned};
+        use ExpressionOp::{Divide, Value};
+
+        let mut e = Expression::from(0xfffffff8);
+        e.divide_value(2, U32, Signed);
+        assert_eq!(e.get_value(), 0xfffffffc);
+
+        let mut e = Expression::from(0xfffffff8);
+        e.divide_value(2, U32, Unsigned);
+        assert_eq!(e.get_value(), 0x7ffffffc);
+
+        let mut e = Expression::from(0xfffffff9);
+        e.remainder_value(4, U32, Signed);
+        assert_eq!(e.get_value(), 0xfffffffd);
+
+        let mut e = Expression::from(VariableSymbol::Varnode(mk_eax()));
+        e.divide_value(1, U32, Unsigned);
+        e.remainder_value(1, U32, Unsigned);
+        assert_eq!(e.get_value(), 0);
+
+        // division by zero is kept as-is
+        let mut e = Expression::from(10);
+        e.divide_value(0, U32, Unsigned);
+        let result: SmallVec<[ExpressionOp; SMALLVEC_SIZE]> = smallvec![
+            Value(10),
+            Value(0),
+            Divide(OpIdx::from_idx(0), OpIdx::from_idx(1), U32, Unsigned)
+        ];
+        assert_eq!(e.0, result);
+    }
+
+    #[test]
+    fn test_extend_and_truncate() {
+        use super::InstructionSize::{U16, U64, U8};
+
+        let mut e = Expression::from(0x80);
+        e.sign_extend(U8, U32);
+        assert_eq!(e.get_value(), 0xffffff80);
+
+        let mut e = Expression::from(0x80);
+        e.zero_extend(U8, U32);
+        assert_eq!(e.get_value(), 0x80);
+
+        let mut e = Expression::from(0x12345678);
+        e.truncate(U32, U8);
+        assert_eq!(e.get_value(), 0x78);
+
+        // truncating an extension back to the original size yields the original expression
+        let al = Expression::from(VariableSymbol::Varnode(mk_eax()));
+        let mut e = al.clone();
+        e.zero_extend(U8, U32);
+        e.truncate(U32, U8);
+        assert_eq!(e, al);
+
+        let mut e = al.clone();
+        e.sign_extend(U8, U16);
+        e.sign_extend(U16, U64);
+        let result: SmallVec<[ExpressionOp; SMALLVEC_SIZE]> = smallvec![
+            var_reg(mk_eax()),
+            ExpressionOp::SignExtend(OpIdx::from_idx(0), U8, U64)
+        ];
+        assert_eq!(e.0, result);
+        assert_eq!(
+            format!("{e}"),
+            "(int64_t)?VarNode { id: 1, offset: 0, size: 4 }"
+        );
+    }
+
+    #[test]
+    fn test_float_ops() {
+        use super::{FloatBinaryOp, FloatCompareOp, FloatUnaryOp, SignedOrUnsiged::Signed};
+
+        let mut e = Expression::from(1.5f64.to_bits());
+        e.float_binary(
+            &Expression::from(2.0f64.to_bits()),
+            FloatBinaryOp::Multiply,
+            8,
+        );
+        assert_eq!(format!("{e}"), "3.0");
+
+        let mut e = Expression::from(0.5f32.to_bits() as u64);
+        e.float_compare(
+            &Expression::from(1.0f32.to_bits() as u64),
+            FloatCompareOp::Less,
+            4,
+        );
+        assert_eq!(e.get_value(), 1);
+
+        let mut e = Expression::from(VariableSymbol::Varnode(mk_eax()));
+        e.float_binary(
+            &Expression::from(0.25f32.to_bits() as u64),
+            FloatBinaryOp::Add,
+            4,
+        );
+        e.float_unary(FloatUnaryOp::Sqrt, 4);
+        assert_eq!(
+            format!("{e}"),
+            "sqrt(?VarNode { id: 1, offset: 0, size: 4 } + 0.25f)"
+        );
+
+        let mut e = Expression::from(VariableSymbol::Varnode(mk_eax()));
+        e.int_to_float(U32, Signed, 8);
+        e.float_to_int(8, U32);
+        assert_eq!(
+            format!("{e}"),
+            "(int32_t)(double)?VarNode { id: 1, offset: 0, size: 4 }"
+        );
+    }
+
+    #[test]
+    fn test_flag_conditions() {
+        use super::InstructionSize::U8;
+        use super::SignedOrUnsiged::{Signed, Unsigned};
+
+        // flags of `cmp n, 1`
+        let n = Expression::from(VariableSymbol::Varnode(mk_eax()));
+        let one = Expression::from(1);
+        let mut result = n.clone();
+        result.sub(&one, U32);
+        let mut zf = result.clone();
+        zf.check_equals_value(0, U32, Unsigned);
+        let mut sf = result.clone();
+        sf.check_less(&Expression::from(0), U32, Signed);
+        let mut of = n.clone();
+        of.signed_borrow(&one, U32);
+        let mut cf = n.clone();
+        cf.check_less(&one, U32, Unsigned);
+
+        // jg: !ZF && OF == SF
+        let mut jg = zf.clone();
+        jg.not();
+        let mut of_eq_sf = of.clone();
+        of_eq_sf.check_equals(&sf, U32, Unsigned);
+        jg.and(&of_eq_sf);
+        assert_eq!(
+            jg,
+            binary_op(n.clone(), &one, |l, r| ExpressionOp::Greater(l, r, Signed))
+        );
+
+        // jl: OF != SF
+        let mut jl = of.clone();
+        jl.check_not_equals(&sf, U32, Unsigned);
+        assert_eq!(
+            jl,
+            binary_op(n.clone(), &one, |l, r| ExpressionOp::Less(l, r, Signed))
+        );
+
+        // jbe: CF || ZF
+        let mut jbe = cf.clone();
+        jbe.or(&zf);
+        assert_eq!(
+            jbe,
+            binary_op(n.clone(), &one, |l, r| ExpressionOp::LessOrEquals(
+                l, r, Unsigned
+            ))
+        );
+
+        let mut carry = Expression::from(0xff);
+        carry.carry(&Expression::from(1), U8, Unsigned);
+        assert_eq!(carry.get_value(), 1);
+
+        let mut carry = Expression::from(0x7f);
+        carry.carry(&Expression::from(1), U8, Signed);
+        assert_eq!(carry.get_value(), 1);
+
+        let mut carry = n.clone();
+        carry.carry(&one, U32, Unsigned);
+        assert_eq!(
+            carry,
+            binary_op(n.clone(), &Expression::from(0xfffffffe), |l, r| {
+                ExpressionOp::Greater(l, r, Unsigned)
+            })
+        );
+    }
+
+    #[test]
+    fn test_logical_conditions() {
+        use super::SignedOrUnsiged::Unsigned;
+
+        let mut a = Expression::from(VariableSymbol::Varnode(mk_eax()));
+        a.check_less_value(10, U32, Unsigned);
+        let mut b = Expression::from(VariableSymbol::Varnode(mk_edi()));
+        b.check_equals_value(0, U32, Unsigned);
+
+        let mut and = a.clone();
+        and.logical_and(&b);
+        assert_eq!(and, binary_op(a.clone(), &b, ExpressionOp::LogicalAnd));
+        assert!(format!("{and}").contains(" && "));
+
+        // !(a && b) is !a || !b
+        let mut not_a = a.clone();
+        not_a.not();
+        let mut not_b = b.clone();
+        not_b.not();
+        and.not();
+        assert_eq!(
+            and,
+            binary_op(not_a.clone(), &not_b, ExpressionOp::LogicalOr)
+        );
+        assert!(format!("{and}").contains(" || "));
+        and.not();
+        assert_eq!(and, binary_op(a.clone(), &b, ExpressionOp::LogicalAnd));
+
+        // constants decide the result or drop out
+        let mut e = a.clone();
+        e.logical_and(&Expression::from(1));
+        assert_eq!(e, a);
+        e.logical_or(&Expression::from(1));
+        assert_eq!(e.get_value(), 1);
+        let mut e = Expression::from(0);
+        e.logical_or(&b);
+        assert_eq!(e, b);
+        e.logical_or(&b);
+        assert_eq!(e, b);
+    }
 }
@@ -1,31 +1,35 @@
 use nodit::{interval::ie, DiscreteFinite, InclusiveInterval, Interval, NoditMap};
 use pcode::VarNode;
 use sleigh_compile::ldef::SleighLanguage;
-use std::collections::HashSet;
+use sleigh_runtime::Instruction;
+use smallvec::SmallVec;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use super::{
     abstract_syntax_tree::AbstractSyntaxTree,
-    basic_block::{BlockIdentifier, BlockStorage, DestinationKind, NextBlock},
-    control_flow_graph::ControlFlowGraph,
+    basic_block::{BlockIdentifier, BlockSlot, BlockStorage, CpuState, DestinationKind, NextBlock},
+    calling_convention::ConventionModel,
+    control_flow_graph::{CFGProperties, ControlFlowGraph},
+    loops::{find_loops, LoopCarried, NaturalLoop},
     program_tree_structure::ProgramTreeStructure,
+    stack_frame::{stack_offset, FramePointer, StackFrame},
     Expression, ExpressionOp, VariableSymbol,
 };
 use crate::{
-    ir::expression::{InstructionSize, OpIdx},
-    memory::Memory,
+    ir::expression::{as_signed, InstructionSize, OpIdx, SignedOrUnsiged},
+    memory::{LiteralKind, Memory},
 };
 
 use super::{Address, BasicBlock};
 
-#[derive(Copy, Clone, Debug)]
-pub enum CallingConvention {
-    Cdecl,
-}
-
 /// Accumulation of analysis resutls of a single function
 pub struct HighFunction {
     pub start: Address,
-    pub calling_convention: CallingConvention,
+    /// Convention of this function, also assumed for the functions it calls
+    pub calling_convention: ConventionModel,
     pub composed_blocks: BlockStorage,
     pub used_call_results: HashSet<VariableSymbol>,
     pub memory_read: HashSet<Expression>,
@@ -33,11 +37,498 @@ pub struct HighFunction {
     pub function_calls: HashSet<DestinationKind>,
     pub cfg: ControlFlowGraph,
     pub pts: ProgramTreeStructure,
+    /// Branch conditions that hold whenever a composed block executes, see [`PathConstraint`]
+    pub path_constraints: HashMap<BlockIdentifier, PathConstraint>,
+    /// Loops of the function, outer loops first
+    pub loops: Vec<NaturalLoop>,
+    /// Bytes the stack pointer moves up by from the entry to the return, including the popped
+    /// return address. `None` if the returns disagree or the function doesn't return.
+    pub stack_delta: Option<u64>,
+    /// Registers and stack slots the arguments are read from: the integer argument registers, the
+    /// floating point ones, then the stack slots as `[SP + offset]` at the entry
+    pub parameters: Vec<VariableSymbol>,
+    /// Register holding the result at the returns, `None` if the function has no result
+    pub return_register: Option<VarNode>,
+    /// Parameters of the analyzed functions called, which the arguments of the calls are taken
+    /// from instead of the calling convention's guess
+    pub callee_parameters: HashMap<DestinationKind, Vec<VariableSymbol>>,
+    /// Locals and stack parameters
+    pub stack_frame: StackFrame,
+    /// Register the function addresses its stack frame through, if any. Blocks are composed with
+    /// its accesses relative to the entry stack pointer, like the accesses through the stack
+    /// pointer.
+    pub frame_pointer: Option<FramePointer>,
+    /// Effects of the function that its callers see
+    pub summary: FunctionSummary,
+}
+
+/// What a call to a function does to the state of its caller.
+///
+/// Summaries of analyzed functions replace the calling convention's guesses at their call sites,
+/// so a caller has to be analyzed again when the summary of one of its callees changes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FunctionSummary {
+    /// Registers that may hold a different value at a return or tail call than at the entry,
+    /// except the stack and instruction pointers
+    pub clobbered_registers: Vec<VarNode>,
+    /// Constant addresses the function writes to
+    pub globals_written: Vec<u64>,
+    /// See [`HighFunction::stack_delta`]
+    pub stack_delta: Option<u64>,
+    /// Number of [`HighFunction::parameters`]
+    pub argument_count: usize,
+    /// See [`HighFunction::return_register`]
+    pub return_register: Option<VarNode>,
+    /// Whether no return of the function is reachable, so calls to it don't come back
+    pub never_returns: bool,
+}
+
+impl FunctionSummary {
+    fn new(
+        composed_blocks: &BlockStorage,
+        memory_written: &HashSet<Expression>,
+        argument_count: usize,
+        return_register: Option<VarNode>,
+        stack_delta: Option<u64>,
+        never_returns: bool,
+        lang: &SleighLanguage,
+    ) -> Self {
+        let initial = CpuState::new();
+        let mut clobbered_registers = Vec::new();
+        for (_, block) in composed_blocks.iter() {
+            if !matches!(block.next, NextBlock::Return | NextBlock::TailCall { .. }) {
+                continue;
+            }
+            for var in initial.changed_registers(&block.registers) {
+                if var.id != lang.sp.id
+                    && var.id != lang.pc.id
+                    && !clobbered_registers.contains(&var)
+                {
+                    clobbered_registers.push(var);
+                }
+            }
+        }
+        clobbered_registers.sort_by_key(|var| (var.id, var.offset, var.size));
+
+        let mut globals_written: Vec<u64> = memory_written
+            .iter()
+            .filter_map(|addr| match (addr.len(), addr.root_op()) {
+                (1, Some(ExpressionOp::Value(v))) => Some(*v),
+                _ => None,
+            })
+            .collect();
+        globals_written.sort();
+
+        Self {
+            clobbered_registers,
+            globals_written,
+            stack_delta,
+            argument_count,
+            return_register,
+            never_returns,
+        }
+    }
+}
+
+/// Register and stack arguments read by the function, see [`HighFunction::parameters`].
+///
+/// A register is an argument if its value from the entry is read, or passed on unchanged to a
+/// callee that reads it. Arguments before the last one read are kept, since the caller has to set
+/// them up too, and every integer argument register comes before a stack argument.
+fn parameters(
+    composed_blocks: &BlockStorage,
+    calling_convention: &ConventionModel,
+    mem: &Memory,
+) -> Vec<VariableSymbol> {
+    let lang = &mem.lang;
+    let is_read = |var: VarNode| {
+        composed_blocks.iter().any(|(_, block)| {
+            block_expressions(block)
+                .iter()
+                .any(|expr| reads_entry_value(expr, var))
+                || passes_register(block, var, mem)
+        })
+    };
+    let leading = |mut registers: Vec<VarNode>| {
+        let used = registers
+            .iter()
+            .rposition(|var| is_read(*var))
+            .map_or(0, |last| last + 1);
+        registers.truncate(used);
+        registers
+    };
+
+    let stack_slots = composed_blocks
+        .iter()
+        .flat_map(|(_, block)| block_expressions(block))
+        .flat_map(|expr| {
+            expr.iter()
+                .filter_map(|op| match op {
+                    ExpressionOp::Dereference(d) => Some(expr.get_sub_expression(*d)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|addr| calling_convention.is_stack_argument(addr, lang))
+        .filter_map(|addr| match addr.get(OpIdx::from_idx(1)) {
+            ExpressionOp::Value(offset) => Some(
+                (offset - calling_convention.stack_argument_offset)
+                    / calling_convention.stack_slot_size
+                    + 1,
+            ),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+
+    let integer = calling_convention.argument_varnodes(lang, false);
+    let integer = if stack_slots > 0 {
+        integer
+    } else {
+        leading(integer)
+    };
+    let float = leading(calling_convention.argument_varnodes(lang, true));
+    let stack = (0..stack_slots).map(|slot| {
+        let mut addr = Expression::from(ExpressionOp::var_reg(lang.sp));
+        addr.add_value(
+            calling_convention.stack_argument_offset + slot * calling_convention.stack_slot_size,
+            lang.sp.size,
+        );
+        VariableSymbol::Ram(Box::new(addr), calling_convention.stack_slot_size as u8)
+    });
+    integer
+        .into_iter()
+        .chain(float)
+        .map(VariableSymbol::Varnode)
+        .chain(stack)
+        .collect()
+}
+
+/// Whether `block` ends with a call to an analyzed function that reads `var` as an argument,
+/// while `var` still holds its value from the entry.
+fn passes_register(block: &BasicBlock, var: VarNode, mem: &Memory) -> bool {
+    let NextBlock::Call {
+        destination: DestinationKind::Concrete(callee),
+        ..
+    } = &block.next
+    else {
+        return false;
+    };
+    let Some(callee) = mem.functions.get(callee) else {
+        return false;
+    };
+    let unchanged = block.registers.get(var).map_or(true, |state| {
+        reads_entry_value(&state, var) && state.len() == 1
+    });
+    unchanged
+        && callee
+            .parameters
+            .iter()
+            .any(|p| matches!(p, VariableSymbol::Varnode(r) if r.id == var.id))
+}
+
+/// Register the function returns its result in.
+///
+/// A floating point result register that holds a value computed by the function wins over the
+/// integer one, as the integer register may just hold a copy of it. Registers holding whatever a
+/// call left there aren't results, unless the call result is returned as is.
+fn return_register(
+    composed_blocks: &BlockStorage,
+    calling_convention: &ConventionModel,
+    lang: &SleighLanguage,
+) -> Option<VarNode> {
+    let at_returns = |var: VarNode| {
+        composed_blocks
+            .iter()
+            .filter(|(_, block)| matches!(block.next, NextBlock::Return))
+            .filter_map(|(_, block)| block.registers.get(var))
+            .filter(|state| !reads_entry_value(state, var) || state.len() != 1)
+            .map(Cow::into_owned)
+            .collect::<Vec<_>>()
+    };
+    let root = |state: &Expression| match state.root_op() {
+        Some(ExpressionOp::Variable(v)) => Some(v.clone()),
+        _ => None,
+    };
+    let from_call = |states: &[Expression]| {
+        states.iter().any(|s| {
+            matches!(
+                root(s),
+                Some(VariableSymbol::CallResult { .. } | VariableSymbol::Clobbered { .. })
+            )
+        })
+    };
+    let clobbered = |states: &[Expression]| {
+        states
+            .iter()
+            .any(|s| matches!(root(s), Some(VariableSymbol::Clobbered { .. })))
+    };
+
+    let float = calling_convention.float_return_register(lang);
+    let float_states = float.map(at_returns).unwrap_or_default();
+    if !float_states.is_empty() && !from_call(&float_states) {
+        return float;
+    }
+    let integer = calling_convention.return_register(lang);
+    let integer_states = integer.map(at_returns).unwrap_or_default();
+    if !integer_states.is_empty() && !clobbered(&integer_states) {
+        return integer;
+    }
+    // `return f()` of a floating point `f`
+    float.filter(|_| !float_states.is_empty() && !clobbered(&float_states))
+}
+
+/// Whether `expr` depends on the value `var` had at the function entry.
+fn reads_entry_value(expr: &Expression, var: VarNode) -> bool {
+    expr.iter_vars()
+        .any(|v| matches!(v, VariableSymbol::Varnode(r) if r.id == var.id))
+}
+
+/// Values a composed block computes: the registers it changes, its memory state and its branch
+/// condition.
+fn block_expressions(block: &BasicBlock) -> Vec<Cow<'_, Expression>> {
+    let mut expressions: Vec<_> = CpuState::new()
+        .changed_registers(&block.registers)
+        .into_iter()
+        .filter_map(|var| block.registers.get(var))
+        .collect();
+    for (addr, value) in &block.memory {
+        expressions.push(Cow::Borrowed(addr));
+        expressions.push(Cow::Borrowed(value));
+    }
+    match &block.next {
+        NextBlock::Jump { condition, .. } => expressions.push(Cow::Borrowed(condition)),
+        NextBlock::Switch { selector, .. } => expressions.push(Cow::Borrowed(selector)),
+        _ => (),
+    }
+    expressions
+}
+
+/// The block at `slot`, lifted again with the frame pointers of `state`, the state it's entered
+/// with, relative to its stack pointer.
+///
+/// The lifter starts every block from unrelated register symbols, so `[EBP - 0x8]` and
+/// `[ESP + 0x8]` of the same stack slot are different addresses inside a block, and a read through
+/// one doesn't see a write through the other. `relifted` keeps the lifted blocks and the registers
+/// they were lifted from.
+fn with_frame_pointers<'b>(
+    slot: BlockSlot,
+    state: &BasicBlock,
+    calling_convention: &ConventionModel,
+    mem: &'b Memory,
+    relifted: &'b mut HashMap<BlockSlot, (CpuState, Option<BasicBlock>)>,
+) -> &'b BasicBlock {
+    let block = &mem.ir[slot];
+    let sp = mem.lang.sp;
+    let Some(sp_offset) = state.registers.get(sp).and_then(|s| stack_offset(&s, sp)) else {
+        return block;
+    };
+    let expressions = block_expressions(block);
+    let mut initial = CpuState::new();
+    let mut seeded = false;
+    for frame_pointer in FramePointer::in_state(&state.registers, calling_convention, &mem.lang) {
+        if expressions
+            .iter()
+            .any(|expr| reads_entry_value(expr, frame_pointer.register))
+        {
+            let mut value = Expression::from(ExpressionOp::var_reg(sp));
+            value.add_value((frame_pointer.offset - sp_offset) as u64, sp.size);
+            initial.set_state(frame_pointer.register, value);
+            seeded = true;
+        }
+    }
+    if !seeded {
+        return block;
+    }
+    if !matches!(relifted.get(&slot), Some((lifted_from, _)) if *lifted_from == initial) {
+        let lifted = match block.identifier {
+            BlockIdentifier::Physical(interval) => {
+                match mem.literal.get_at_point(interval.start()).map(|l| &l.kind) {
+                    Some(LiteralKind::Instruction(_, instructions)) => {
+                        super::relift_block(block, instructions, &mem.lang, initial.clone())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        relifted.insert(slot, (initial, lifted));
+    }
+    relifted[&slot].1.as_ref().unwrap_or(block)
+}
+
+/// Rounds of composing loop bodies before giving up on their states settling.
+const MAX_LOOP_ROUNDS: usize = 16;
+
+/// Conjunction of branch conditions that hold on every path from the function start to a block.
+///
+/// Conditions are in terms of the function's initial state, as the composed
+/// [`NextBlock::Jump`] conditions are. The false side of a branch is stored negated.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathConstraint(Vec<Expression>);
+
+impl PathConstraint {
+    /// The block executes unconditionally once the function is entered.
+    pub fn is_unconstrained(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn conditions(&self) -> &[Expression] {
+        &self.0
+    }
+
+    /// Constraint of a successor reached through the `taken` side of `condition`.
+    pub fn and_branch(&self, condition: &Expression, taken: bool) -> Self {
+        let mut condition = condition.clone();
+        if !taken {
+            condition.not();
+        }
+        let mut conditions = self.0.clone();
+        if !conditions.contains(&condition) {
+            conditions.push(condition);
+        }
+        Self(conditions)
+    }
+
+    /// Keep only the conditions that also hold on `other`, which is another path to the same block.
+    ///
+    /// # Returns
+    /// Whether any condition was removed
+    fn intersect(&mut self, other: &Self) -> bool {
+        let len = self.0.len();
+        self.0.retain(|condition| other.0.contains(condition));
+        len != self.0.len()
+    }
+
+    /// All conditions joined into a single expression, `None` when unconstrained.
+    pub fn to_expression(&self) -> Option<Expression> {
+        let mut conditions = self.0.iter();
+        let mut output = conditions.next()?.clone();
+        for condition in conditions {
+            output.logical_and(condition);
+        }
+        Some(output)
+    }
+}
+
+impl std::fmt::Display for PathConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("true");
+        }
+        for (idx, condition) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" && ")?;
+            }
+            write!(f, "{condition}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Successors of a composed block, with the branch condition and side that leads to each.
+///
+/// The successors of a switch are reached when the selector equals one of their case values.
+fn successors_with_branch(
+    blocks: &BlockStorage,
+    slot: BlockSlot,
+) -> SmallVec<[(BlockSlot, Option<(Cow<'_, Expression>, bool)>); 2]> {
+    match &blocks[slot].next {
+        NextBlock::Jump {
+            condition,
+            true_branch,
+            false_branch,
+        } => {
+            let true_slot = blocks.slot_by_destination(true_branch);
+            let false_slot = blocks.slot_by_destination(false_branch);
+            if true_slot == false_slot {
+                return true_slot.into_iter().map(|s| (s, None)).collect();
+            }
+            true_slot
+                .map(|s| (s, Some((Cow::Borrowed(condition), true))))
+                .into_iter()
+                .chain(false_slot.map(|s| (s, Some((Cow::Borrowed(condition), false)))))
+                .collect()
+        }
+        NextBlock::Switch { selector, cases } => blocks
+            .iter_neighbors(slot)
+            .map(|successor| {
+                let values: Vec<u64> = cases
+                    .iter()
+                    .filter(|(_, destination)| {
+                        blocks.slot_by_destination(destination) == Some(successor)
+                    })
+                    .map(|(value, _)| *value)
+                    .collect();
+                (
+                    successor,
+                    Some((Cow::Owned(case_condition(selector, &values)), true)),
+                )
+            })
+            .collect(),
+        _ => blocks.iter_neighbors(slot).map(|s| (s, None)).collect(),
+    }
+}
+
+/// Condition under which `selector` equals one of `values`.
+fn case_condition(selector: &Expression, values: &[u64]) -> Expression {
+    let size = selector
+        .size_of(selector.get_entry_point())
+        .unwrap_or(InstructionSize::U64);
+    let mut condition: Option<Expression> = None;
+    for value in values {
+        let mut equals = selector.clone();
+        equals.check_equals_value(*value, size, SignedOrUnsiged::Unsigned);
+        match condition.as_mut() {
+            Some(condition) => condition.or(&equals),
+            None => condition = Some(equals),
+        }
+    }
+    condition.unwrap_or_else(|| Expression::from(0))
+}
+
+/// Compute the [`PathConstraint`] of every block reachable from `start`.
+///
+/// A block reached through several paths keeps the conditions common to all of them, so
+/// both sides of an `if` join back to the constraint before the branch and loop headers aren't
+/// constrained by their own loop condition. Constraints only shrink, which bounds the iteration.
+fn path_constraints(
+    blocks: &BlockStorage,
+    start: BlockSlot,
+) -> HashMap<BlockIdentifier, PathConstraint> {
+    let mut constraints: HashMap<BlockSlot, PathConstraint> = HashMap::new();
+    constraints.insert(start, PathConstraint::default());
+    let mut worklist = vec![start];
+
+    while let Some(slot) = worklist.pop() {
+        let constraint = constraints[&slot].clone();
+        for (successor, branch) in successors_with_branch(blocks, slot) {
+            let candidate = match branch {
+                Some((condition, taken)) => constraint.and_branch(&condition, taken),
+                None => constraint.clone(),
+            };
+            match constraints.get_mut(&successor) {
+                Some(existing) => {
+                    if existing.intersect(&candidate) {
+                        worklist.push(successor);
+                    }
+                }
+                None => {
+                    constraints.insert(successor, candidate);
+                    worklist.push(successor);
+                }
+            }
+        }
+    }
+
+    constraints
+        .into_iter()
+        .map(|(slot, constraint)| (blocks[slot].identifier, constraint))
+        .collect()
 }
 
 fn analysis(
     composed_block: &BasicBlock,
-    sp: VarNode,
     used_call_results: &mut HashSet<VariableSymbol>,
     memory_read: &mut HashSet<Expression>,
     memory_written: &mut HashSet<Expression>,
@@ -66,157 +557,535 @@ fn analysis(
                 }
             }
         }
-        NextBlock::Call { destination, .. } => {
+        NextBlock::Call { destination, .. } | NextBlock::TailCall { destination, .. } => {
             function_calls.insert(destination.clone());
         }
-        NextBlock::Return => {
-            let stack_state = composed_block.registers.get(sp).unwrap();
-            if stack_state.get_entry_point().as_idx() != 0
-                || !matches!(
-                    stack_state.get(OpIdx::from_idx(0)),
-                    ExpressionOp::Variable(_)
-                )
-            {
-                println!(
-                    "TODO: Function returns at {} Non-initial stack pointer: ESP = {}",
-                    composed_block.identifier, stack_state
-                );
-                if composed_block.identifier.contains(0x4b1724_u64) {
-                    println!("\t this is because MessageBoxA follows stdcall calling convention, not cdecl.")
+        _ => (),
+    }
+}
+
+/// How far the stack pointer at the end of `block` is above the one at its start.
+fn stack_delta(block: &BasicBlock, sp: VarNode) -> Option<u64> {
+    let Some(state) = block.registers.get(sp) else {
+        return Some(0);
+    };
+    let delta = match (state.len(), state.root_op()) {
+        (1, Some(ExpressionOp::Variable(VariableSymbol::Varnode(v)))) if *v == sp => 0,
+        (3, Some(ExpressionOp::Add(_, _, _))) => {
+            match (state.get(OpIdx::from_idx(0)), state.get(OpIdx::from_idx(1))) {
+                (
+                    ExpressionOp::Variable(VariableSymbol::Varnode(v)),
+                    ExpressionOp::Value(delta),
+                ) if *v == sp => as_signed(*delta, sp.size.into()),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    u64::try_from(delta).ok()
+}
+
+/// Stack delta of a function, if all of its returns in `composed_blocks` agree on it.
+///
+/// A tail call returns with the stack delta of its callee, on top of the stack pointer it jumps
+/// with.
+fn function_stack_delta(composed_blocks: &BlockStorage, mem: &Memory) -> Option<u64> {
+    let sp = mem.lang.sp;
+    let mut deltas = composed_blocks
+        .iter()
+        .filter_map(|(_, block)| match &block.next {
+            NextBlock::Return => Some(stack_delta(block, sp)),
+            NextBlock::TailCall { destination, .. } => Some(
+                stack_delta(block, sp)
+                    .zip(callee_stack_delta(destination, mem))
+                    .map(|(delta, callee_delta)| delta + callee_delta),
+            ),
+            _ => None,
+        });
+    let first = deltas.next()??;
+    deltas.all(|delta| delta == Some(first)).then_some(first)
+}
+
+/// Stack delta of the function called at `destination`.
+///
+/// Functions that were analyzed already know it, others are looked up from the `ret` instructions
+/// of their lifted blocks, so `ret 8` gives a delta of 8 plus the return address.
+fn callee_stack_delta(destination: &DestinationKind, mem: &Memory) -> Option<u64> {
+    let DestinationKind::Concrete(start) = destination else {
+        return None;
+    };
+    if let Some(callee) = mem.functions.get(start) {
+        return callee.stack_delta;
+    }
+    let start = mem.ir.slot_by_address(*start)?;
+    let mut visited = HashSet::from([start]);
+    let mut worklist = vec![start];
+    let mut delta = None;
+    while let Some(slot) = worklist.pop() {
+        let block = &mem.ir[slot];
+        if matches!(block.next, NextBlock::Return) {
+            let ret = last_instruction(block, mem)?;
+            let lifted = super::lift(std::slice::from_ref(ret), &mem.lang, None);
+            let ret_delta =
+                stack_delta(lifted.get_by_address(Address(ret.inst_start))?, mem.lang.sp)?;
+            if delta.is_some_and(|delta| delta != ret_delta) {
+                return None;
+            }
+            delta = Some(ret_delta);
+        }
+        for neighbor in mem.ir.iter_neighbors(slot) {
+            if visited.insert(neighbor) {
+                worklist.push(neighbor);
+            }
+        }
+    }
+    delta
+}
+
+/// The decoded instruction that ends the lifted `block`.
+fn last_instruction<'m>(block: &BasicBlock, mem: &'m Memory) -> Option<&'m Instruction> {
+    let BlockIdentifier::Physical(interval) = block.identifier else {
+        return None;
+    };
+    let last = interval.end();
+    let LiteralKind::Instruction(_, instructions) = &mem.literal.get_at_point(last)?.kind else {
+        return None;
+    };
+    instructions
+        .iter()
+        .find(|i| i.inst_start <= last.0 && last.0 < i.inst_next)
+}
+
+/// Address of the jump ending the composed block at `slot`, if it's a tail call out of the
+/// function at `start`.
+///
+/// An unconditional jump is a tail call when it goes to the start of a known function, or when
+/// it's taken after the block popped the rest of the frame. The latter only counts for targets
+/// that are `only_way_in` to, as a jump within the function can be made with an empty frame too.
+fn tail_call_origin(
+    slot: BlockSlot,
+    composed_blocks: &BlockStorage,
+    start: Address,
+    only_way_in: impl Fn(BlockSlot) -> bool,
+    mem: &Memory,
+) -> Option<Address> {
+    let block = &mem.ir[slot];
+    let composed = composed_blocks.get_by_identifier(block.identifier)?;
+    let NextBlock::Jump {
+        condition,
+        true_branch,
+        ..
+    } = &composed.next
+    else {
+        return None;
+    };
+    if condition.is_symbolic() || condition.get_value() == 0 {
+        return None;
+    }
+    let sp = mem.lang.sp;
+    // only the return address is left on the stack
+    let restored_frame = composed
+        .registers
+        .get(sp)
+        .is_some_and(|state| stack_offset(&state, sp) == Some(0))
+        && stack_delta(block, sp).is_some_and(|delta| delta > 0);
+    let is_tail_call = match true_branch {
+        DestinationKind::Concrete(target) if *target != start => {
+            mem.is_function_start(*target)
+                || restored_frame && mem.ir.slot_by_address(*target).is_some_and(only_way_in)
+        }
+        DestinationKind::Symbolic(_) => restored_frame,
+        _ => false,
+    };
+    if !is_tail_call {
+        return None;
+    }
+    Some(Address(last_instruction(block, mem)?.inst_start))
+}
+
+/// State of `composed_block` as seen by its successor.
+///
+/// A call returns to the successor, so the return value is applied, the registers the
+/// `calling_convention` doesn't preserve get unknown values and the stack pointer moves by the
+/// `callee_deltas` of the callee. Callees with an unknown stack delta are assumed to clean up
+/// after the `calling_convention`.
+///
+/// Calls to analyzed functions apply their [`FunctionSummary`] instead: only the registers they
+/// clobber get unknown values, and the globals they write are forgotten.
+fn state_after<'b>(
+    composed_block: &'b BasicBlock,
+    calling_convention: &ConventionModel,
+    callee_deltas: &HashMap<DestinationKind, u64>,
+    mem: &Memory,
+) -> Cow<'b, BasicBlock> {
+    let NextBlock::Call {
+        origin,
+        destination,
+        ..
+    } = &composed_block.next
+    else {
+        return Cow::Borrowed(composed_block);
+    };
+    let mut after_call = composed_block.clone();
+    let summary = match destination {
+        DestinationKind::Concrete(callee) => mem.functions.get(callee).map(|f| &f.summary),
+        _ => None,
+    };
+    // without a summary, the result is wherever the caller reads it from
+    let results: SmallVec<[VarNode; 2]> = match summary {
+        Some(summary) => summary.return_register.into_iter().collect(),
+        None => [
+            calling_convention.return_register(&mem.lang),
+            calling_convention.float_return_register(&mem.lang),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+    };
+    let clobbered: Vec<VarNode> = match summary {
+        Some(summary) => {
+            for global in &summary.globals_written {
+                after_call.memory.remove(&Expression::from(*global));
+            }
+            summary
+                .clobbered_registers
+                .iter()
+                .copied()
+                .filter(|register| register.id != mem.lang.sp.id)
+                .collect()
+        }
+        None => calling_convention.killed_registers(&mem.lang).collect(),
+    };
+    for register in clobbered {
+        after_call.registers.set_state(
+            register,
+            Expression::from(VariableSymbol::Clobbered {
+                call_from: *origin,
+                register,
+            }),
+        );
+    }
+    for result in results {
+        after_call.registers.set_state(
+            result,
+            Expression::from(VariableSymbol::CallResult {
+                call_from: *origin,
+                call_to: Box::new(destination.clone()),
+            }),
+        );
+    }
+    // the return pops the return address, and the arguments if the callee cleans up
+    let delta = callee_deltas.get(destination).copied().unwrap_or_else(|| {
+        let stack_arguments = if calling_convention.callee_cleanup {
+            calling_convention
+                .stack_arguments(composed_block, &mem.lang)
+                .len()
+        } else {
+            0
+        };
+        calling_convention.stack_cleanup(stack_arguments)
+    });
+    let mut sp_state = after_call
+        .registers
+        .get_or_symbolic(mem.lang.sp)
+        .into_owned();
+    sp_state.add_value(delta, mem.lang.sp.size);
+    after_call.registers.set_state(mem.lang.sp, sp_state);
+    Cow::Owned(after_call)
+}
+
+/// Blocks reachable from `start` in reverse postorder, and the `(from, to)` edges that jump back
+/// to a block still being visited, i.e. that close a loop.
+///
+/// Every other edge goes from a block to one later in the order. Blocks that `ends_function`
+/// have no successors.
+fn reverse_postorder(
+    blocks: &BlockStorage,
+    start: BlockSlot,
+    ends_function: impl Fn(BlockSlot) -> bool,
+) -> (Vec<BlockSlot>, HashSet<(BlockSlot, BlockSlot)>) {
+    let mut postorder = Vec::new();
+    let mut back_edges = HashSet::new();
+    let mut visited = HashSet::from([start]);
+    let mut on_path = HashSet::from([start]);
+    let mut stack = vec![(start, blocks.iter_neighbors(start))];
+
+    while let Some((slot, neighbors)) = stack.last_mut() {
+        let slot = *slot;
+        let next = if ends_function(slot) {
+            None
+        } else {
+            neighbors.next()
+        };
+        match next {
+            Some(neighbor) if on_path.contains(&neighbor) => {
+                back_edges.insert((slot, neighbor));
+            }
+            Some(neighbor) => {
+                if visited.insert(neighbor) {
+                    on_path.insert(neighbor);
+                    stack.push((neighbor, blocks.iter_neighbors(neighbor)));
                 }
             }
+            None => {
+                on_path.remove(&slot);
+                postorder.push(slot);
+                stack.pop();
+            }
         }
-        _ => (),
+    }
+
+    postorder.reverse();
+    (postorder, back_edges)
+}
+
+/// Blocks of a function composed in the order they execute, see [`compose`].
+struct Composition {
+    order: Vec<BlockSlot>,
+    composed_blocks: BlockStorage,
+    loop_entries: HashMap<BlockSlot, BasicBlock>,
+    loop_carried: HashMap<BlockSlot, LoopCarried>,
+    /// Jumps leaving the function that weren't known to be tail calls yet, with their origins
+    new_tail_calls: Vec<(BlockSlot, Address)>,
+}
+
+/// Composes the blocks of the function at `start`, which begins with the lifted block `block_id`.
+///
+/// The blocks in `tail_calls` end the function with a tail call made from the address they map to.
+fn compose(
+    start: Address,
+    block_id: BlockSlot,
+    calling_convention: &ConventionModel,
+    tail_calls: &HashMap<BlockSlot, Address>,
+    mem: &Memory,
+) -> Composition {
+    let block = &mem.ir[block_id];
+
+    // Compose every block after all of its predecessors, so blocks reached through several
+    // paths start from the join of their states. Loops are entered with the state before them,
+    // then composed again with the values their body changes widened, until nothing changes.
+    // composition stops after calls that never return and at tail calls
+    let ends_function = |slot: BlockSlot| {
+        tail_calls.contains_key(&slot)
+            || matches!(&mem.ir[slot].next, NextBlock::Call { destination, .. }
+                if mem.never_returns(destination))
+    };
+    let (order, back_edges) = reverse_postorder(&mem.ir, block_id, ends_function);
+    let mut predecessors: HashMap<BlockSlot, Vec<BlockSlot>> = HashMap::new();
+    for &slot in &order {
+        if ends_function(slot) {
+            continue;
+        }
+        for neighbor in mem.ir.iter_neighbors(slot) {
+            let preds = predecessors.entry(neighbor).or_default();
+            if !back_edges.contains(&(slot, neighbor)) && !preds.contains(&slot) {
+                preds.push(slot);
+            }
+        }
+    }
+
+    let callee_deltas: HashMap<DestinationKind, u64> = order
+        .iter()
+        .filter_map(|slot| match &mem.ir[*slot].next {
+            NextBlock::Call { destination, .. } => {
+                Some((destination.clone(), callee_stack_delta(destination, mem)?))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut loop_carried: HashMap<BlockSlot, LoopCarried> = HashMap::new();
+    let mut loop_entries: HashMap<BlockSlot, BasicBlock> = HashMap::new();
+    let mut relifted = HashMap::new();
+    let mut composed_blocks = BlockStorage::new();
+    for round in 0.. {
+        composed_blocks = BlockStorage::new();
+        for &slot in &order {
+            let mut composed = if slot == block_id {
+                block.clone()
+            } else {
+                let mut incoming = predecessors[&slot].iter().map(|pred| {
+                    let composed_pred = composed_blocks
+                        .get_by_identifier(mem.ir[*pred].identifier)
+                        .unwrap();
+                    state_after(composed_pred, calling_convention, &callee_deltas, mem)
+                });
+                let first = incoming.next().unwrap();
+                let state = incoming.fold(first, |state, other| {
+                    if state.registers != other.registers || state.memory != other.memory {
+                        Cow::Owned(state.merge_state(&other))
+                    } else {
+                        state
+                    }
+                });
+                if back_edges.iter().any(|(_, header)| *header == slot) {
+                    let mut widened = state.clone().into_owned();
+                    loop_entries.insert(slot, state.into_owned());
+                    if let Some(carried) = loop_carried.get(&slot) {
+                        carried.widen(mem.ir[slot].identifier.start_address(), &mut widened);
+                    }
+                    with_frame_pointers(slot, &widened, calling_convention, mem, &mut relifted)
+                        .inherit_state_from(&widened, mem.lang.sp)
+                } else {
+                    with_frame_pointers(slot, &state, calling_convention, mem, &mut relifted)
+                        .inherit_state_from(&state, mem.lang.sp)
+                }
+            };
+            if let NextBlock::Call { default_return, .. } = &mut composed.next {
+                if ends_function(slot) {
+                    *default_return = None;
+                }
+            }
+            if let (Some(origin), NextBlock::Jump { true_branch, .. }) =
+                (tail_calls.get(&slot), &composed.next)
+            {
+                composed.next = NextBlock::TailCall {
+                    origin: *origin,
+                    destination: true_branch.clone(),
+                };
+            }
+            composed_blocks.insert(composed);
+        }
+
+        let mut changed = false;
+        for (latch, header) in &back_edges {
+            let Some(entry) = loop_entries.get(header) else {
+                // the function start has no state to widen
+                continue;
+            };
+            let carried = loop_carried.entry(*header).or_default();
+            let mut header_state = entry.clone();
+            carried.widen(
+                mem.ir[*header].identifier.start_address(),
+                &mut header_state,
+            );
+            let latch_block = composed_blocks
+                .get_by_identifier(mem.ir[*latch].identifier)
+                .unwrap();
+            let latch_state = state_after(latch_block, calling_convention, &callee_deltas, mem);
+            changed |= carried.add_changes(&header_state, &latch_state, mem.lang.sp);
+        }
+        if !changed {
+            break;
+        }
+        if round == MAX_LOOP_ROUNDS {
+            println!("Loop states at {start} didn't settle after {MAX_LOOP_ROUNDS} rounds");
+            break;
+        }
+    }
+
+    let new_tail_calls = order
+        .iter()
+        .filter(|slot| !tail_calls.contains_key(slot))
+        .filter_map(|&slot| {
+            let only_way_in = |target: BlockSlot| {
+                predecessors
+                    .get(&target)
+                    .is_some_and(|preds| preds.len() == 1)
+                    && !back_edges.iter().any(|(_, header)| *header == target)
+            };
+            let origin = tail_call_origin(slot, &composed_blocks, start, only_way_in, mem)?;
+            Some((slot, origin))
+        })
+        .collect();
+    Composition {
+        order,
+        composed_blocks,
+        loop_entries,
+        loop_carried,
+        new_tail_calls,
     }
 }
 
 impl HighFunction {
     pub fn from_mem(addr: impl Into<Address>, mem: &Memory) -> Self {
-        let calling_convention = CallingConvention::Cdecl;
+        let calling_convention = mem.default_calling_convention();
         let addr = addr.into();
 
         let block_id = mem
             .ir
             .slot_by_address(addr)
             .expect("Unable to get IR at function start");
-        let block = &mem.ir[block_id];
 
-        let mut composed_blocks = BlockStorage::new();
         let mut used_call_results = HashSet::new();
         let mut memory_read = HashSet::new();
         let mut memory_written = HashSet::new();
         let mut function_calls = HashSet::new();
 
-        composed_blocks.insert(block.clone());
-        analysis(
-            block,
-            mem.lang.sp,
-            &mut used_call_results,
-            &mut memory_read,
-            &mut memory_written,
-            &mut function_calls,
-        );
-        let mut visited = std::collections::HashSet::new();
-        let mut stack = vec![block_id];
-        let mut copy_vec = Vec::with_capacity(10);
-
-        while let Some(current_block_id) = stack.pop() {
-            if !visited.contains(&current_block_id) {
-                visited.insert(current_block_id);
-                let composed_block = composed_blocks
-                    .get_by_identifier(mem.ir[current_block_id].identifier)
-                    .unwrap();
-
-                for neighbor_block_id in mem.ir.iter_neighbors(current_block_id) {
-                    stack.push(neighbor_block_id);
-                    let neighbor_block = &mem.ir[neighbor_block_id];
-                    let composed = if let NextBlock::Call {
-                        origin,
-                        destination,
-                        ..
-                    } = &composed_block.next
-                    {
-                        let mut after_call = composed_block.clone();
-                        match calling_convention {
-                            CallingConvention::Cdecl => {
-                                let eax = mem
-                                    .lang
-                                    .sleigh
-                                    .get_reg("EAX")
-                                    .and_then(|v| v.get_var())
-                                    .unwrap();
-                                after_call.registers.set_state(
-                                    eax,
-                                    Expression::from(VariableSymbol::CallResult {
-                                        call_from: *origin,
-                                        call_to: Box::new(destination.clone()),
-                                    }),
-                                );
-                            }
-                        }
-                        // all ret instructions pop return pointer off the stack
-                        let mut esp_state = after_call
-                            .registers
-                            .get_or_symbolic(mem.lang.sp)
-                            .into_owned();
-                        esp_state.add_value(4, InstructionSize::U32);
-                        after_call.registers.set_state(mem.lang.sp, esp_state);
-                        neighbor_block.inherit_state_from(&after_call)
-                    } else {
-                        neighbor_block.inherit_state_from(composed_block)
-                    };
-
-                    copy_vec.push(composed);
-                }
-                while let Some(composed) = copy_vec.pop() {
-                    // different paths lead to this composed block.
-                    // the states will be potentially different.
-                    if let Some(other) = composed_blocks.get_by_identifier(composed.identifier) {
-                        if other.registers != composed.registers && !composed.is_return() {
-                            println!("Diverging states detected to block {}", composed.identifier);
-                            // diverging states to return block can be either different error handling
-                            // or getting different cases of a result
-                            // for (k, v) in other.registers.iter() {
-                            //     if let Some(cv) = composed.registers.get_vec(k) {
-                            //         if v != cv {
-                            //             println!("Todo: Registers differ, add multiequals")
-                            //             // let mut e = v.clone();
-                            //             // e.multiequals(cv);
-                            //             // printed_regs.insert(*k, e);
-                            //         }
-                            //     } else {
-                            //         // printed_regs.push(*k);
-                            //         todo!("\tRegister {k:?} OR Not set");
-                            //     }
-                            // }
-                            // for (k, v) in &composed.registers {
-                            //     if !printed_regs.contains_key(k) && !other.registers.contains_key(k) && v != &Expression::from(VariableSymbol::Register(*k)) {
-                            //         todo!("\tRegister {k:?} Not set OR {v}")
-                            //     }
-                            // }
-                            // for (reg, state) in printed_regs {
-                            //     other.registers.insert(reg, state);
-                            // }
-                        }
-                    } else {
-                        analysis(
-                            &composed,
-                            mem.lang.sp,
-                            &mut used_call_results,
-                            &mut memory_read,
-                            &mut memory_written,
-                            &mut function_calls,
-                        );
-                        //println!("Inserting block {}", composed.identifier);
-                        composed_blocks.insert(composed);
-                    }
-                }
+        // Jumps that leave the function end it like returns. Once some are found, the function is
+        // composed again without the blocks they jump to.
+        let mut tail_calls = HashMap::new();
+        let Composition {
+            order,
+            composed_blocks,
+            loop_entries,
+            loop_carried,
+            ..
+        } = loop {
+            let composition = compose(addr, block_id, &calling_convention, &tail_calls, mem);
+            if composition.new_tail_calls.is_empty() {
+                break composition;
             }
+            tail_calls.extend(composition.new_tail_calls);
+        };
+
+        for (_, composed) in composed_blocks.iter() {
+            analysis(
+                composed,
+                &mut used_call_results,
+                &mut memory_read,
+                &mut memory_written,
+                &mut function_calls,
+            );
         }
 
-        assert_eq!(composed_blocks.len(), visited.len());
+        assert_eq!(composed_blocks.len(), order.len());
+        let stack_delta = function_stack_delta(&composed_blocks, mem);
+        let parameters = parameters(&composed_blocks, &calling_convention, mem);
+        let return_register = return_register(&composed_blocks, &calling_convention, &mem.lang);
+        let callee_parameters = function_calls
+            .iter()
+            .filter_map(|destination| match destination {
+                DestinationKind::Concrete(callee) => Some((
+                    destination.clone(),
+                    mem.functions.get(callee)?.parameters.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
+        let stack_frame = StackFrame::new(&composed_blocks, &calling_convention, &mem.lang);
+        let frame_pointer = FramePointer::find(&composed_blocks, &calling_convention, &mem.lang);
         let cfg = ControlFlowGraph::new(addr, &composed_blocks);
+        let summary = FunctionSummary::new(
+            &composed_blocks,
+            &memory_written,
+            parameters.len(),
+            return_register,
+            stack_delta,
+            cfg.properties.contains(CFGProperties::NeverReturns),
+            &mem.lang,
+        );
+        // a function that never returns has no stack delta to infer
+        if stack_delta.is_none() && !summary.never_returns {
+            println!("Unable to infer the stack delta of the function at {addr}");
+        }
         let pts = ProgramTreeStructure::new(&cfg, &composed_blocks);
+        let path_constraints = path_constraints(
+            &composed_blocks,
+            composed_blocks.slot_by_address(addr).unwrap(),
+        );
+        let loops = find_loops(
+            &cfg,
+            &composed_blocks,
+            &loop_entries
+                .into_iter()
+                .map(|(slot, entry)| (mem.ir[slot].identifier, entry))
+                .collect(),
+            &loop_carried
+                .into_iter()
+                .map(|(slot, carried)| (mem.ir[slot].identifier, carried))
+                .collect(),
+        );
         Self {
             start: addr,
             calling_convention,
@@ -227,9 +1096,23 @@ impl HighFunction {
             memory_written,
             memory_read,
             function_calls,
+            path_constraints,
+            loops,
+            stack_delta,
+            parameters,
+            return_register,
+            callee_parameters,
+            stack_frame,
+            frame_pointer,
+            summary,
         }
     }
 
+    /// Branch conditions that hold whenever the block `identifier` executes.
+    pub fn path_constraint(&self, identifier: BlockIdentifier) -> Option<&PathConstraint> {
+        self.path_constraints.get(&identifier)
+    }
+
     pub fn build_ast(&self, mem: &Memory) -> AbstractSyntaxTree {
         AbstractSyntaxTree::new(self, mem)
     }
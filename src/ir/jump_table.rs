@@ -0,0 +1,362 @@
+//! # Jump tables
+//!
+//! Compilers lower a dense `switch` into a bounds check followed by an indirect jump through a
+//! table of destinations:
+//!
+//! ```text
+//! cmp  eax, 4
+//! ja   default
+//! jmp  [table + eax*4]
+//! ```
+//!
+//! Lifted as-is, the indirect jump is a [`DestinationKind::Symbolic`] branch and control flow stops
+//! there. [`recover`] matches the destination of such a jump against the table idioms, takes the
+//! number of entries from the bounds check in the block's [`PathConstraint`] and reads the
+//! entries out of [`Memory::literal`]. [`resolve_jump_tables`] then turns the jump into a
+//! [`NextBlock::Switch`].
+//!
+//! Both absolute tables (`jmp [table + idx*N]`) and tables of offsets relative to a base address
+//! (`base + (int32_t)[table + idx*4]`, as emitted for position independent code) are understood.
+//! Entries are read little-endian. Tables that were decoded as instructions can't be read.
+
+use nodit::InclusiveInterval;
+
+use super::{
+    address::Address,
+    basic_block::{BasicBlock, BlockIdentifier, DestinationKind, NextBlock},
+    expression::{as_signed, InstructionSize, SignedOrUnsiged},
+    high_function::{HighFunction, PathConstraint},
+    split_block_at, Expression, ExpressionOp, VariableSymbol,
+};
+use crate::memory::{LiteralKind, Memory};
+
+/// Tables with more entries than this are assumed to be misread.
+const MAX_JUMP_TABLE_ENTRIES: u64 = 1024;
+
+/// Rounds of resolving jump tables in a function. Each round can uncover tables in the cases
+/// of the previous one.
+const MAX_RESOLVE_ROUNDS: usize = 8;
+
+/// A recovered jump table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JumpTable {
+    /// Value the case labels are compared against
+    pub selector: Expression,
+    /// Address of the first entry
+    pub address: Address,
+    /// Size of one entry, in bytes
+    pub entry_size: u8,
+    /// Case value and destination of every entry, in table order
+    pub cases: Vec<(u64, Address)>,
+}
+
+/// How the destination of an indirect jump is computed from a table entry.
+#[derive(Debug, PartialEq)]
+struct TableShape {
+    /// Value the table is indexed by
+    index: Expression,
+    address: u64,
+    entry_size: u8,
+    /// Entries are offsets added to this address, and whether they are sign extended
+    relative_to: Option<(u64, bool)>,
+}
+
+/// Recognize the jump table `block` jumps through, if it ends with one.
+///
+/// # Arguments
+/// * `block` - Composed block ending with an indirect jump
+/// * `constraint` - Path constraint of `block`, which has to bound the table index
+pub fn recover(block: &BasicBlock, constraint: &PathConstraint, mem: &Memory) -> Option<JumpTable> {
+    let NextBlock::Jump {
+        true_branch: DestinationKind::Symbolic(destination),
+        ..
+    } = &block.next
+    else {
+        return None;
+    };
+    let shape = table_shape(destination)?;
+    let last = constraint
+        .conditions()
+        .iter()
+        .filter_map(|condition| upper_bound(condition, &shape.index))
+        .min()?;
+    if last >= MAX_JUMP_TABLE_ENTRIES {
+        return None;
+    }
+
+    let (selector, offset) = split_offset(&shape.index);
+    let size = shape
+        .index
+        .size_of(shape.index.get_entry_point())
+        .unwrap_or(InstructionSize::U64);
+    let mut cases = Vec::new();
+    for idx in 0..=last {
+        let entry_address = shape.address + idx * shape.entry_size as u64;
+        let bytes = mem.read_bytes(entry_address.into(), shape.entry_size as usize)?;
+        let mut entry = [0u8; 8];
+        entry[..bytes.len()].copy_from_slice(bytes);
+        let entry = u64::from_le_bytes(entry);
+        let target = match shape.relative_to {
+            Some((base, true)) => {
+                base.wrapping_add(as_signed(entry, shape.entry_size.into()) as u64)
+            }
+            Some((base, false)) => base.wrapping_add(entry),
+            None => entry,
+        };
+        // an entry that doesn't lead to code means the table was misread
+        if mem.ir.get_by_address(target).is_none() {
+            return None;
+        }
+        cases.push((size.wrap(idx.wrapping_add(offset)), Address(target)));
+    }
+
+    Some(JumpTable {
+        selector,
+        address: shape.address.into(),
+        entry_size: shape.entry_size,
+        cases,
+    })
+}
+
+/// Resolve the jump tables of the function at `start` and turn their indirect jumps into
+/// [`NextBlock::Switch`]es in [`Memory::ir`]. Blocks are split so that every case starts one.
+///
+/// # Returns
+/// Whether any jump table was resolved
+pub fn resolve_jump_tables(start: Address, mem: &mut Memory) -> bool {
+    let mut resolved_any = false;
+    for _ in 0..MAX_RESOLVE_ROUNDS {
+        let hf = HighFunction::from_mem(start, mem);
+        let tables: Vec<(BlockIdentifier, JumpTable)> = hf
+            .composed_blocks
+            .iter()
+            .filter_map(|(_, block)| {
+                let constraint = hf.path_constraint(block.identifier)?;
+                recover(block, constraint, mem).map(|table| (block.identifier, table))
+            })
+            .collect();
+
+        let mut resolved = false;
+        for (identifier, table) in tables {
+            resolved |= apply(identifier, &table, mem);
+        }
+        if !resolved {
+            break;
+        }
+        resolved_any = true;
+    }
+    resolved_any
+}
+
+/// Turn the indirect jump of block `identifier` in [`Memory::ir`] into a switch over `table`.
+fn apply(identifier: BlockIdentifier, table: &JumpTable, mem: &mut Memory) -> bool {
+    let BlockIdentifier::Physical(interval) = identifier else {
+        return false;
+    };
+    // the jump is the last instruction of the block, and stays there when the block is split
+    let jump_address = interval.end();
+
+    for (_, target) in &table.cases {
+        let Some(literal) = mem.literal.get_at_point(*target) else {
+            return false;
+        };
+        let LiteralKind::Instruction(_, instructions) = &literal.kind else {
+            return false;
+        };
+        if !split_block_at(&mut mem.ir, instructions, &mem.lang, *target) {
+            return false;
+        }
+    }
+
+    let Some(slot) = mem.ir.slot_by_address(jump_address) else {
+        return false;
+    };
+    // the case values are in terms of the block's own state
+    let NextBlock::Jump {
+        true_branch: DestinationKind::Symbolic(destination),
+        ..
+    } = &mem.ir[slot].next
+    else {
+        return false;
+    };
+    let Some(shape) = table_shape(destination) else {
+        return false;
+    };
+    let (selector, _) = split_offset(&shape.index);
+    mem.ir[slot].next = NextBlock::Switch {
+        selector,
+        cases: table
+            .cases
+            .iter()
+            .map(|(value, target)| (*value, DestinationKind::Concrete(*target)))
+            .collect(),
+    };
+    true
+}
+
+/// Match `destination` against `[address + index * size]` and
+/// `base + (intN_t)[address + index * size]`.
+fn table_shape(destination: &Expression) -> Option<TableShape> {
+    let (entry, relative_to) = match destination.root_op()? {
+        ExpressionOp::Add(l, r, _) => {
+            let (l, r) = (
+                destination.get_sub_expression(*l),
+                destination.get_sub_expression(*r),
+            );
+            let (base, offset) = match (l.root_op()?, r.root_op()?) {
+                (ExpressionOp::Value(base), _) => (*base, r),
+                (_, ExpressionOp::Value(base)) => (*base, l),
+                _ => return None,
+            };
+            let is_signed = matches!(offset.root_op()?, ExpressionOp::SignExtend(..));
+            (strip_extensions(&offset), Some((base, is_signed)))
+        }
+        _ => (strip_extensions(destination), None),
+    };
+    let ExpressionOp::Variable(VariableSymbol::Ram(address, entry_size)) = entry.root_op()? else {
+        return None;
+    };
+
+    let ExpressionOp::Add(l, r, _) = address.root_op()? else {
+        return None;
+    };
+    let (l, r) = (
+        address.get_sub_expression(*l),
+        address.get_sub_expression(*r),
+    );
+    let (table, scaled) = match (l.root_op()?, r.root_op()?) {
+        (ExpressionOp::Value(table), _) => (*table, r),
+        (_, ExpressionOp::Value(table)) => (*table, l),
+        _ => return None,
+    };
+    let (index, scale) = match scaled.root_op()? {
+        ExpressionOp::Multiply(l, r, _) => match (scaled.get(*l), scaled.get(*r)) {
+            (ExpressionOp::Value(scale), _) => (scaled.get_sub_expression(*r), *scale),
+            (_, ExpressionOp::Value(scale)) => (scaled.get_sub_expression(*l), *scale),
+            _ => return None,
+        },
+        ExpressionOp::BitShiftLeft(l, r, _) => match scaled.get(*r) {
+            ExpressionOp::Value(shift) if *shift < 8 => (scaled.get_sub_expression(*l), 1 << shift),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if !matches!(entry_size, 1 | 2 | 4 | 8) || scale != *entry_size as u64 {
+        return None;
+    }
+
+    Some(TableShape {
+        index: strip_extensions(&index),
+        address: table,
+        entry_size: *entry_size,
+        relative_to,
+    })
+}
+
+/// `expression` without the sign and zero extensions around it.
+fn strip_extensions(expression: &Expression) -> Expression {
+    match expression.root_op() {
+        Some(ExpressionOp::SignExtend(inner, _, _) | ExpressionOp::ZeroExtend(inner, _, _)) => {
+            strip_extensions(&expression.get_sub_expression(*inner))
+        }
+        _ => expression.clone(),
+    }
+}
+
+/// Split `index` into the selector and the value of the first case, for tables that start
+/// at a case other than 0 and are indexed by `selector - first`.
+fn split_offset(index: &Expression) -> (Expression, u64) {
+    match index.root_op() {
+        Some(ExpressionOp::Add(l, r, size)) => match (index.get(*l), index.get(*r)) {
+            (_, ExpressionOp::Value(v)) => (
+                index.get_sub_expression(*l),
+                size.wrap(as_signed(*v, *size).wrapping_neg() as u64),
+            ),
+            (ExpressionOp::Value(v), _) => (
+                index.get_sub_expression(*r),
+                size.wrap(as_signed(*v, *size).wrapping_neg() as u64),
+            ),
+            _ => (index.clone(), 0),
+        },
+        Some(ExpressionOp::Sub(l, r, _)) => match index.get(*r) {
+            ExpressionOp::Value(v) => (index.get_sub_expression(*l), *v),
+            _ => (index.clone(), 0),
+        },
+        _ => (index.clone(), 0),
+    }
+}
+
+/// Largest value of `index` that `condition` allows, when it's an unsigned comparison of
+/// `index` against a constant.
+fn upper_bound(condition: &Expression, index: &Expression) -> Option<u64> {
+    use ExpressionOp::*;
+    let (l, r, or_equal, index_on_left) = match condition.root_op()? {
+        LessOrEquals(l, r, SignedOrUnsiged::Unsigned) => (l, r, true, true),
+        Less(l, r, SignedOrUnsiged::Unsigned) => (l, r, false, true),
+        GreaterOrEquals(l, r, SignedOrUnsiged::Unsigned) => (l, r, true, false),
+        Greater(l, r, SignedOrUnsiged::Unsigned) => (l, r, false, false),
+        _ => return None,
+    };
+    let (variable, bound) = if index_on_left { (l, r) } else { (r, l) };
+    let ExpressionOp::Value(bound) = condition.get(*bound) else {
+        return None;
+    };
+    if strip_extensions(&condition.get_sub_expression(*variable)) != *index {
+        return None;
+    }
+    if or_equal {
+        Some(*bound)
+    } else {
+        bound.checked_sub(1)
+    }
+}
+
+mod test {
+    use pcode::VarNode;
+
+    use super::{split_offset, table_shape, upper_bound, TableShape};
+    use crate::ir::expression::{
+        Expression, ExpressionOp, InstructionSize::*, SignedOrUnsiged::Unsigned, VariableSymbol,
+    };
+
+    const EAX: VarNode = VarNode {
+        id: 1,
+        offset: 0,
+        size: 4,
+    };
+
+    #[test]
+    fn test_absolute_table_shape() {
+        // jmp [0x402000 + eax*4]
+        let eax = Expression::from(ExpressionOp::var_reg(EAX));
+        let mut address = eax.clone();
+        address.multiply_value(4, U32);
+        address.add_value(0x402000, U32);
+        let destination = Expression::from(VariableSymbol::Ram(Box::new(address), 4));
+
+        assert_eq!(
+            table_shape(&destination),
+            Some(TableShape {
+                index: eax.clone(),
+                address: 0x402000,
+                entry_size: 4,
+                relative_to: None,
+            })
+        );
+
+        // cmp eax, 4; ja default
+        let mut in_bounds = eax.clone();
+        in_bounds.check_greater_value(4, U32, Unsigned);
+        in_bounds.not();
+        assert_eq!(upper_bound(&in_bounds, &eax), Some(4));
+    }
+
+    #[test]
+    fn test_table_offset() {
+        // sub eax, 3 before indexing: cases start at 3
+        let eax = Expression::from(ExpressionOp::var_reg(EAX));
+        let mut index = eax.clone();
+        index.add_value(3u32.wrapping_neg() as u64, U32);
+        assert_eq!(split_offset(&index), (eax, 3));
+    }
+}
@@ -0,0 +1,461 @@
+//! # Loops of a function
+//!
+//! Composition of [`BasicBlock`]s enters every loop with the state from before the loop. Registers
+//! and memory that a loop body changes are then *widened* at the loop header, see [`LoopCarried`]:
+//! their value is replaced with a [`VariableSymbol::LoopCarried`] of that loop, which stands for
+//! "the value at the start of the current iteration".
+//!
+//! After composition, [`find_loops`] uses back edges of the dominator tree to find
+//! [`NaturalLoop`]s, and recognizes [`InductionVariable`]s - widened values that change by a
+//! constant step every iteration, like `i` in `for (i = 0; i < n; i++)`.
+
+use std::collections::{HashMap, HashSet};
+
+use pcode::VarNode;
+
+use super::{
+    basic_block::{BasicBlock, BlockIdentifier, BlockSlot, BlockStorage, NextBlock},
+    control_flow_graph::ControlFlowGraph,
+    expression::{as_signed, InstructionSize},
+    Address, Expression, ExpressionOp, VariableSymbol,
+};
+
+/// Registers and memory that change between iterations of a loop.
+#[derive(Clone, Debug, Default)]
+pub struct LoopCarried {
+    pub registers: Vec<VarNode>,
+    /// Addresses and the size of the values stored at them
+    pub memory: Vec<(Expression, u8)>,
+}
+
+/// Value of `location` at the start of an iteration of the loop at `header`.
+fn carried_value(header: Address, location: VariableSymbol) -> ExpressionOp {
+    ExpressionOp::Variable(VariableSymbol::LoopCarried {
+        header,
+        location: Box::new(location),
+    })
+}
+
+impl LoopCarried {
+    /// Add the locations that differ between the state entering the loop header and the state
+    /// at the end of an iteration.
+    ///
+    /// The stack pointer is never widened. Stack variables are addressed relative to it, and
+    /// any drift across iterations comes from the call model rather than from the loop.
+    /// Memory takes the size of the value stored at the end of the iteration, or the size of the
+    /// stack pointer when that value has no size.
+    ///
+    /// # Returns
+    /// Whether any new location was added
+    pub fn add_changes(
+        &mut self,
+        header_state: &BasicBlock,
+        latch_state: &BasicBlock,
+        stack_pointer: VarNode,
+    ) -> bool {
+        let mut changed = false;
+        for var_node in header_state
+            .registers
+            .changed_registers(&latch_state.registers)
+        {
+            if var_node.id != stack_pointer.id && !self.registers.contains(&var_node) {
+                self.registers.push(var_node);
+                changed = true;
+            }
+        }
+        for addr in header_state.changed_memory(latch_state) {
+            if !self.memory.iter().any(|(a, _)| *a == addr) {
+                let value = &latch_state.memory[&addr];
+                let size = value
+                    .size_of(value.get_entry_point())
+                    .map_or(stack_pointer.size, |size| (size.bits() / 8) as u8);
+                self.memory.push((addr, size));
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Replace every loop-carried value of `state` with its [`VariableSymbol::LoopCarried`] of the
+    /// loop at `header`.
+    pub fn widen(&self, header: Address, state: &mut BasicBlock) {
+        for var_node in &self.registers {
+            state.registers.set_state(
+                *var_node,
+                carried_value(header, VariableSymbol::Varnode(*var_node)),
+            );
+        }
+        for (addr, size) in &self.memory {
+            let location = VariableSymbol::Ram(Box::new(addr.clone()), *size);
+            state.memory_sizes.insert(addr.clone(), *size);
+            state
+                .memory
+                .insert(addr.clone(), carried_value(header, location).into());
+        }
+    }
+}
+
+/// A loop variable that changes by a constant step every iteration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InductionVariable {
+    /// Register or memory read holding the variable, as it appears inside the loop
+    pub variable: Expression,
+    /// Value before the first iteration
+    pub init: Expression,
+    /// Amount added every iteration
+    pub step: i64,
+    pub size: InstructionSize,
+    /// Condition on the variable that keeps the loop going, if the loop tests it
+    pub condition: Option<Expression>,
+}
+
+impl InductionVariable {
+    /// Whether `destination = value` is the assignment that steps the variable, like `i = i + 1`.
+    ///
+    /// `destination` is a register or a dereferenced address, as in an assignment statement.
+    pub fn is_step(&self, destination: &Expression, value: &Expression) -> bool {
+        let Some(ExpressionOp::Variable(VariableSymbol::LoopCarried { location, .. })) =
+            self.variable.root_op()
+        else {
+            return false;
+        };
+        let writes_variable = match (location.as_ref(), destination.root_op()) {
+            (VariableSymbol::Ram(addr, _), Some(ExpressionOp::Dereference(d))) => {
+                destination.get_sub_expression(*d) == **addr
+            }
+            (
+                VariableSymbol::Varnode(var_node),
+                Some(ExpressionOp::Variable(VariableSymbol::Varnode(written))),
+            ) => var_node == written,
+            _ => false,
+        };
+        writes_variable && induction_step(value, &self.variable) == Some((self.step, self.size))
+    }
+
+    /// The bound the variable is compared against in [`Self::condition`].
+    pub fn bound(&self) -> Option<Expression> {
+        let condition = self.condition.as_ref()?;
+        match condition.root_op()? {
+            ExpressionOp::Less(l, r, _)
+            | ExpressionOp::LessOrEquals(l, r, _)
+            | ExpressionOp::Greater(l, r, _)
+            | ExpressionOp::GreaterOrEquals(l, r, _)
+            | ExpressionOp::Equals(l, r, _)
+            | ExpressionOp::NotEquals(l, r, _) => {
+                let (l, r) = (
+                    condition.get_sub_expression(*l),
+                    condition.get_sub_expression(*r),
+                );
+                if l == self.variable {
+                    Some(r)
+                } else {
+                    Some(l)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A loop with a single entry through its header.
+#[derive(Clone, Debug)]
+pub struct NaturalLoop {
+    pub header: BlockSlot,
+    /// Blocks that jump back to the header
+    pub latches: Vec<BlockSlot>,
+    /// Every block of the loop, including the header and latches
+    pub body: HashSet<BlockSlot>,
+    pub induction_variables: Vec<InductionVariable>,
+}
+
+/// Find the natural loops of `cfg` and their induction variables.
+///
+/// # Arguments
+/// * `blocks` - Composed blocks the `cfg` was built from
+/// * `entries` - Joined state entering each loop header from outside the loop, before widening
+/// * `carried` - Widened locations of each loop header
+pub fn find_loops(
+    cfg: &ControlFlowGraph,
+    blocks: &BlockStorage,
+    entries: &HashMap<BlockIdentifier, BasicBlock>,
+    carried: &HashMap<BlockIdentifier, LoopCarried>,
+) -> Vec<NaturalLoop> {
+    let mut latches: HashMap<BlockSlot, Vec<BlockSlot>> = HashMap::new();
+    for (latch, header) in cfg.back_edges() {
+        latches.entry(header).or_default().push(latch);
+    }
+
+    let mut loops: Vec<NaturalLoop> = latches
+        .into_iter()
+        .map(|(header, latches)| {
+            let body = cfg.natural_loop(header, &latches);
+            let identifier = blocks[header].identifier;
+            let induction_variables = match (entries.get(&identifier), carried.get(&identifier)) {
+                (Some(entry), Some(carried)) => {
+                    induction_variables(blocks, header, &latches, &body, entry, carried)
+                }
+                _ => Vec::new(),
+            };
+            NaturalLoop {
+                header,
+                latches,
+                body,
+                induction_variables,
+            }
+        })
+        .collect();
+    // outer loops first
+    loops.sort_by_key(|l| std::cmp::Reverse(l.body.len()));
+    loops
+}
+
+fn induction_variables(
+    blocks: &BlockStorage,
+    header: BlockSlot,
+    latches: &[BlockSlot],
+    body: &HashSet<BlockSlot>,
+    entry: &BasicBlock,
+    carried: &LoopCarried,
+) -> Vec<InductionVariable> {
+    let mut output = Vec::new();
+    let address = blocks[header].identifier.start_address();
+
+    for var_node in &carried.registers {
+        let variable = Expression::from(carried_value(address, VariableSymbol::Varnode(*var_node)));
+        let init = entry
+            .registers
+            .get(*var_node)
+            .map(|e| e.into_owned())
+            .unwrap_or_else(|| Expression::from(ExpressionOp::var_reg(*var_node)));
+        let next = latches
+            .iter()
+            .map(|l| blocks[*l].registers.get(*var_node).map(|e| e.into_owned()));
+        if let Some((step, size)) = common_step(next, &variable) {
+            output.push(InductionVariable {
+                condition: loop_condition(blocks, header, latches, body, &variable),
+                variable,
+                init,
+                step,
+                size,
+            });
+        }
+    }
+
+    for (addr, size) in &carried.memory {
+        let location = VariableSymbol::Ram(Box::new(addr.clone()), *size);
+        let variable = Expression::from(carried_value(address, location.clone()));
+        let next = latches.iter().map(|l| blocks[*l].memory.get(addr).cloned());
+        let init = entry
+            .memory
+            .get(addr)
+            .cloned()
+            .unwrap_or_else(|| Expression::from(location));
+        if let Some((step, size)) = common_step(next, &variable) {
+            output.push(InductionVariable {
+                condition: loop_condition(blocks, header, latches, body, &variable),
+                variable,
+                init,
+                step,
+                size,
+            });
+        }
+    }
+
+    output
+}
+
+/// Step that every latch adds to `variable`, when they all add the same constant.
+fn common_step(
+    mut next: impl Iterator<Item = Option<Expression>>,
+    variable: &Expression,
+) -> Option<(i64, InstructionSize)> {
+    let first = induction_step(&next.next()??, variable)?;
+    for value in next {
+        if induction_step(&value?, variable)? != first {
+            return None;
+        }
+    }
+    Some(first)
+}
+
+/// Match `variable + c` or `variable - c`.
+fn induction_step(value: &Expression, variable: &Expression) -> Option<(i64, InstructionSize)> {
+    let (l, r, size, sign) = match value.root_op()? {
+        ExpressionOp::Add(l, r, size) => (l, r, size, 1),
+        ExpressionOp::Sub(l, r, size) => (l, r, size, -1),
+        _ => return None,
+    };
+    let (l, r) = (value.get_sub_expression(*l), value.get_sub_expression(*r));
+    let constant = match (l == *variable, r == *variable) {
+        (true, _) => r,
+        (_, true) if sign == 1 => l,
+        _ => return None,
+    };
+    match constant.root_op()? {
+        ExpressionOp::Value(c) if constant.len() == 1 => {
+            // negating the lowest value overflows, like `x - 0x8000000000000000`
+            let step = as_signed(*c, *size).checked_mul(sign)?;
+            Some((step, *size)).filter(|(step, _)| *step != 0)
+        }
+        _ => None,
+    }
+}
+
+/// Condition that keeps the loop going, when the header or a latch tests `variable` to decide
+/// between staying in the loop and leaving it.
+fn loop_condition(
+    blocks: &BlockStorage,
+    header: BlockSlot,
+    latches: &[BlockSlot],
+    body: &HashSet<BlockSlot>,
+    variable: &Expression,
+) -> Option<Expression> {
+    std::iter::once(&header).chain(latches).find_map(|slot| {
+        let NextBlock::Jump {
+            condition,
+            true_branch,
+            false_branch,
+        } = &blocks[*slot].next
+        else {
+            return None;
+        };
+        let stays_on_true = blocks
+            .slot_by_destination(true_branch)
+            .is_some_and(|s| body.contains(&s));
+        let stays_on_false = blocks
+            .slot_by_destination(false_branch)
+            .is_some_and(|s| body.contains(&s));
+        let condition = match (stays_on_true, stays_on_false) {
+            (true, false) => condition.clone(),
+            (false, true) => {
+                let mut condition = condition.clone();
+                condition.not();
+                condition
+            }
+            _ => return None,
+        };
+        tests_variable(&condition, variable).then_some(condition)
+    })
+}
+
+/// Whether `condition` compares `variable` directly.
+fn tests_variable(condition: &Expression, variable: &Expression) -> bool {
+    match condition.root_op() {
+        Some(
+            ExpressionOp::Less(l, r, _)
+            | ExpressionOp::LessOrEquals(l, r, _)
+            | ExpressionOp::Greater(l, r, _)
+            | ExpressionOp::GreaterOrEquals(l, r, _)
+            | ExpressionOp::Equals(l, r, _)
+            | ExpressionOp::NotEquals(l, r, _),
+        ) => {
+            condition.get_sub_expression(*l) == *variable
+                || condition.get_sub_expression(*r) == *variable
+        }
+        _ => false,
+    }
+}
+
+mod test {
+    use pcode::VarNode;
+    use smallvec::smallvec;
+
+    use super::{induction_step, InductionVariable, LoopCarried};
+    use crate::ir::{
+        address::Address,
+        basic_block::BasicBlock,
+        expression::{
+            Expression, ExpressionOp,
+            InstructionSize::{U32, U64},
+            OpIdx, VariableSymbol,
+        },
+    };
+
+    const ECX: VarNode = VarNode {
+        id: 3,
+        offset: 0,
+        size: 4,
+    };
+    const ESP: VarNode = VarNode {
+        id: 4,
+        offset: 0,
+        size: 4,
+    };
+
+    #[test]
+    fn test_widen() {
+        let counter = Expression::from(0x404000);
+        let mut state = BasicBlock::new();
+        state.registers.set_state(ECX, Expression::from(0));
+        state.write_memory(counter.clone(), Expression::from(5), 1, ESP);
+
+        let carried = LoopCarried {
+            registers: vec![ECX],
+            memory: vec![(counter.clone(), 4)],
+        };
+        carried.widen(Address(0x401000), &mut state);
+
+        // the values at the start of an iteration are neither the values before the loop, nor
+        // the values the function was entered with
+        let ecx = state.registers.get(ECX).unwrap().into_owned();
+        assert_eq!(
+            ecx,
+            Expression::from(ExpressionOp::Variable(VariableSymbol::LoopCarried {
+                header: Address(0x401000),
+                location: Box::new(VariableSymbol::Varnode(ECX)),
+            }))
+        );
+        let memory = state.get_memory_state_or_none(&counter).unwrap();
+        assert_eq!(
+            *memory,
+            Expression::from(ExpressionOp::Variable(VariableSymbol::LoopCarried {
+                header: Address(0x401000),
+                location: Box::new(VariableSymbol::Ram(Box::new(counter.clone()), 4)),
+            }))
+        );
+        // the whole carried value is read back, not the byte written before the loop
+        assert_eq!(state.read_memory(&counter, 4).as_ref(), Some(memory));
+
+        // stepping the widened counter in memory is what a `for` header does
+        let mut next = memory.clone();
+        next.add_value(1, U32);
+        let mut destination = counter.clone();
+        destination.dereference();
+        let induction = InductionVariable {
+            variable: memory.clone(),
+            init: Expression::from(5),
+            step: 1,
+            size: U32,
+            condition: None,
+        };
+        assert!(induction.is_step(&destination, &next));
+        assert!(!induction.is_step(&destination, memory));
+    }
+
+    #[test]
+    fn test_induction_step() {
+        let ecx = Expression::from(ExpressionOp::var_reg(ECX));
+
+        let mut next = ecx.clone();
+        next.add_value(1, U32);
+        assert_eq!(induction_step(&next, &ecx), Some((1, U32)));
+
+        let mut next = ecx.clone();
+        next.add_value(0xfffffffc, U32);
+        assert_eq!(induction_step(&next, &ecx), Some((-4, U32)));
+
+        let mut next = ecx.clone();
+        next.multiply_value(2, U32);
+        assert_eq!(induction_step(&next, &ecx), None);
+
+        // the step of `rcx - 0x8000000000000000` doesn't fit an i64
+        let rcx = VarNode { size: 8, ..ECX };
+        let next = Expression::from(smallvec![
+            ExpressionOp::var_reg(rcx),
+            ExpressionOp::Value(0x8000000000000000),
+            ExpressionOp::Sub(OpIdx::from_idx(0), OpIdx::from_idx(1), U64)
+        ]);
+        let rcx = Expression::from(ExpressionOp::var_reg(rcx));
+        assert_eq!(induction_step(&next, &rcx), None);
+    }
+}
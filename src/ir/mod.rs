@@ -5,7 +5,7 @@
 //! * Convert PCode blocks to [`BasicBlock`]s by using [`PCodeToBasicBlocks`].
 //!     - *Note:* one PCode block may yield 2 or more [`BasicBlock`]s, but only for some instructions.
 //! * For each defined function:
-//!     - Compose [`BasicBlock`]s together as-if executing the function. Execute only 1 round of loops.
+//!     - Compose [`BasicBlock`]s together as-if executing the function. Loop bodies are composed until the values they change are widened, see [`loops`].
 //!     - Generate [`ControlFlowGraph`] by treating [`BasicBlock`]s as nodes.
 //!     - Perform dominance and post-dominance analysis of basic blocks to generate [`SingleEntrySingleExit`] (SESE) pairs of the graph
 //!     - Use SESE pairs to generate [`ProgramTreeStructure`] - which SESEs are nested within other SESEs. This allows us
@@ -19,25 +19,35 @@ use std::{
     collections::{HashMap, HashSet},
 };
 
-use nodit::interval::ie;
+use nodit::{interval::ie, DiscreteFinite, InclusiveInterval};
 use sleigh_compile::ldef::SleighLanguage;
 use sleigh_runtime::{Decoder, Instruction, Lifter as InstructionToPCode};
 
 pub mod abstract_syntax_tree;
 pub mod address;
 pub mod basic_block;
+pub mod calling_convention;
+pub mod compiler_spec;
 pub mod control_flow_graph;
+pub mod evaluate;
 pub mod expression;
 pub mod high_function;
+pub mod jump_table;
+pub mod loops;
 pub mod program_tree_structure;
+pub mod rewrite;
 pub mod scope;
+pub mod smt;
+pub mod stack_frame;
 pub mod type_system;
 
 use address::Address;
 use basic_block::{
     BasicBlock, BlockIdentifier, BlockSlot, BlockStorage, CpuState, DestinationKind,
 };
-use expression::{Expression, ExpressionOp, VariableSymbol};
+use expression::{
+    Expression, ExpressionOp, FloatBinaryOp, FloatCompareOp, FloatUnaryOp, VariableSymbol,
+};
 
 use crate::ir::basic_block::NextBlock;
 
@@ -65,12 +75,36 @@ pub fn lift(
     instructions: &[Instruction],
     lang: &SleighLanguage,
     storage: Option<BlockStorage>,
+) -> BlockStorage {
+    lift_with_boundaries(instructions, lang, storage, [])
+}
+
+/// [`lift`], with additional addresses that have to start a new block.
+fn lift_with_boundaries(
+    instructions: &[Instruction],
+    lang: &SleighLanguage,
+    storage: Option<BlockStorage>,
+    boundaries: impl IntoIterator<Item = Address>,
+) -> BlockStorage {
+    lift_from_state(instructions, lang, storage, boundaries, CpuState::new())
+}
+
+/// [`lift_with_boundaries`], with the registers of the first block starting at `initial` instead
+/// of their symbols.
+fn lift_from_state(
+    instructions: &[Instruction],
+    lang: &SleighLanguage,
+    storage: Option<BlockStorage>,
+    boundaries: impl IntoIterator<Item = Address>,
+    initial: CpuState,
 ) -> BlockStorage {
     let mut pcode_lifter = InstructionToPCode::new();
     let mut my_lifter = PCodeToBasicBlocks::new();
     if let Some(storage) = storage {
         my_lifter.blocks = storage;
     }
+    my_lifter.current_block.registers = initial;
+    my_lifter.known_block_boundaries.extend(boundaries);
     let mut dasm = String::new();
     for instruction in instructions {
         let pcode = pcode_lifter.lift(&lang.sleigh, instruction).unwrap();
@@ -88,6 +122,103 @@ pub fn lift(
     my_lifter.blocks
 }
 
+/// Make `address` the start of a block, by lifting the block that contains it again in two parts.
+///
+/// `instructions` are the lifted instructions that contain the block.
+///
+/// # Returns
+/// Whether a block now starts at `address`. Blocks emitted in the middle of an instruction
+/// can't be split.
+pub fn split_block_at(
+    blocks: &mut BlockStorage,
+    instructions: &[Instruction],
+    lang: &SleighLanguage,
+    address: Address,
+) -> bool {
+    let Some(slot) = blocks.slot_by_address(address) else {
+        return false;
+    };
+    let BlockIdentifier::Physical(interval) = blocks[slot].identifier else {
+        return false;
+    };
+    if interval.start() == address {
+        return true;
+    }
+    let end = interval.end().up().unwrap();
+    // the instruction after the block ends the tail block
+    let Some(first) = instructions
+        .iter()
+        .position(|i| i.inst_start == interval.start().0)
+    else {
+        return false;
+    };
+    let count = instructions[first..]
+        .iter()
+        .position(|i| i.inst_start >= end.0)
+        .map(|count| count + 1)
+        .unwrap_or(instructions.len() - first);
+
+    let relifted = lift_with_boundaries(
+        &instructions[first..first + count],
+        lang,
+        None,
+        [address, end],
+    );
+    let (Some(head), Some(tail)) = (
+        relifted.get_by_address(interval.start()),
+        relifted.get_by_address(address),
+    ) else {
+        return false;
+    };
+    if head.identifier != BlockIdentifier::Physical(ie(interval.start(), address))
+        || tail.identifier != BlockIdentifier::Physical(ie(address, end))
+    {
+        return false;
+    }
+    blocks.split(slot, head.clone(), tail.clone());
+    true
+}
+
+/// Lift the physical `block` again, with its registers starting at `initial` instead of their
+/// symbols, e.g. with the frame pointer relative to the stack pointer.
+///
+/// `instructions` are the lifted instructions that contain the block.
+///
+/// # Returns
+/// The lifted block, `None` for blocks emitted in the middle of an instruction.
+pub fn relift_block(
+    block: &BasicBlock,
+    instructions: &[Instruction],
+    lang: &SleighLanguage,
+    initial: CpuState,
+) -> Option<BasicBlock> {
+    let BlockIdentifier::Physical(interval) = block.identifier else {
+        return None;
+    };
+    let end = interval.end().up()?;
+    let first = instructions
+        .iter()
+        .position(|i| i.inst_start == interval.start().0)?;
+    // the instruction after the block ends it, as in [`split_block_at`]
+    let count = instructions[first..]
+        .iter()
+        .position(|i| i.inst_start >= end.0)
+        .map(|count| count + 1)
+        .unwrap_or(instructions.len() - first);
+
+    let relifted = lift_from_state(
+        &instructions[first..first + count],
+        lang,
+        None,
+        [interval.start(), end],
+        initial,
+    );
+    relifted
+        .get_by_address(interval.start())
+        .filter(|relifted| relifted.identifier == block.identifier)
+        .cloned()
+}
+
 struct PCodeToBasicBlocks {
     pub blocks: BlockStorage,
     current_block: BasicBlock,
@@ -243,9 +374,9 @@ impl PCodeToBasicBlocks {
 
                     assert!(pcode.inputs.second().is_invalid());
                     let value = match space {
-                        pcode::RAM_SPACE => self
-                            .current_block
-                            .get_memory_state(addr, pcode.inputs.first().size()),
+                        pcode::RAM_SPACE => {
+                            self.current_block.get_memory_state(addr, pcode.output.size)
+                        }
                         pcode::REGISTER_SPACE => {
                             self.current_block.registers.get_or_symbolic(pcode.output)
                         }
@@ -267,7 +398,6 @@ impl PCodeToBasicBlocks {
                         .into_owned();
                     let value = get_state(pcode.inputs.second(), &mut self.current_block.registers)
                         .into_owned();
-                    self.current_block.memory_writes.insert(addr.clone());
 
                     let mut assignment = addr.clone();
                     assignment.dereference();
@@ -277,7 +407,12 @@ impl PCodeToBasicBlocks {
                         .insert(instruction_pointer, assignment);
 
                     match space {
-                        pcode::RAM_SPACE => self.current_block.set_memory_state(addr, value),
+                        pcode::RAM_SPACE => {
+                            let size = pcode.inputs.second().size();
+                            self.current_block
+                                .write_memory(addr.clone(), value, size, lang.sp);
+                            self.current_block.memory_writes.insert(addr);
+                        }
                         a => todo!("Unsupported memory space {a}"),
                     }
                 }
@@ -307,8 +442,7 @@ impl PCodeToBasicBlocks {
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
                     let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
-                    left.sub(&right, pcode.inputs.first().size());
-                    left.overflow(Signed);
+                    left.signed_borrow(&right, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntCarry => {
@@ -316,8 +450,7 @@ impl PCodeToBasicBlocks {
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
                     let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
-                    left.add(&right, pcode.inputs.first().size());
-                    left.overflow(Unsigned);
+                    left.carry(&right, pcode.inputs.first().size(), Unsigned);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntSignedCarry => {
@@ -325,8 +458,7 @@ impl PCodeToBasicBlocks {
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
                     let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
-                    left.add(&right, pcode.inputs.first().size());
-                    left.overflow(Signed);
+                    left.carry(&right, pcode.inputs.first().size(), Signed);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntSub => {
@@ -418,13 +550,21 @@ impl PCodeToBasicBlocks {
                     }
                     self.current_block.registers.set_state(pcode.output, left);
                 }
-                BoolNot | IntNot | IntNotEqual => {
+                BoolNot | IntNot => {
                     let mut left =
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
                     left.not();
                     self.current_block.registers.set_state(pcode.output, left);
                 }
+                IntNotEqual => {
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.check_not_equals(&right, pcode.inputs.first().size(), Unsigned);
+                    self.current_block.registers.set_state(pcode.output, left);
+                }
                 IntOr | BoolOr => {
                     let mut left =
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
@@ -522,15 +662,17 @@ impl PCodeToBasicBlocks {
                     ));
                 }
                 ZeroExtend => {
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.zero_extend(pcode.inputs.first().size(), pcode.output.size);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 SignExtend => {
-                    // TODO: Implement proper sign extension
-                    // For now, treating same as zero extend
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.sign_extend(pcode.inputs.first().size(), pcode.output.size);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 Branch(hint) => {
@@ -558,7 +700,7 @@ impl PCodeToBasicBlocks {
                             self.current_block.next = basic_block::NextBlock::Call {
                                 origin: instruction_pointer,
                                 destination,
-                                default_return: next_instruction_pointer,
+                                default_return: Some(next_instruction_pointer),
                             }
                         }
                         pcode::BranchHint::Jump => {
@@ -583,174 +725,192 @@ impl PCodeToBasicBlocks {
                     ));
                 }
                 IntDiv => {
-                    // Unsigned division
-                    // TODO: Implement proper division - for now treating as symbolic
-                    let left =
+                    let mut left =
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
-                    let _right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.divide(&right, pcode.inputs.first().size(), Unsigned);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntSignedDiv => {
-                    // Signed division
-                    // TODO: Implement proper signed division - for now treating as symbolic
-                    let left =
+                    let mut left =
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
-                    let _right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.divide(&right, pcode.inputs.first().size(), Signed);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntRem => {
-                    // Unsigned remainder (modulo)
-                    // TODO: Implement proper modulo - for now treating as symbolic
-                    let left =
+                    let mut left =
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
-                    let _right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.remainder(&right, pcode.inputs.first().size(), Unsigned);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntSignedRem => {
-                    // Signed remainder
-                    // TODO: Implement proper signed remainder - for now treating as symbolic
-                    let left =
+                    let mut left =
                         get_state(pcode.inputs.first(), &mut self.current_block.registers)
                             .into_owned();
-                    let _right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.remainder(&right, pcode.inputs.first().size(), Signed);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
-                Subpiece(_truncation_amount) => {
-                    // Extract a piece of a larger value (like extracting AL from RAX)
-                    // For now, just copy the value
-                    // TODO: Implement proper subpiece extraction with truncation
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                Subpiece(truncation_amount) => {
+                    // Extract a piece of a larger value (like extracting AL from RAX):
+                    // drop the lowest `truncation_amount` bytes, then keep as many as the output holds
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    if truncation_amount > 0 {
+                        left.bit_shift_right(
+                            truncation_amount as u64 * 8,
+                            pcode.inputs.first().size(),
+                        );
+                    }
+                    left.truncate(pcode.inputs.first().size(), pcode.output.size);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatIsNan => {
-                    // Check if a floating-point value is NaN
-                    // TODO: Implement proper NaN checking
-                    // For now, treating as symbolic (unknown result)
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::IsNan, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatAdd => {
-                    // Floating-point addition
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_binary(&right, FloatBinaryOp::Add, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatSub => {
-                    // Floating-point subtraction
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_binary(&right, FloatBinaryOp::Sub, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatMul => {
-                    // Floating-point multiplication
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_binary(&right, FloatBinaryOp::Multiply, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatDiv => {
-                    // Floating-point division
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_binary(&right, FloatBinaryOp::Divide, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatLess => {
-                    // Floating-point less-than comparison
-                    // TODO: Implement proper float comparisons
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_compare(&right, FloatCompareOp::Less, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatLessEqual => {
-                    // Floating-point less-than-or-equal comparison
-                    // TODO: Implement proper float comparisons
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_compare(
+                        &right,
+                        FloatCompareOp::LessOrEquals,
+                        pcode.inputs.first().size(),
+                    );
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatEqual => {
-                    // Floating-point equality comparison
-                    // TODO: Implement proper float comparisons
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_compare(&right, FloatCompareOp::Equals, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatNotEqual => {
-                    // Floating-point inequality comparison
-                    // TODO: Implement proper float comparisons
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    let right = get_state(pcode.inputs.second(), &mut self.current_block.registers);
+                    left.float_compare(
+                        &right,
+                        FloatCompareOp::NotEquals,
+                        pcode.inputs.first().size(),
+                    );
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatNegate => {
-                    // Floating-point negation
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::Negate, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatAbs => {
-                    // Floating-point absolute value
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::Abs, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatSqrt => {
-                    // Floating-point square root
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::Sqrt, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatCeiling => {
-                    // Floating-point rounding operations
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::Ceiling, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatFloor => {
-                    // Floating-point rounding operations
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::Floor, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatRound => {
-                    // Floating-point rounding operations
-                    // TODO: Implement proper float operations
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_unary(FloatUnaryOp::Round, pcode.inputs.first().size());
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatToFloat => {
-                    // Floating-point conversion operations
-                    // TODO: Implement proper conversions
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_to_float(pcode.inputs.first().size(), pcode.output.size);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 FloatToInt => {
-                    // Floating-point conversion operations
-                    // TODO: Implement proper conversions
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.float_to_int(pcode.inputs.first().size(), pcode.output.size);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 IntToFloat => {
-                    // Floating-point conversion operations
-                    // TODO: Implement proper conversions
-                    let left = get_state(pcode.inputs.first(), &mut self.current_block.registers)
-                        .into_owned();
+                    let mut left =
+                        get_state(pcode.inputs.first(), &mut self.current_block.registers)
+                            .into_owned();
+                    left.int_to_float(pcode.inputs.first().size(), Signed, pcode.output.size);
                     self.current_block.registers.set_state(pcode.output, left);
                 }
                 a => {
@@ -148,24 +148,39 @@ fn compute_sese_address_ranges(
 
     if let Some(children) = ctx.pts_tree.get(&root) {
         while let Some(c_pts) = children.iter().find(|p| p.0 == branch_block) {
-            if let NextBlock::Jump {
-                true_branch,
-                false_branch,
-                ..
-            } = &ctx.blocks[branch_block].next
-            {
-                let true_branch_block = ctx
-                    .blocks
-                    .slot_by_destination(true_branch)
-                    .expect("TODO:Handle symbolic branches");
-                let false_branch_block = ctx
-                    .blocks
-                    .slot_by_destination(false_branch)
-                    .expect("TODO:Handle symbolic branches");
-                compute_sese_address_ranges(block_ownership_table, ctx, true_branch_block, *c_pts);
-                compute_sese_address_ranges(block_ownership_table, ctx, false_branch_block, *c_pts);
-            } else {
-                panic!("Unexpected start of a program segment")
+            match &ctx.blocks[branch_block].next {
+                NextBlock::Jump {
+                    true_branch,
+                    false_branch,
+                    ..
+                } => {
+                    let true_branch_block = ctx
+                        .blocks
+                        .slot_by_destination(true_branch)
+                        .expect("TODO:Handle symbolic branches");
+                    let false_branch_block = ctx
+                        .blocks
+                        .slot_by_destination(false_branch)
+                        .expect("TODO:Handle symbolic branches");
+                    compute_sese_address_ranges(
+                        block_ownership_table,
+                        ctx,
+                        true_branch_block,
+                        *c_pts,
+                    );
+                    compute_sese_address_ranges(
+                        block_ownership_table,
+                        ctx,
+                        false_branch_block,
+                        *c_pts,
+                    );
+                }
+                NextBlock::Switch { .. } => {
+                    for case_block in ctx.blocks.iter_neighbors(branch_block) {
+                        compute_sese_address_ranges(block_ownership_table, ctx, case_block, *c_pts);
+                    }
+                }
+                _ => panic!("Unexpected start of a program segment"),
             }
 
             if c_pts.1 != ctx.cfg.single_end() {
@@ -275,29 +290,40 @@ where
     let mut root_children = Vec::new();
 
     for sese in seses {
-        while let Some(top) = stack.last() {
+        // regions on the stack that stay open for `sese`
+        let mut depth = stack.len();
+        let mut is_cross_over = false;
+        while let Some(top) = depth.checked_sub(1).map(|i| stack[i]) {
             let start_top_dominates_sese = is_ancestor(top.0, sese.0, &dom);
             let end_top_postdominates_sese = is_ancestor(top.1, sese.1, &pdom);
             match (start_top_dominates_sese, end_top_postdominates_sese) {
                 // top "encloses" sese
                 (true, true) => break,
                 // top and sese aren't related
-                (false, false) => _ = stack.pop(),
+                (false, false) => depth -= 1,
 
                 (true, false) => {
                     if !is_ancestor(sese.1, top.1, &pdom) && sese.0 != top.0 {
-                        todo!("Cross-over (irreducible) segment detected: {top:?} and {sese:?}");
+                        is_cross_over = true;
+                        break;
                     }
-                    stack.pop();
+                    depth -= 1;
                 }
                 (false, true) => {
                     if is_ancestor(sese.0, top.0, &pdom) && sese.0 != top.0 {
-                        todo!("Cross-over (irreducible) segment detected: {top:?} and {sese:?}");
+                        is_cross_over = true;
+                        break;
                     }
-                    stack.pop();
+                    depth -= 1;
                 }
             }
         }
+        if is_cross_over {
+            // cross-over (irreducible) regions can't nest, so they're left out of the tree and
+            // the syntax tree reaches their blocks through `goto`s
+            continue;
+        }
+        stack.truncate(depth);
         if let Some(top) = stack.last() {
             // sese is child of top
             let children = pts.entry(*top).or_insert(Vec::new());
@@ -0,0 +1,246 @@
+//! # Rule-based expression rewriting
+//!
+//! [`Expression`] builders canonicalize while the expression is being constructed. This module
+//! adds identities on top of that without touching the builders: a [`Rewriter`] holds a registry
+//! of [`RewriteRule`]s and applies them bottom-up over the whole expression until none of them
+//! matches anymore.
+//!
+//! A rule looks at the root of a (sub-)expression and returns a replacement for it:
+//!
+//! ```ignore
+//! let mut rewriter = Rewriter::new();
+//! rewriter.add_rule("x ^ x → 0", |e: &Expression| match e.root_op()? {
+//!     ExpressionOp::Xor(l, r) if e.get_sub_expression(*l) == e.get_sub_expression(*r) => {
+//!         Some(Expression::from(0))
+//!     }
+//!     _ => None,
+//! });
+//! let simplified = rewriter.rewrite(&expression);
+//! ```
+
+use super::expression::Expression;
+
+/// Upper bound on full passes over an expression, protecting against rules that undo each other.
+const MAX_PASSES: usize = 64;
+
+/// A single pattern → replacement rule.
+///
+/// Implemented for any `Fn(&Expression) -> Option<Expression>`.
+pub trait RewriteRule {
+    /// Inspect the root of `expression` and return its replacement, or `None` if the rule doesn't match.
+    fn rewrite(&self, expression: &Expression) -> Option<Expression>;
+}
+
+impl<F> RewriteRule for F
+where
+    F: Fn(&Expression) -> Option<Expression>,
+{
+    fn rewrite(&self, expression: &Expression) -> Option<Expression> {
+        self(expression)
+    }
+}
+
+/// Registry of [`RewriteRule`]s, applied to a fixpoint.
+///
+/// Rules are tried in the order they were added, the first matching rule wins.
+#[derive(Default)]
+pub struct Rewriter {
+    rules: Vec<(&'static str, Box<dyn RewriteRule>)>,
+}
+
+impl Rewriter {
+    /// Create a rewriter without any rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a rewriter with the rules from [`rules`] registered.
+    pub fn with_default_rules() -> Self {
+        let mut rewriter = Self::new();
+        rewriter
+            .add_rule("x ^ x → 0", rules::xor_self)
+            .add_rule("x & x → x", rules::and_self)
+            .add_rule("x | x → x", rules::or_self)
+            .add_rule("x << c → x * 2^c", rules::shift_left_to_multiply);
+        rewriter
+    }
+
+    /// Register a rule. `name` identifies the rule in [`Rewriter::rule_names`].
+    pub fn add_rule<R: RewriteRule + 'static>(&mut self, name: &'static str, rule: R) -> &mut Self {
+        self.rules.push((name, Box::new(rule)));
+        self
+    }
+
+    /// Names of the registered rules, in the order they are tried.
+    pub fn rule_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|(name, _)| *name)
+    }
+
+    /// Rewrite `expression` until no rule matches anymore.
+    pub fn rewrite(&self, expression: &Expression) -> Expression {
+        let mut result = expression.clone();
+        self.rewrite_in_place(&mut result);
+        result
+    }
+
+    /// Rewrite `expression` in place until no rule matches anymore.
+    ///
+    /// # Returns
+    /// `true` if any rule changed the expression
+    pub fn rewrite_in_place(&self, expression: &mut Expression) -> bool {
+        if expression.len() == 0 {
+            return false;
+        }
+        let mut changed = false;
+        for _ in 0..MAX_PASSES {
+            let mut pass_changed = false;
+            let rewritten = self.rewrite_bottom_up(expression, &mut pass_changed);
+            if !pass_changed {
+                break;
+            }
+            *expression = rewritten;
+            changed = true;
+        }
+        changed
+    }
+
+    /// One pass: rebuild the expression from its leaves, trying the rules at every node.
+    fn rewrite_bottom_up(&self, expression: &Expression, changed: &mut bool) -> Expression {
+        let root = expression.root_kind();
+        let operands: Vec<Expression> = root
+            .operands()
+            .iter()
+            .map(|idx| self.rewrite_bottom_up(&expression.get_sub_expression(*idx), changed))
+            .collect();
+        let node = if operands.is_empty() {
+            expression.clone()
+        } else {
+            Expression::from_operands(root, &operands)
+        };
+        match self.apply_first(&node) {
+            Some(replacement) => {
+                *changed = true;
+                replacement
+            }
+            None => node,
+        }
+    }
+
+    /// Apply the first rule matching the root of `expression`.
+    fn apply_first(&self, expression: &Expression) -> Option<Expression> {
+        self.rules
+            .iter()
+            .filter_map(|(_, rule)| rule.rewrite(expression))
+            .find(|replacement| replacement != expression)
+    }
+}
+
+/// Rules registered by [`Rewriter::with_default_rules`].
+pub mod rules {
+    use crate::ir::expression::{Expression, ExpressionOp};
+
+    /// `x ^ x → 0`
+    pub fn xor_self(e: &Expression) -> Option<Expression> {
+        match e.root_op()? {
+            ExpressionOp::Xor(l, r) if e.get_sub_expression(*l) == e.get_sub_expression(*r) => {
+                Some(Expression::from(0))
+            }
+            _ => None,
+        }
+    }
+
+    /// `x & x → x`
+    pub fn and_self(e: &Expression) -> Option<Expression> {
+        match e.root_op()? {
+            ExpressionOp::And(l, r) if e.get_sub_expression(*l) == e.get_sub_expression(*r) => {
+                Some(e.get_sub_expression(*l))
+            }
+            _ => None,
+        }
+    }
+
+    /// `x | x → x`
+    pub fn or_self(e: &Expression) -> Option<Expression> {
+        match e.root_op()? {
+            ExpressionOp::Or(l, r) if e.get_sub_expression(*l) == e.get_sub_expression(*r) => {
+                Some(e.get_sub_expression(*l))
+            }
+            _ => None,
+        }
+    }
+
+    /// `x << c → x * 2^c` for a constant `c`
+    pub fn shift_left_to_multiply(e: &Expression) -> Option<Expression> {
+        match e.root_op()? {
+            ExpressionOp::BitShiftLeft(l, r, size) => match e.get(*r) {
+                ExpressionOp::Value(shift) if *shift < 64 => {
+                    let mut result = e.get_sub_expression(*l);
+                    result.multiply_value(1 << shift, *size);
+                    Some(result)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+mod test {
+    use pcode::VarNode;
+    use smallvec::smallvec;
+
+    use super::{rules, Rewriter};
+    use crate::ir::expression::{
+        Expression, ExpressionOp, InstructionSize::U32, OpIdx, VariableSymbol,
+    };
+
+    #[inline]
+    fn eax() -> ExpressionOp {
+        ExpressionOp::Variable(VariableSymbol::Varnode(VarNode {
+            id: 1,
+            offset: 0,
+            size: 4,
+        }))
+    }
+
+    #[test]
+    fn test_single_rules() {
+        let xor = Expression::from(smallvec![
+            eax(),
+            eax(),
+            ExpressionOp::Xor(OpIdx::from_idx(0), OpIdx::from_idx(1))
+        ]);
+        assert_eq!(rules::xor_self(&xor), Some(Expression::from(0)));
+        assert_eq!(rules::xor_self(&Expression::from(0)), None);
+
+        let mut shift = Expression::from(eax());
+        shift.bit_shift_left(3, U32);
+        let mut multiply = Expression::from(eax());
+        multiply.multiply_value(8, U32);
+        assert_eq!(rules::shift_left_to_multiply(&shift), Some(multiply));
+    }
+
+    #[test]
+    fn test_rewrite_to_fixpoint() {
+        // [(eax << 3) ^ (eax << 3)]
+        let expression = Expression::from(smallvec![
+            eax(),
+            ExpressionOp::Value(3),
+            ExpressionOp::BitShiftLeft(OpIdx::from_idx(0), OpIdx::from_idx(1), U32),
+            eax(),
+            ExpressionOp::Value(3),
+            ExpressionOp::BitShiftLeft(OpIdx::from_idx(3), OpIdx::from_idx(4), U32),
+            ExpressionOp::Xor(OpIdx::from_idx(2), OpIdx::from_idx(5)),
+            ExpressionOp::Dereference(OpIdx::from_idx(6))
+        ]);
+
+        let mut zero = Expression::from(0);
+        zero.dereference();
+        assert_eq!(Rewriter::with_default_rules().rewrite(&expression), zero);
+
+        let mut rewriter = Rewriter::new();
+        assert!(!rewriter.rewrite_in_place(&mut expression.clone()));
+        rewriter.add_rule("x ^ x → 0", rules::xor_self);
+        assert_eq!(rewriter.rewrite(&expression), zero);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::{
     basic_block::BlockSlot, control_flow_graph::SingleEntrySingleExit, expression::VariableSymbol,
@@ -28,6 +28,8 @@ pub struct Scope {
     /// Map of SESEs to what variables are defined at that level
     map: HashMap<SingleEntrySingleExit<BlockSlot>, SymbolMap>,
     pub parents: HashMap<SingleEntrySingleExit<BlockSlot>, Vec<SingleEntrySingleExit<BlockSlot>>>,
+    /// Blocks that a `goto` jumps to. Unlike variables, labels belong to the whole function
+    pub labels: HashSet<BlockSlot>,
 }
 
 impl Scope {
@@ -35,6 +37,7 @@ impl Scope {
         Self {
             map: HashMap::new(),
             parents: HashMap::new(),
+            labels: HashSet::new(),
         }
     }
 
@@ -86,7 +89,11 @@ impl Scope {
                 }
             }
         }
-        None
+        // sections without parents or children, e.g. the root of a function without branches
+        self.map
+            .iter()
+            .find(|(_, symbols)| symbols.contains_key(symbol))
+            .map(|(section, _)| *section)
     }
 
     pub fn get_symbol_recursive(
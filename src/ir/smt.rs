@@ -0,0 +1,701 @@
+//! # SMT-LIB2 export
+//!
+//! [`SmtScript`] serializes [`Expression`]s into [SMT-LIB2](https://smtlib.cs.uiowa.edu/) terms
+//! so that an external solver such as `z3` or `cvc5` can reason about them:
+//! - check whether the path to a block is feasible with [`SmtScript::assert_branch`]
+//! - prove two expressions equal by asserting them distinct and getting `unsat`
+//! - find inputs that reach a block from the model of a `sat` result
+//!
+//! Every [`VariableSymbol`] becomes a free bit-vector constant of the width of its varnode or
+//! memory access. Distinct memory addresses are independent constants, aliasing isn't modelled.
+//!
+//! ```ignore
+//! let mut script = SmtScript::new();
+//! script.assert_branch(&block.next, true)?;
+//! let output = Command::new("z3").arg("-in").stdin(...); // write `script.to_string()`
+//! ```
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use super::basic_block::NextBlock;
+use super::expression::{
+    Expression, ExpressionOp, FloatBinaryOp, FloatCompareOp, FloatPrecision, FloatUnaryOp, OpIdx,
+    SignedOrUnsiged, VariableSymbol,
+};
+
+/// Width of call results, which don't carry a size of their own.
+const CALL_RESULT_WIDTH: u32 = 64;
+
+/// Width of booleans when they're used as a number, matching the size of CPU flags.
+const BOOL_WIDTH: u32 = 8;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SmtError {
+    #[error("Can't express {0} in SMT-LIB2")]
+    Unsupported(&'static str),
+}
+
+/// SMT-LIB2 sort of a translated term.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Sort {
+    Bool,
+    BitVec(u32),
+    Float(FloatPrecision),
+}
+
+impl Sort {
+    /// Width of this sort when it's used as a bit-vector.
+    fn width(&self) -> u32 {
+        match self {
+            Sort::Bool => BOOL_WIDTH,
+            Sort::BitVec(width) => *width,
+            Sort::Float(precision) => float_format(*precision).0,
+        }
+    }
+}
+
+impl std::fmt::Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sort::Bool => f.write_str("Bool"),
+            Sort::BitVec(width) => write!(f, "(_ BitVec {width})"),
+            Sort::Float(precision) => {
+                let (_, eb, sb) = float_format(*precision);
+                write!(f, "(_ FloatingPoint {eb} {sb})")
+            }
+        }
+    }
+}
+
+/// Total width, exponent width and significand width (including the hidden bit) of `precision`.
+fn float_format(precision: FloatPrecision) -> (u32, u32, u32) {
+    match precision {
+        FloatPrecision::Half => (16, 5, 11),
+        FloatPrecision::Single => (32, 8, 24),
+        FloatPrecision::Double => (64, 11, 53),
+        FloatPrecision::Extended => (80, 15, 64),
+    }
+}
+
+struct Term {
+    text: String,
+    sort: Sort,
+}
+
+impl Term {
+    fn new(text: String, sort: Sort) -> Self {
+        Self { text, sort }
+    }
+
+    fn bool(text: String) -> Self {
+        Self::new(text, Sort::Bool)
+    }
+
+    fn bv(text: String, width: u32) -> Self {
+        Self::new(text, Sort::BitVec(width))
+    }
+
+    /// This term as a bit-vector of `width` bits, truncating or zero extending it.
+    fn into_bv(self, width: u32) -> Result<String, SmtError> {
+        Ok(match self.sort {
+            Sort::Bool => format!("(ite {} (_ bv1 {width}) (_ bv0 {width}))", self.text),
+            Sort::BitVec(w) if w == width => self.text,
+            Sort::BitVec(w) if w > width => format!("((_ extract {} 0) {})", width - 1, self.text),
+            Sort::BitVec(w) => format!("((_ zero_extend {}) {})", width - w, self.text),
+            Sort::Float(_) => {
+                return Err(SmtError::Unsupported(
+                    "floating-point value used as an integer",
+                ))
+            }
+        })
+    }
+
+    /// This term as a boolean, where any non-zero bit-vector is `true`.
+    fn into_bool(self) -> Result<String, SmtError> {
+        Ok(match self.sort {
+            Sort::Bool => self.text,
+            Sort::BitVec(w) => format!("(distinct {} (_ bv0 {w}))", self.text),
+            Sort::Float(_) => {
+                return Err(SmtError::Unsupported(
+                    "floating-point value used as a boolean",
+                ))
+            }
+        })
+    }
+
+    /// This term as a floating-point number, reinterpreting the bits of bit-vectors.
+    fn into_float(self, precision: FloatPrecision) -> Result<String, SmtError> {
+        let (width, eb, sb) = float_format(precision);
+        match self.sort {
+            Sort::Float(p) if p == precision => Ok(self.text),
+            Sort::Float(_) => Ok(format!("((_ to_fp {eb} {sb}) RNE {})", self.text)),
+            _ if precision == FloatPrecision::Extended => {
+                Err(SmtError::Unsupported("x87 extended precision bit pattern"))
+            }
+            _ => Ok(format!("((_ to_fp {eb} {sb}) {})", self.into_bv(width)?)),
+        }
+    }
+}
+
+fn bv_constant(value: u128, width: u32) -> String {
+    let value = match width {
+        width if width >= 128 => value,
+        width => value & ((1u128 << width) - 1),
+    };
+    format!("(_ bv{value} {width})")
+}
+
+/// An SMT-LIB2 script of declarations and assertions over [`Expression`]s.
+///
+/// The [`Display`](std::fmt::Display) output is a complete script ending in `(check-sat)`.
+/// Append `(get-model)` to get the values of the variables when the result is `sat`.
+#[derive(Default)]
+pub struct SmtScript {
+    /// Declared constant for each symbolic variable, see [`Self::symbol_name`]
+    symbols: HashMap<VariableSymbol, String>,
+    declarations: Vec<String>,
+    assertions: Vec<String>,
+    uses_floats: bool,
+    fresh_names: usize,
+}
+
+impl SmtScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name of the constant declared for `symbol`, to map a model back to variables.
+    pub fn symbol_name(&self, symbol: &VariableSymbol) -> Option<&str> {
+        self.symbols.get(symbol).map(String::as_str)
+    }
+
+    /// Iterate over every declared variable and the name of its constant.
+    pub fn symbols(&self) -> impl Iterator<Item = (&VariableSymbol, &str)> {
+        self.symbols
+            .iter()
+            .map(|(symbol, name)| (symbol, name.as_str()))
+    }
+
+    /// Assert that `condition` holds, i.e. is non-zero.
+    pub fn assert(&mut self, condition: &Expression) -> Result<(), SmtError> {
+        let term = self.translate(condition, condition.get_entry_point(), None)?;
+        let term = term.into_bool()?;
+        self.assertions.push(term);
+        Ok(())
+    }
+
+    /// Assert that `condition` doesn't hold, i.e. is zero.
+    pub fn assert_not(&mut self, condition: &Expression) -> Result<(), SmtError> {
+        let term = self.translate(condition, condition.get_entry_point(), None)?;
+        let term = term.into_bool()?;
+        self.assertions.push(format!("(not {term})"));
+        Ok(())
+    }
+
+    /// Assert that the branch of `next` is taken, or not taken when `taken` is false.
+    ///
+    /// Only [`NextBlock::Jump`] has a condition, every other kind of successor adds nothing.
+    pub fn assert_branch(&mut self, next: &NextBlock, taken: bool) -> Result<(), SmtError> {
+        match next {
+            NextBlock::Jump { condition, .. } if taken => self.assert(condition),
+            NextBlock::Jump { condition, .. } => self.assert_not(condition),
+            _ => Ok(()),
+        }
+    }
+
+    /// Assert that `lhs` and `rhs` differ. An `unsat` result proves them equal.
+    pub fn assert_distinct(&mut self, lhs: &Expression, rhs: &Expression) -> Result<(), SmtError> {
+        let lhs = self.translate(lhs, lhs.get_entry_point(), None)?;
+        let width = lhs.sort.width();
+        let rhs = self.translate(rhs, rhs.get_entry_point(), Some(width))?;
+        let term = match (lhs.sort, rhs.sort) {
+            (Sort::Bool, Sort::Bool) => format!("(distinct {} {})", lhs.text, rhs.text),
+            _ => format!("(distinct {} {})", lhs.into_bv(width)?, rhs.into_bv(width)?),
+        };
+        self.assertions.push(term);
+        Ok(())
+    }
+
+    /// Define `name` as the value of `expression`, e.g. a register or memory state of a block.
+    ///
+    /// The name is quoted, so any name without `|` or `\` is allowed.
+    pub fn define(&mut self, name: &str, expression: &Expression) -> Result<(), SmtError> {
+        let term = self.translate(expression, expression.get_entry_point(), None)?;
+        self.declarations.push(format!(
+            "(define-fun |{name}| () {} {})",
+            term.sort, term.text
+        ));
+        Ok(())
+    }
+
+    fn declare(&mut self, symbol: &VariableSymbol, sort: Sort) -> String {
+        if let Some(name) = self.symbols.get(symbol) {
+            return name.clone();
+        }
+        let name = format!("sym{}", self.symbols.len());
+        self.declarations
+            .push(format!("(declare-const {name} {sort}) ; {symbol}"));
+        self.symbols.insert(symbol.clone(), name.clone());
+        name
+    }
+
+    fn fresh(&mut self, prefix: &str, sort: Sort) -> String {
+        let name = format!("{prefix}{}", self.fresh_names);
+        self.fresh_names += 1;
+        self.declarations
+            .push(format!("(declare-const {name} {sort})"));
+        name
+    }
+
+    /// Translate the operation at `idx` to a bit-vector as wide as the operation itself.
+    fn translate_own_width(
+        &mut self,
+        expression: &Expression,
+        idx: OpIdx,
+    ) -> Result<(String, u32), SmtError> {
+        let term = self.translate(expression, idx, None)?;
+        let width = term.sort.width();
+        Ok((term.into_bv(width)?, width))
+    }
+
+    /// Translate the operands of an operation without a size to terms of the same width.
+    ///
+    /// The width comes from `width` when known, otherwise from the first non-constant operand.
+    /// Two constants take the default width of [`Self::translate`].
+    fn translate_pair(
+        &mut self,
+        expression: &Expression,
+        l: OpIdx,
+        r: OpIdx,
+        width: Option<u32>,
+    ) -> Result<(Term, Term, u32), SmtError> {
+        let is_value = |idx| matches!(expression.get(idx), ExpressionOp::Value(_));
+        if width.is_none() && is_value(l) && !is_value(r) {
+            let (r, l, width) = self.translate_pair(expression, r, l, None)?;
+            return Ok((l, r, width));
+        }
+        let l = self.translate(expression, l, width)?;
+        let width = width.unwrap_or(l.sort.width());
+        let r = self.translate(expression, r, Some(width))?;
+        Ok((l, r, width))
+    }
+
+    fn translate_bv(
+        &mut self,
+        expression: &Expression,
+        idx: OpIdx,
+        width: u32,
+    ) -> Result<String, SmtError> {
+        self.translate(expression, idx, Some(width))?.into_bv(width)
+    }
+
+    fn translate_float(
+        &mut self,
+        expression: &Expression,
+        idx: OpIdx,
+        precision: FloatPrecision,
+    ) -> Result<String, SmtError> {
+        self.uses_floats = true;
+        let width = float_format(precision).0;
+        self.translate(expression, idx, Some(width))?
+            .into_float(precision)
+    }
+
+    /// Translate the operation at `idx`. Constants and variables without a size of their own
+    /// take `width` bits, or 64 bits when it's `None`.
+    fn translate(
+        &mut self,
+        expression: &Expression,
+        idx: OpIdx,
+        width: Option<u32>,
+    ) -> Result<Term, SmtError> {
+        use ExpressionOp::*;
+
+        Ok(match expression.get(idx) {
+            Value(v) => {
+                let width = width.unwrap_or(64);
+                Term::bv(bv_constant(*v as u128, width), width)
+            }
+            FloatValue(bits, precision) => {
+                self.uses_floats = true;
+                let (total, eb, sb) = float_format(*precision);
+                Term::new(
+                    format!(
+                        "((_ to_fp {eb} {sb}) {})",
+                        bv_constant(*bits as u128, total)
+                    ),
+                    Sort::Float(*precision),
+                )
+            }
+            Variable(symbol) => {
+                let width = symbol
+                    .size()
+                    .map_or(CALL_RESULT_WIDTH, |size| size as u32 * 8);
+                Term::bv(self.declare(symbol, Sort::BitVec(width)), width)
+            }
+            DestinationRegister(_) => return Err(SmtError::Unsupported("destination register")),
+            Interrupt(_) => return Err(SmtError::Unsupported("interrupt")),
+            Dereference(_) => return Err(SmtError::Unsupported("dereference without size")),
+            Overflow(_, _) => return Err(SmtError::Unsupported("overflow")),
+            Assign(_, r) => self.translate(expression, *r, width)?,
+            Multiequals(l, r) => {
+                // Either of the merged values, so a fresh constant equal to one of them
+                let l = self.translate(expression, *l, width)?;
+                let sort = l.sort;
+                let r = self.translate(expression, *r, Some(sort.width()))?;
+                let (l, r) = match sort {
+                    Sort::Bool => (l.text, r.into_bool()?),
+                    Sort::BitVec(width) => (l.text, r.into_bv(width)?),
+                    Sort::Float(precision) => (l.text, r.into_float(precision)?),
+                };
+                let name = self.fresh("phi", sort);
+                self.assertions
+                    .push(format!("(or (= {name} {l}) (= {name} {r}))"));
+                Term::new(name, sort)
+            }
+
+            Add(l, r, size)
+            | Sub(l, r, size)
+            | Multiply(l, r, size)
+            | Divide(l, r, size, _)
+            | Remainder(l, r, size, _)
+            | BitShiftRight(l, r, size)
+            | BitShiftLeft(l, r, size) => {
+                let function = match expression.get(idx) {
+                    Add(..) => "bvadd",
+                    Sub(..) => "bvsub",
+                    Multiply(..) => "bvmul",
+                    Divide(_, _, _, SignedOrUnsiged::Signed) => "bvsdiv",
+                    Divide(_, _, _, SignedOrUnsiged::Unsigned) => "bvudiv",
+                    Remainder(_, _, _, SignedOrUnsiged::Signed) => "bvsrem",
+                    Remainder(_, _, _, SignedOrUnsiged::Unsigned) => "bvurem",
+                    BitShiftRight(..) => "bvlshr",
+                    _ => "bvshl",
+                };
+                let width = size.bits();
+                let l = self.translate_bv(expression, *l, width)?;
+                let r = self.translate_bv(expression, *r, width)?;
+                Term::bv(format!("({function} {l} {r})"), width)
+            }
+
+            LessOrEquals(l, r, sgn)
+            | Less(l, r, sgn)
+            | GreaterOrEquals(l, r, sgn)
+            | Greater(l, r, sgn)
+            | Equals(l, r, sgn)
+            | NotEquals(l, r, sgn) => {
+                let signed = *sgn == SignedOrUnsiged::Signed;
+                let function = match expression.get(idx) {
+                    LessOrEquals(..) if signed => "bvsle",
+                    LessOrEquals(..) => "bvule",
+                    Less(..) if signed => "bvslt",
+                    Less(..) => "bvult",
+                    GreaterOrEquals(..) if signed => "bvsge",
+                    GreaterOrEquals(..) => "bvuge",
+                    Greater(..) if signed => "bvsgt",
+                    Greater(..) => "bvugt",
+                    Equals(..) => "=",
+                    _ => "distinct",
+                };
+                let (l, r, width) = self.translate_pair(expression, *l, *r, None)?;
+                Term::bool(format!(
+                    "({function} {} {})",
+                    l.into_bv(width)?,
+                    r.into_bv(width)?
+                ))
+            }
+
+            And(l, r) | Or(l, r) | Xor(l, r) => {
+                let (l, r, width) = self.translate_pair(expression, *l, *r, width)?;
+                let is_bool = l.sort == Sort::Bool && r.sort == Sort::Bool;
+                let function = match (expression.get(idx), is_bool) {
+                    (And(..), true) => "and",
+                    (And(..), false) => "bvand",
+                    (Or(..), true) => "or",
+                    (Or(..), false) => "bvor",
+                    (_, true) => "xor",
+                    (_, false) => "bvxor",
+                };
+                if is_bool {
+                    Term::bool(format!("({function} {} {})", l.text, r.text))
+                } else {
+                    Term::bv(
+                        format!("({function} {} {})", l.into_bv(width)?, r.into_bv(width)?),
+                        width,
+                    )
+                }
+            }
+            LogicalAnd(l, r) | LogicalOr(l, r) => {
+                let function = match expression.get(idx) {
+                    LogicalAnd(..) => "and",
+                    _ => "or",
+                };
+                let l = self.translate(expression, *l, None)?.into_bool()?;
+                let r = self.translate(expression, *r, None)?.into_bool()?;
+                Term::bool(format!("({function} {l} {r})"))
+            }
+            Not(l) => {
+                let l = self.translate(expression, *l, width)?;
+                match l.sort {
+                    Sort::Bool => Term::bool(format!("(not {})", l.text)),
+                    sort => {
+                        let width = sort.width();
+                        Term::bv(format!("(bvnot {})", l.into_bv(width)?), width)
+                    }
+                }
+            }
+            CountOnes(l) => {
+                let (operand, operand_width) = self.translate_own_width(expression, *l)?;
+                let bits = (0..operand_width)
+                    .map(|bit| {
+                        format!(
+                            "((_ zero_extend {}) ((_ extract {bit} {bit}) popcount_x))",
+                            operand_width - 1
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let sum = match bits.len() {
+                    1 => bits[0].clone(),
+                    _ => format!("(bvadd {})", bits.join(" ")),
+                };
+                Term::bv(
+                    format!("(let ((popcount_x {operand})) {sum})"),
+                    operand_width,
+                )
+            }
+
+            SignExtend(l, from, to) | ZeroExtend(l, from, to) | Truncate(l, from, to) => {
+                let (from, to) = (from.bits(), to.bits());
+                let l = self.translate_bv(expression, *l, from)?;
+                let text = match expression.get(idx) {
+                    _ if from == to => l,
+                    _ if from > to => format!("((_ extract {} 0) {l})", to - 1),
+                    SignExtend(..) => format!("((_ sign_extend {}) {l})", to - from),
+                    _ => format!("((_ zero_extend {}) {l})", to - from),
+                };
+                Term::bv(text, to)
+            }
+
+            Carry(l, r, size, sgn) => {
+                let width = size.bits();
+                let l = self.translate_bv(expression, *l, width)?;
+                let r = self.translate_bv(expression, *r, width)?;
+                Term::bool(match sgn {
+                    SignedOrUnsiged::Unsigned => format!("(bvult (bvadd {l} {r}) {l})"),
+                    SignedOrUnsiged::Signed => signed_overflow(&l, &r, "bvadd", true, width),
+                })
+            }
+            Borrow(l, r, size) => {
+                let width = size.bits();
+                let l = self.translate_bv(expression, *l, width)?;
+                let r = self.translate_bv(expression, *r, width)?;
+                Term::bool(signed_overflow(&l, &r, "bvsub", false, width))
+            }
+
+            FloatBinary(l, r, op, precision) => {
+                let function = match op {
+                    FloatBinaryOp::Add => "fp.add",
+                    FloatBinaryOp::Sub => "fp.sub",
+                    FloatBinaryOp::Multiply => "fp.mul",
+                    FloatBinaryOp::Divide => "fp.div",
+                };
+                let l = self.translate_float(expression, *l, *precision)?;
+                let r = self.translate_float(expression, *r, *precision)?;
+                Term::new(format!("({function} RNE {l} {r})"), Sort::Float(*precision))
+            }
+            FloatCompare(l, r, op, precision) => {
+                let l = self.translate_float(expression, *l, *precision)?;
+                let r = self.translate_float(expression, *r, *precision)?;
+                Term::bool(match op {
+                    FloatCompareOp::Less => format!("(fp.lt {l} {r})"),
+                    FloatCompareOp::LessOrEquals => format!("(fp.leq {l} {r})"),
+                    FloatCompareOp::Equals => format!("(fp.eq {l} {r})"),
+                    FloatCompareOp::NotEquals => format!("(not (fp.eq {l} {r}))"),
+                })
+            }
+            FloatUnary(l, op, precision) => {
+                let l = self.translate_float(expression, *l, *precision)?;
+                let text = match op {
+                    FloatUnaryOp::Negate => format!("(fp.neg {l})"),
+                    FloatUnaryOp::Abs => format!("(fp.abs {l})"),
+                    FloatUnaryOp::Sqrt => format!("(fp.sqrt RNE {l})"),
+                    FloatUnaryOp::Ceiling => format!("(fp.roundToIntegral RTP {l})"),
+                    FloatUnaryOp::Floor => format!("(fp.roundToIntegral RTN {l})"),
+                    FloatUnaryOp::Round => format!("(fp.roundToIntegral RNA {l})"),
+                    FloatUnaryOp::IsNan => return Ok(Term::bool(format!("(fp.isNaN {l})"))),
+                };
+                Term::new(text, Sort::Float(*precision))
+            }
+            IntToFloat(l, sgn, precision) => {
+                self.uses_floats = true;
+                let (_, eb, sb) = float_format(*precision);
+                let (l, _) = self.translate_own_width(expression, *l)?;
+                let function = match sgn {
+                    SignedOrUnsiged::Signed => "to_fp",
+                    SignedOrUnsiged::Unsigned => "to_fp_unsigned",
+                };
+                Term::new(
+                    format!("((_ {function} {eb} {sb}) RNE {l})"),
+                    Sort::Float(*precision),
+                )
+            }
+            FloatToFloat(l, from, to) => {
+                let (_, eb, sb) = float_format(*to);
+                let l = self.translate_float(expression, *l, *from)?;
+                Term::new(format!("((_ to_fp {eb} {sb}) RNE {l})"), Sort::Float(*to))
+            }
+            FloatToInt(l, size) => {
+                let operand = self.translate(expression, *l, None)?;
+                let precision = match operand.sort {
+                    Sort::Float(precision) => precision,
+                    _ => FloatPrecision::Double,
+                };
+                self.uses_floats = true;
+                let width = size.bits();
+                let l = operand.into_float(precision)?;
+                Term::bv(format!("((_ fp.to_sbv {width}) RTZ {l})"), width)
+            }
+        })
+    }
+}
+
+/// Signed overflow of `l op r`: the operands have the same sign for an addition, or different
+/// signs for a subtraction, and the result has a different sign than `l`.
+fn signed_overflow(l: &str, r: &str, function: &str, same_sign: bool, width: u32) -> String {
+    let sign = |term: &str| format!("((_ extract {0} {0}) {term})", width - 1);
+    let operands = if same_sign { "=" } else { "distinct" };
+    format!(
+        "(and ({operands} {} {}) (distinct {} {}))",
+        sign(l),
+        sign(r),
+        sign(&format!("({function} {l} {r})")),
+        sign(l)
+    )
+}
+
+impl std::fmt::Display for SmtScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let logic = if self.uses_floats { "QF_FPBV" } else { "QF_BV" };
+        writeln!(f, "(set-logic {logic})")?;
+        for declaration in &self.declarations {
+            writeln!(f, "{declaration}")?;
+        }
+        for assertion in &self.assertions {
+            writeln!(f, "(assert {assertion})")?;
+        }
+        f.write_str("(check-sat)\n")
+    }
+}
+
+impl Expression {
+    /// This expression as a complete SMT-LIB2 script that defines it as `expr`.
+    ///
+    /// The script declares the variables of the expression and asserts the constraints of its
+    /// merged values. Build an [`SmtScript`] instead to add assertions of your own.
+    pub fn to_smtlib(&self) -> Result<String, SmtError> {
+        let mut script = SmtScript::new();
+        script.define("expr", self)?;
+        Ok(script.to_string())
+    }
+}
+
+mod test {
+    use pcode::VarNode;
+    use smallvec::smallvec;
+
+    use super::SmtScript;
+    use crate::ir::expression::{
+        Expression, ExpressionOp,
+        InstructionSize::{U32, U8},
+        OpIdx,
+        SignedOrUnsiged::{Signed, Unsigned},
+        VariableSymbol,
+    };
+
+    const EAX: VarNode = VarNode {
+        id: 1,
+        offset: 0,
+        size: 4,
+    };
+
+    const EBX: VarNode = VarNode {
+        id: 2,
+        offset: 0,
+        size: 4,
+    };
+
+    #[test]
+    fn test_smtlib_condition() {
+        let mut e = Expression::from(VariableSymbol::Varnode(EAX));
+        e.truncate(U32, U8);
+        e.sign_extend(U8, U32);
+        e.check_less(&Expression::from(VariableSymbol::Varnode(EBX)), U32, Signed);
+
+        let mut script = SmtScript::new();
+        script.assert(&e).unwrap();
+        let script_text = script.to_string();
+        let lines = script_text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "(set-logic QF_BV)");
+        assert!(lines[1].starts_with("(declare-const sym0 (_ BitVec 32))"));
+        assert!(lines[2].starts_with("(declare-const sym1 (_ BitVec 32))"));
+        assert_eq!(
+            lines[3],
+            "(assert (bvslt ((_ sign_extend 24) ((_ extract 7 0) sym0)) sym1))"
+        );
+        assert_eq!(lines[4], "(check-sat)");
+        assert_eq!(
+            script.symbol_name(&VariableSymbol::Varnode(EBX)),
+            Some("sym1")
+        );
+    }
+
+    #[test]
+    fn test_smtlib_equivalence() {
+        let eax = Expression::from(VariableSymbol::Varnode(EAX));
+        let mut lhs = eax.clone();
+        lhs.add(&eax, U32);
+        let mut rhs = eax.clone();
+        rhs.multiply_value(2, U32);
+
+        let mut script = SmtScript::new();
+        script.assert_distinct(&lhs, &rhs).unwrap();
+        let script = script.to_string();
+        assert!(script.contains("(assert (distinct "));
+        assert_eq!(script.matches("declare-const").count(), 1);
+
+        let mut e = eax.clone();
+        e.check_greater_value(10, U32, Unsigned);
+        let script = e.to_smtlib().unwrap();
+        assert!(script.contains("(declare-const sym0 (_ BitVec 32))"));
+        assert!(script.contains("(define-fun |expr| () Bool (bvugt sym0 (_ bv10 32)))"));
+
+        let mut e = eax.clone();
+        e.multiequals(&Expression::from(VariableSymbol::Varnode(EBX)));
+        let script = e.to_smtlib().unwrap();
+        assert!(script.contains("(declare-const phi0 (_ BitVec 32))"));
+        assert!(script.contains("(assert (or (= phi0 sym0) (= phi0 sym1)))"));
+
+        // the merged operand is declared once, though its width decides the popcount's
+        e.count_ones();
+        let script = e.to_smtlib().unwrap();
+        assert_eq!(script.matches("(declare-const phi").count(), 1);
+        assert_eq!(script.matches("(assert (or ").count(), 1);
+    }
+
+    #[test]
+    fn test_smtlib_constants() {
+        // [3 == 3], which the builders would have folded
+        let e = Expression::from(smallvec![
+            ExpressionOp::Value(3),
+            ExpressionOp::Value(3),
+            ExpressionOp::Equals(OpIdx::from_idx(0), OpIdx::from_idx(1), Unsigned)
+        ]);
+        let mut script = SmtScript::new();
+        script.assert(&e).unwrap();
+        assert!(script
+            .to_string()
+            .contains("(assert (= (_ bv3 64) (_ bv3 64)))"));
+    }
+}
@@ -0,0 +1,277 @@
+//! # Stack frames
+//!
+//! Composed blocks address the stack relative to the stack pointer at the function entry, which
+//! points at the return address. Accesses below it are locals, accesses above the return address
+//! are stack parameters:
+//!
+//! ```text
+//!   [SP + 0x8]    param_8
+//!   [SP + 0x0]    return address
+//!   [SP - 0x8]    saved RBP
+//!   [SP - 0x14]   local_14
+//! ```
+//!
+//! A [`StackFrame`] partitions those accesses into non-overlapping [`StackVariable`]s. Accesses
+//! inside a bigger access belong to it, e.g. a 4-byte read of an 8-byte local.
+
+use std::{borrow::Cow, ops::Range};
+
+use pcode::VarNode;
+use sleigh_compile::ldef::SleighLanguage;
+
+use super::{
+    basic_block::{BlockStorage, CpuState, NextBlock},
+    calling_convention::ConventionModel,
+    expression::{as_signed, OpIdx},
+    Expression, ExpressionOp, VariableSymbol,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StackVariableKind {
+    Local,
+    Parameter,
+}
+
+/// Contiguous bytes of the stack frame accessed as one variable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackVariable {
+    /// Offset from the stack pointer at the function entry
+    pub offset: i64,
+    pub size: u32,
+    pub kind: StackVariableKind,
+}
+
+impl StackVariable {
+    /// Default name, `local_14` for `[SP - 0x14]` or `param_8` for `[SP + 0x8]`.
+    pub fn name(&self) -> String {
+        match self.kind {
+            StackVariableKind::Local => format!("local_{:x}", self.offset.unsigned_abs()),
+            StackVariableKind::Parameter => format!("param_{:x}", self.offset),
+        }
+    }
+
+    /// Symbol of the variable in the function's [`Scope`](super::scope::Scope). Its size saturates
+    /// for variables bigger than a symbol can hold, e.g. buffers.
+    pub fn symbol(&self, sp: VarNode) -> VariableSymbol {
+        let mut addr = Expression::from(ExpressionOp::var_reg(sp));
+        addr.add_value(self.offset as u64, sp.size);
+        VariableSymbol::Ram(Box::new(addr), self.size.try_into().unwrap_or(u8::MAX))
+    }
+
+    fn end(&self) -> i64 {
+        self.offset + self.size as i64
+    }
+}
+
+/// Stack variables of a function, ordered by offset.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StackFrame {
+    variables: Vec<StackVariable>,
+}
+
+impl StackFrame {
+    /// Partition the stack accesses of `composed_blocks`.
+    ///
+    /// Registers the function saves for its caller aren't variables, and neither is the return
+    /// address. Reads don't carry a size, they cover the bytes up to the next access, at most a
+    /// stack slot of the `calling_convention`.
+    pub fn new(
+        composed_blocks: &BlockStorage,
+        calling_convention: &ConventionModel,
+        lang: &SleighLanguage,
+    ) -> Self {
+        let sp = lang.sp;
+        let mut accesses: Vec<(i64, Option<u32>)> = Vec::new();
+        for (_, block) in composed_blocks.iter() {
+            for (addr, value) in &block.memory {
+                let Some(offset) = stack_offset(addr, sp) else {
+                    continue;
+                };
+                let saved_register = matches!(
+                    value.root_op(),
+                    Some(ExpressionOp::Variable(VariableSymbol::Varnode(r)))
+                        if value.len() == 1 && calling_convention.is_preserved(lang, *r)
+                );
+                if !saved_register {
+                    let size = value.size_of(value.get_entry_point());
+                    accesses.push((offset, size.map(|s| s.bits() / 8)));
+                }
+            }
+            let registers = CpuState::new().changed_registers(&block.registers);
+            let states = registers.iter().filter_map(|var| block.registers.get(*var));
+            let condition = match &block.next {
+                NextBlock::Jump { condition, .. } => Some(condition),
+                NextBlock::Switch { selector, .. } => Some(selector),
+                _ => None,
+            };
+            for expr in states
+                .chain(
+                    block
+                        .memory
+                        .iter()
+                        .flat_map(|(a, v)| [a, v])
+                        .map(Cow::Borrowed),
+                )
+                .chain(condition.map(Cow::Borrowed))
+            {
+                for op in expr.iter() {
+                    if let ExpressionOp::Dereference(d) = op {
+                        if let Some(offset) = stack_offset(&expr.get_sub_expression(*d), sp) {
+                            accesses.push((offset, None));
+                        }
+                    }
+                }
+            }
+        }
+        accesses.sort();
+        accesses.dedup();
+
+        let slot = calling_convention.stack_slot_size as i64;
+        let return_address = 0..calling_convention.return_address_size as i64;
+        Self {
+            variables: partition(&accesses, slot, return_address),
+        }
+    }
+
+    pub fn variables(&self) -> &[StackVariable] {
+        &self.variables
+    }
+
+    /// Variable holding the byte at `addr`, if `addr` is relative to the entry stack pointer.
+    pub fn variable_at(&self, addr: &Expression, sp: VarNode) -> Option<&StackVariable> {
+        let offset = stack_offset(addr, sp)?;
+        self.variables
+            .iter()
+            .find(|v| v.offset <= offset && offset < v.end())
+    }
+}
+
+/// Merge the sorted `(offset, size)` accesses into variables, skipping the return address. Unsized
+/// accesses cover the bytes up to the next access, at most a `slot`.
+fn partition(
+    accesses: &[(i64, Option<u32>)],
+    slot: i64,
+    return_address: Range<i64>,
+) -> Vec<StackVariable> {
+    let mut variables: Vec<StackVariable> = Vec::new();
+    for (idx, &(offset, size)) in accesses.iter().enumerate() {
+        if return_address.contains(&offset) {
+            continue;
+        }
+        let size = match size {
+            Some(size) => size as i64,
+            None => accesses[idx + 1..]
+                .iter()
+                .map(|(next, _)| next - offset)
+                .find(|gap| *gap > 0)
+                .map_or(slot, |gap| gap.min(slot)),
+        };
+        match variables.last_mut() {
+            Some(last) if offset < last.end() => {
+                last.size = last.size.max((offset + size - last.offset) as u32);
+            }
+            _ => variables.push(StackVariable {
+                offset,
+                size: size as u32,
+                kind: if offset < 0 {
+                    StackVariableKind::Local
+                } else {
+                    StackVariableKind::Parameter
+                },
+            }),
+        }
+    }
+    variables
+}
+
+/// Preserved register pointing into the stack frame, like `EBP` after `push ebp; mov ebp, esp`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FramePointer {
+    pub register: VarNode,
+    /// Offset from the stack pointer at the function entry
+    pub offset: i64,
+}
+
+impl FramePointer {
+    /// Preserved registers, other than the stack pointer, that hold an address relative to the
+    /// entry stack pointer in `registers`.
+    pub fn in_state(
+        registers: &CpuState,
+        calling_convention: &ConventionModel,
+        lang: &SleighLanguage,
+    ) -> Vec<Self> {
+        calling_convention
+            .preserved_varnodes(lang)
+            .into_iter()
+            .filter(|register| *register != lang.sp)
+            .filter_map(|register| {
+                let offset = stack_offset(&*registers.get(register)?, lang.sp)?;
+                Some(Self { register, offset })
+            })
+            .collect()
+    }
+
+    /// Frame pointer of the function, the register that points to the same offset in every
+    /// composed block it points into the stack frame in.
+    pub fn find(
+        composed_blocks: &BlockStorage,
+        calling_convention: &ConventionModel,
+        lang: &SleighLanguage,
+    ) -> Option<Self> {
+        // offset of each register, `None` once two blocks disagree
+        let mut candidates: Vec<(VarNode, Option<i64>)> = Vec::new();
+        for (_, block) in composed_blocks.iter() {
+            for found in Self::in_state(&block.registers, calling_convention, lang) {
+                match candidates.iter_mut().find(|(r, _)| *r == found.register) {
+                    Some((_, offset)) if *offset != Some(found.offset) => *offset = None,
+                    Some(_) => (),
+                    None => candidates.push((found.register, Some(found.offset))),
+                }
+            }
+        }
+        candidates.into_iter().find_map(|(register, offset)| {
+            Some(Self {
+                register,
+                offset: offset?,
+            })
+        })
+    }
+}
+
+/// Offset of `addr` from the stack pointer at the function entry.
+pub fn stack_offset(addr: &Expression, sp: VarNode) -> Option<i64> {
+    match (addr.len(), addr.root_op()?) {
+        (1, ExpressionOp::Variable(VariableSymbol::Varnode(r))) if *r == sp => Some(0),
+        (3, ExpressionOp::Add(_, _, _)) => {
+            match (addr.get(OpIdx::from_idx(0)), addr.get(OpIdx::from_idx(1))) {
+                (
+                    ExpressionOp::Variable(VariableSymbol::Varnode(r)),
+                    ExpressionOp::Value(offset),
+                ) if *r == sp => Some(as_signed(*offset, sp.size.into())),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+mod test {
+    use super::{partition, StackVariableKind};
+
+    #[test]
+    fn test_partition() {
+        // a buffer read in overlapping 8-byte chunks, the return address and a stack parameter
+        let mut accesses: Vec<(i64, Option<u32>)> =
+            (0..0x40).map(|k| (-0x120 + 4 * k, Some(8))).collect();
+        accesses.extend([(0, None), (8, None)]);
+
+        let variables = partition(&accesses, 8, 0..8);
+        assert_eq!(variables.len(), 2);
+        assert_eq!(variables[0].offset, -0x120);
+        assert_eq!(variables[0].size, 0x104);
+        assert_eq!(variables[0].name(), "local_120");
+        assert_eq!(variables[1].offset, 8);
+        assert_eq!(variables[1].size, 8);
+        assert_eq!(variables[1].kind, StackVariableKind::Parameter);
+    }
+}
@@ -1,21 +1,24 @@
+use crate::ir::calling_convention::CallingConvention;
 use crate::memory::{navigation::Section, Memory};
 use crate::memory::LiteralState;
 use crate::tab_viewer::TabSignals;
 use goblin::Object;
 
+use super::LanguageId;
+
 pub fn load<'s>(
     bytes: &'s [u8],
     memory: &mut Memory,
     signals: &mut TabSignals,
-) -> Result<String, super::LoaderError> {
+) -> Result<LanguageId, super::LoaderError> {
     let o = Object::parse(&bytes)?;
-    let sleigh_lang_id = match o {
+    let (sleigh_lang_id, calling_convention) = match o {
         Object::Elf(ref elf) => {
             // Detect architecture from ELF header
             use goblin::elf::header::*;
             match elf.header.e_machine {
-                EM_386 => "x86:LE:32:default",
-                EM_X86_64 => "x86:LE:64:default",
+                EM_386 => ("x86:LE:32:default", CallingConvention::Cdecl),
+                EM_X86_64 => ("x86:LE:64:default", CallingConvention::SystemV),
                 _ => {
                     return Err(super::LoaderError::MalformedFile(format!(
                         "Unsupported architecture: e_machine = {}. Only x86 (EM_386) and x86-64 (EM_X86_64) are currently supported.",
@@ -29,8 +32,8 @@ pub fn load<'s>(
             use goblin::pe::header::COFF_MACHINE_X86;
             use goblin::pe::header::COFF_MACHINE_X86_64;
             match pe.header.coff_header.machine {
-                COFF_MACHINE_X86 => "x86:LE:32:default",
-                COFF_MACHINE_X86_64 => "x86:LE:64:default",
+                COFF_MACHINE_X86 => ("x86:LE:32:default", CallingConvention::Cdecl),
+                COFF_MACHINE_X86_64 => ("x86:LE:64:default", CallingConvention::MicrosoftX64),
                 _ => {
                     return Err(super::LoaderError::MalformedFile(format!(
                         "Unsupported architecture: machine = {:#x}. Only x86 (I386) and x86-64 (AMD64) are currently supported.",
@@ -45,6 +48,12 @@ pub fn load<'s>(
             ));
         }
     };
+    memory.calling_convention = Some(calling_convention);
+    // ELF binaries are assumed to be built by gcc, PE binaries by Visual Studio
+    let compiler = match o {
+        Object::PE(_) => "windows",
+        _ => "gcc",
+    };
 
     match o {
         Object::Elf(elf) => {
@@ -70,11 +79,17 @@ pub fn load<'s>(
                 });
 
             if let Some(plt_sec) = plt_section {
-                let plt_base = plt_sec.sh_addr;
-
                 // Each PLT entry is typically 16 bytes in modern x86-64
                 const PLT_ENTRY_SIZE: u64 = 16;
 
+                // .plt starts with the entry that calls the dynamic linker, .plt.sec doesn't
+                let is_plt_sec = elf.shdr_strtab.get_at(plt_sec.sh_name) == Some(".plt.sec");
+                let plt_base = if is_plt_sec {
+                    plt_sec.sh_addr
+                } else {
+                    plt_sec.sh_addr + PLT_ENTRY_SIZE
+                };
+
                 // Iterate over PLT relocations
                 for (index, reloc) in elf.pltrelocs.iter().enumerate() {
                     // Calculate PLT stub address: base + (index * entry_size)
@@ -181,6 +196,14 @@ pub fn load<'s>(
                     ));
                 }
             }
+            // import address table slots, called through as `call [ExitProcess]`
+            for import in &pe.imports {
+                memory.symbols.add(
+                    pe.image_base + import.rva as u64,
+                    import.size as u8,
+                    import.name.to_string(),
+                );
+            }
             // pe.image_base
             // for entry in pe.imports {
             //     println!("{entry:?}")
@@ -195,5 +218,8 @@ pub fn load<'s>(
         }
         _ => unreachable!("Architecture detection should have caught unsupported formats"),
     }
-    Ok(sleigh_lang_id.to_string())
+    Ok(LanguageId {
+        language: sleigh_lang_id.to_string(),
+        compiler: compiler.to_string(),
+    })
 }
@@ -8,6 +8,15 @@ use crate::{
     tab_viewer::TabSignals,
 };
 
+/// Ghidra ids of the language and the compiler of a loaded binary, as listed in the `.ldefs` file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LanguageId {
+    /// e.g. `x86:LE:64:default`
+    pub language: String,
+    /// e.g. `gcc` or `windows`
+    pub compiler: String,
+}
+
 #[derive(Error, Debug)]
 pub enum LoaderError {
     #[error("I/O error: {0}")]
@@ -20,7 +29,11 @@ pub enum LoaderError {
 
 mod load_with_goblin;
 
-pub fn load<P>(path: P, memory: &mut Memory, signals: &mut TabSignals) -> Result<String, LoaderError>
+pub fn load<P>(
+    path: P,
+    memory: &mut Memory,
+    signals: &mut TabSignals,
+) -> Result<LanguageId, LoaderError>
 where
     P: AsRef<Path>,
 {
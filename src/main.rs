@@ -4,6 +4,7 @@ mod memory;
 mod symbol_resolver;
 
 use std::{
+    collections::{HashMap, HashSet},
     fs::File,
     io::{stdout, Read, Write},
 };
@@ -30,9 +31,17 @@ use tab_viewer::{
     TabSignals, TabViewer,
 };
 
+/// How often a function is analyzed again because the summary of a callee changed, so that
+/// recursive functions whose summaries keep changing each other settle.
+const MAX_REANALYSES: u32 = 4;
+
 struct DecompilerApp {
     memory: Memory,
     current_function: Option<Address>,
+    /// Times each function was analyzed again after a callee's summary changed
+    reanalyses: HashMap<Address, u32>,
+    /// Callers queued to be analyzed again, which don't take over the view
+    requeued: HashSet<Address>,
     signals: TabSignals,
     tree: DockState<TabKind>,
     buttons: [(&'static str, TabKind); 5],
@@ -92,15 +101,23 @@ fn main() -> eframe::Result {
                 let sleigh_lang_id = loaders::load(path, &mut memory, &mut signals).unwrap();
                 let lang = sleigh_compile::SleighLanguageBuilder::new(
                     "./SLEIGH/Processors/x86/data/languages/x86.ldefs",
-                    &sleigh_lang_id,
+                    &sleigh_lang_id.language,
                 )
                 .build()
                 .unwrap();
                 memory.set_language(lang);
+                if let Err(e) = memory.load_compiler_spec(
+                    "./SLEIGH/Processors/x86/data/languages/x86.ldefs",
+                    &sleigh_lang_id,
+                ) {
+                    println!("Unable to load compiler spec: {e}");
+                }
             }
 
             Ok(Box::new(DecompilerApp {
                 current_function: None,
+                reanalyses: HashMap::new(),
+                requeued: HashSet::new(),
                 memory,
                 tree,
                 signals,
@@ -125,11 +142,17 @@ impl eframe::App for DecompilerApp {
                                 loaders::load(binary, &mut self.memory, &mut self.signals).unwrap();
                             let lang = sleigh_compile::SleighLanguageBuilder::new(
                                 "./SLEIGH/Processors/x86/data/languages/x86.ldefs",
-                                &sleigh_lang_id,
+                                &sleigh_lang_id.language,
                             )
                             .build()
                             .unwrap();
                             self.memory.set_language(lang);
+                            if let Err(e) = self.memory.load_compiler_spec(
+                                "./SLEIGH/Processors/x86/data/languages/x86.ldefs",
+                                &sleigh_lang_id,
+                            ) {
+                                println!("Unable to load compiler spec: {e}");
+                            }
                         }
                     }
                 });
@@ -196,6 +219,7 @@ impl eframe::App for DecompilerApp {
                     if self.memory.ir.get_by_address(*f).is_none() {
                         is_repopulate = mark_instructions(*f, &mut self.memory);
                     }
+                    ir::jump_table::resolve_jump_tables(*f, &mut self.memory);
                     let hf = HighFunction::from_mem(*f, &self.memory);
 
                     hf.fill_global_symbols(&mut self.memory);
@@ -203,8 +227,30 @@ impl eframe::App for DecompilerApp {
 
                     let ast = hf.build_ast(&self.memory);
                     self.memory.ast.insert(*f, ast);
+                    let summary_changed = self
+                        .memory
+                        .functions
+                        .get(f)
+                        .map_or(true, |old| old.summary != hf.summary);
                     self.memory.functions.insert(*f, hf);
-                    self.current_function = Some(*f);
+                    if !self.requeued.remove(f) {
+                        self.current_function = Some(*f);
+                    }
+
+                    // Callers applied the old summary at their call sites
+                    if summary_changed {
+                        for caller in self.memory.callers_of(*f) {
+                            if caller == *f || discovered_functions.contains(&caller) {
+                                continue;
+                            }
+                            let reanalyses = self.reanalyses.entry(caller).or_default();
+                            if *reanalyses < MAX_REANALYSES {
+                                *reanalyses += 1;
+                                self.requeued.insert(caller);
+                                discovered_functions.push(caller);
+                            }
+                        }
+                    }
 
                     // Discover functions called from this function
                     discover_functions_from_calls(*f, &self.memory, &mut discovered_functions);
@@ -241,7 +287,9 @@ fn discover_functions_from_calls(func_addr: Address, memory: &Memory, discovered
         ) {
             let composed_block = &hf.composed_blocks[block];
 
-            if let NextBlock::Call { destination, .. } = &composed_block.next {
+            if let NextBlock::Call { destination, .. } | NextBlock::TailCall { destination, .. } =
+                &composed_block.next
+            {
                 if let DestinationKind::Concrete(called_addr) = destination {
                     // Check if this function has already been analyzed
                     if !memory.functions.contains_key(called_addr) && !discovered.contains(called_addr) {
@@ -10,9 +10,14 @@ use std::collections::HashMap;
 pub mod navigation;
 
 use crate::ir::{
-    abstract_syntax_tree::AbstractSyntaxTree, address::Address, basic_block::BlockStorage,
+    abstract_syntax_tree::AbstractSyntaxTree,
+    address::Address,
+    basic_block::{BlockStorage, DestinationKind},
+    calling_convention::{CallingConvention, ConventionModel},
+    compiler_spec::{CompilerSpec, CompilerSpecError},
     high_function::HighFunction,
 };
+use crate::loaders::LanguageId;
 use crate::symbol_resolver::SymbolTable;
 
 pub enum LiteralKind {
@@ -56,6 +61,10 @@ pub struct Memory {
     // We have a choice of granularity, a small state per large interval, or large state per small interval.
     pub literal: NoditMap<Address, nodit::Interval<Address>, LiteralState>,
     pub lang: SleighLanguage,
+    /// Convention of the ABI the binary was built for, set by the loader
+    pub calling_convention: Option<CallingConvention>,
+    /// Calling conventions of the compiler that built the binary, if known
+    pub compiler_spec: Option<CompilerSpec>,
     pub ir: BlockStorage,
     pub navigation: Navigation,
     /// All analyzed functions
@@ -144,6 +153,8 @@ impl Memory {
     pub fn new(lang: SleighLanguage) -> Self {
         Self {
             lang,
+            calling_convention: None,
+            compiler_spec: None,
             literal: NoditMap::new(),
             ir: BlockStorage::new(),
             functions: HashMap::new(),
@@ -155,6 +166,71 @@ impl Memory {
 
     pub fn set_language(&mut self, lang: SleighLanguage) {
         self.lang = lang;
+        self.compiler_spec = None;
+    }
+
+    /// Load the compiler spec of `id` from the language definitions in `ldefs`.
+    pub fn load_compiler_spec(
+        &mut self,
+        ldefs: impl AsRef<std::path::Path>,
+        id: &LanguageId,
+    ) -> Result<(), CompilerSpecError> {
+        self.compiler_spec = Some(CompilerSpec::load(ldefs, &id.language, &id.compiler)?);
+        Ok(())
+    }
+
+    /// Convention of functions nothing else is known about: the default prototype of the
+    /// compiler spec, or without a spec the built-in one of the binary's ABI, falling back to the
+    /// default of the language when the ABI is unknown.
+    pub fn default_calling_convention(&self) -> ConventionModel {
+        match &self.compiler_spec {
+            Some(spec) => spec.default_model().clone(),
+            None => self
+                .calling_convention
+                .unwrap_or_else(|| CallingConvention::default_for(&self.lang))
+                .model(&self.lang),
+        }
+    }
+
+    /// Analyzed functions that call `callee` directly, which depend on its
+    /// [`FunctionSummary`](crate::ir::high_function::FunctionSummary).
+    pub fn callers_of(&self, callee: Address) -> Vec<Address> {
+        let callee = DestinationKind::Concrete(callee);
+        let mut callers: Vec<Address> = self
+            .functions
+            .iter()
+            .filter(|(_, f)| f.function_calls.contains(&callee))
+            .map(|(addr, _)| *addr)
+            .collect();
+        callers.sort();
+        callers
+    }
+
+    /// Whether a call to `destination` never comes back: a known library function that doesn't
+    /// return, or an analyzed function with no reachable return.
+    pub fn never_returns(&self, destination: &DestinationKind) -> bool {
+        self.symbols.never_returns(destination)
+            || matches!(destination, DestinationKind::Concrete(callee)
+                if self.functions.get(callee).is_some_and(|f| f.summary.never_returns))
+    }
+
+    /// Whether a function starts at `addr`: an analyzed one, or one named by a symbol like a PLT
+    /// stub.
+    pub fn is_function_start(&self, addr: Address) -> bool {
+        self.functions.contains_key(&addr) || self.symbols.map.contains_key(&addr)
+    }
+
+    /// `len` bytes at `addr`, if they are loaded as data. Bytes that were decoded as
+    /// instructions aren't kept.
+    pub fn read_bytes(&self, addr: Address, len: usize) -> Option<&[u8]> {
+        let state = self.literal.get_at_point(addr)?;
+        match &state.kind {
+            LiteralKind::Data(data) => {
+                let offset = (addr.0 - state.addr.0) as usize;
+                data.get(offset..offset + len)
+            }
+            LiteralKind::Instruction(_, _) => None,
+        }
     }
 
     // pub fn get_symbol_resolver(&self) -> Option<Box<dyn SymbolResolver>> {
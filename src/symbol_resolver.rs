@@ -8,6 +8,36 @@ use crate::ir::{
     type_system::VariableType,
 };
 
+/// Library functions that never return to their caller.
+pub const NORETURN_FUNCTIONS: &[&str] = &[
+    "exit",
+    "_exit",
+    "_Exit",
+    "quick_exit",
+    "abort",
+    "__assert_fail",
+    "__stack_chk_fail",
+    "__fortify_fail",
+    "__chk_fail",
+    "longjmp",
+    "siglongjmp",
+    "__longjmp_chk",
+    "pthread_exit",
+    "err",
+    "errx",
+    "verr",
+    "verrx",
+    "__cxa_throw",
+    "__cxa_rethrow",
+    "_Unwind_Resume",
+    "ExitProcess",
+    "ExitThread",
+    "FatalExit",
+    "_CxxThrowException",
+    "_invalid_parameter_noinfo_noreturn",
+    "__report_gsfailure",
+];
+
 pub struct SymbolTable {
     pub map: HashMap<Address, VariableDefinition>,
 }
@@ -43,18 +73,24 @@ impl SymbolTable {
 
     pub fn resolve(&self, e: &VariableSymbol) -> Option<&VariableDefinition> {
         match e {
-            VariableSymbol::Varnode(_) | VariableSymbol::CallResult { .. } => None,
+            VariableSymbol::Varnode(_)
+            | VariableSymbol::CallResult { .. }
+            | VariableSymbol::Clobbered { .. } => None,
             VariableSymbol::Ram(e, _) => self.resolve_exp(e),
+            VariableSymbol::LoopCarried { location, .. } => self.resolve(location),
         }
     }
 
     pub fn resolve_mut(&mut self, e: &VariableSymbol) -> Option<&mut VariableDefinition> {
         match e {
-            VariableSymbol::Varnode(_) | VariableSymbol::CallResult { .. } => None,
+            VariableSymbol::Varnode(_)
+            | VariableSymbol::CallResult { .. }
+            | VariableSymbol::Clobbered { .. } => None,
             VariableSymbol::Ram(e, _) => {
                 get_expresson_value_or_dereference_value(e, e.get_entry_point())
                     .and_then(|addr| self.map.get_mut(&addr))
             }
+            VariableSymbol::LoopCarried { location, .. } => self.resolve_mut(location),
         }
     }
 
@@ -70,6 +106,12 @@ impl SymbolTable {
             DestinationKind::Virtual(_, _) => None,
         }
     }
+
+    /// Whether `dst` is one of the [`NORETURN_FUNCTIONS`].
+    pub fn never_returns(&self, dst: &DestinationKind) -> bool {
+        self.resolve_destination(dst)
+            .is_some_and(|symbol| NORETURN_FUNCTIONS.contains(&symbol.name.as_str()))
+    }
 }
 
 fn get_expresson_value_or_dereference_value(e: &Expression, pos: OpIdx) -> Option<Address> {
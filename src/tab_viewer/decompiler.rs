@@ -13,7 +13,7 @@ use crate::ir::{
     address::Address,
     basic_block::DestinationKind,
     control_flow_graph::SingleEntrySingleExit,
-    expression::{Expression, ExpressionOp},
+    expression::{Expression, ExpressionOp, SignedOrUnsiged},
     high_function::HighFunction,
     scope::VariableDefinition,
     type_system::VariableType,
@@ -50,6 +50,13 @@ impl Decompiler {
             ("if", theme.make_rich(TokenType::Keyword, "if ")),
             ("else", theme.make_rich(TokenType::Keyword, "else ")),
             ("while", theme.make_rich(TokenType::Keyword, "while ")),
+            ("for", theme.make_rich(TokenType::Keyword, "for ")),
+            ("do", theme.make_rich(TokenType::Keyword, "do")),
+            ("switch", theme.make_rich(TokenType::Keyword, "switch ")),
+            ("case", theme.make_rich(TokenType::Keyword, "case ")),
+            ("break", theme.make_rich(TokenType::Keyword, "break")),
+            ("continue", theme.make_rich(TokenType::Keyword, "continue")),
+            ("goto", theme.make_rich(TokenType::Keyword, "goto ")),
             ("return", theme.make_rich(TokenType::Keyword, "return ")),
             ("int_32t", theme.make_rich(TokenType::Type, "int_32t ")),
             ("uint_32t", theme.make_rich(TokenType::Type, "uint_32t ")),
@@ -65,6 +72,8 @@ impl Decompiler {
             ("{", theme.make_rich(TokenType::Punctuation, "{")),
             ("}", theme.make_rich(TokenType::Punctuation, "}")),
             ("=", theme.make_rich(TokenType::Punctuation, " = ")),
+            ("+=", theme.make_rich(TokenType::Punctuation, " += ")),
+            ("-=", theme.make_rich(TokenType::Punctuation, " -= ")),
             ("==", theme.make_rich(TokenType::Punctuation, " == ")),
             ("!=", theme.make_rich(TokenType::Punctuation, " != ")),
             ("+", theme.make_rich(TokenType::Punctuation, " + ")),
@@ -78,13 +87,35 @@ impl Decompiler {
             ("&", theme.make_rich(TokenType::Punctuation, " & ")),
             ("|", theme.make_rich(TokenType::Punctuation, " | ")),
             ("^", theme.make_rich(TokenType::Punctuation, " ^ ")),
+            ("&&", theme.make_rich(TokenType::Punctuation, " && ")),
+            ("||", theme.make_rich(TokenType::Punctuation, " || ")),
             ("~", theme.make_rich(TokenType::Punctuation, "~")),
             ("*", theme.make_rich(TokenType::Punctuation, " * ")),
+            ("/", theme.make_rich(TokenType::Punctuation, " / ")),
+            ("%", theme.make_rich(TokenType::Punctuation, " % ")),
             (",", theme.make_rich(TokenType::Punctuation, ", ")),
             (";", theme.make_rich(TokenType::Punctuation, ";")),
+            (":", theme.make_rich(TokenType::Punctuation, ":")),
             (" ", theme.make_rich(TokenType::Whitespace, " ")),
             ("overflow", theme.make_rich(TokenType::Keyword, "overflow")),
             ("popcount", theme.make_rich(TokenType::Keyword, "popcount")),
+            ("carry", theme.make_rich(TokenType::Keyword, "carry")),
+            ("scarry", theme.make_rich(TokenType::Keyword, "scarry")),
+            ("sborrow", theme.make_rich(TokenType::Keyword, "sborrow")),
+            ("int8_t", theme.make_rich(TokenType::Type, "int8_t")),
+            ("uint8_t", theme.make_rich(TokenType::Type, "uint8_t")),
+            ("int16_t", theme.make_rich(TokenType::Type, "int16_t")),
+            ("uint16_t", theme.make_rich(TokenType::Type, "uint16_t")),
+            ("int32_t", theme.make_rich(TokenType::Type, "int32_t")),
+            ("uint32_t", theme.make_rich(TokenType::Type, "uint32_t")),
+            ("int64_t", theme.make_rich(TokenType::Type, "int64_t")),
+            ("uint64_t", theme.make_rich(TokenType::Type, "uint64_t")),
+            ("int128_t", theme.make_rich(TokenType::Type, "int128_t")),
+            ("uint128_t", theme.make_rich(TokenType::Type, "uint128_t")),
+            ("int256_t", theme.make_rich(TokenType::Type, "int256_t")),
+            ("uint256_t", theme.make_rich(TokenType::Type, "uint256_t")),
+            ("int512_t", theme.make_rich(TokenType::Type, "int512_t")),
+            ("uint512_t", theme.make_rich(TokenType::Type, "uint512_t")),
         ]);
         Self {
             theme,
@@ -265,6 +296,7 @@ impl Decompiler {
                 destination,
                 params,
                 call_from,
+                tail,
                 sese: pts,
             } => {
                 let result = VariableSymbol::CallResult {
@@ -273,7 +305,9 @@ impl Decompiler {
                 };
                 ui.horizontal(|ui| {
                     ui.label(tab_prefix);
-                    if let Cow::Borrowed(s) = resolve_symbol(mem, &result, hf, *pts) {
+                    if *tail {
+                        ui.label(self.mk_color("return"));
+                    } else if let Cow::Borrowed(s) = resolve_symbol(mem, &result, hf, *pts) {
                         self.draw_symbol(ui, signals, &result, true, mem, hf, *pts);
                         ui.label(self.mk_color("="));
                     }
@@ -343,16 +377,39 @@ impl Decompiler {
                     ui.label(self.mk_color(";"));
                 });
             }
-            AstStatement::Loop {
+            AstStatement::While {
                 sese: pts,
                 condition,
                 body,
-                body_address,
+                ..
+            }
+            | AstStatement::DoWhile {
+                sese: pts,
+                condition,
+                body,
+                ..
+            }
+            | AstStatement::For {
+                sese: pts,
+                condition,
+                body,
+                ..
             } => {
+                let induction = match stmt {
+                    AstStatement::For { induction, .. } => Some(induction),
+                    _ => None,
+                };
+                let do_while = matches!(stmt, AstStatement::DoWhile { .. });
                 let mut loop_rect = Rect::NOTHING;
                 ui.horizontal(|ui| {
                     ui.label(tab_prefix.clone());
-                    let while_keyword = ui.label(self.mk_color("while"));
+                    let while_keyword = ui.label(self.mk_color(if do_while {
+                        "do"
+                    } else if induction.is_some() {
+                        "for"
+                    } else {
+                        "while"
+                    }));
                     loop_rect = while_keyword.rect;
                     if while_keyword.clicked() {
                         signals.request_pos(hf.composed_blocks[pts.0].identifier.start_address());
@@ -361,7 +418,38 @@ impl Decompiler {
                         while_keyword.highlight();
                         ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
                     }
+                    if do_while {
+                        ui.label(self.mk_color(" "));
+                        ui.label(self.mk_color("{"));
+                        return;
+                    }
                     ui.label(self.mk_color("("));
+                    if let Some(induction) = induction {
+                        let variable = &induction.variable;
+                        self.draw_expression(
+                            ui,
+                            signals,
+                            mem,
+                            hf,
+                            variable,
+                            *pts,
+                            variable.get_entry_point(),
+                            false,
+                        );
+                        ui.label(self.mk_color("="));
+                        self.draw_expression(
+                            ui,
+                            signals,
+                            mem,
+                            hf,
+                            &induction.init,
+                            *pts,
+                            induction.init.get_entry_point(),
+                            false,
+                        );
+                        ui.label(self.mk_color(";"));
+                        ui.label(self.mk_color(" "));
+                    }
                     self.draw_expression(
                         ui,
                         signals,
@@ -372,6 +460,26 @@ impl Decompiler {
                         condition.get_entry_point(),
                         false,
                     );
+                    if let Some(induction) = induction {
+                        let variable = &induction.variable;
+                        ui.label(self.mk_color(";"));
+                        ui.label(self.mk_color(" "));
+                        self.draw_expression(
+                            ui,
+                            signals,
+                            mem,
+                            hf,
+                            variable,
+                            *pts,
+                            variable.get_entry_point(),
+                            false,
+                        );
+                        ui.label(self.mk_color(if induction.step < 0 { "-=" } else { "+=" }));
+                        ui.label(self.theme.make_rich(
+                            TokenType::NumericalLiteral,
+                            format!("{}", induction.step.unsigned_abs()),
+                        ));
+                    }
                     ui.label(self.mk_color(")"));
                     ui.label(self.mk_color(" "));
                     ui.label(self.mk_color("{"));
@@ -395,6 +503,152 @@ impl Decompiler {
                             Stroke::new(1.0, Color32::DARK_GRAY),
                         );
                     }
+                    if do_while {
+                        ui.label(self.mk_color(" "));
+                        ui.label(self.mk_color("while"));
+                        ui.label(self.mk_color("("));
+                        self.draw_expression(
+                            ui,
+                            signals,
+                            mem,
+                            hf,
+                            condition,
+                            *pts,
+                            condition.get_entry_point(),
+                            false,
+                        );
+                        ui.label(self.mk_color(")"));
+                        ui.label(self.mk_color(";"));
+                    }
+                });
+            }
+            AstStatement::Break => {
+                ui.horizontal(|ui| {
+                    ui.label(tab_prefix);
+                    ui.label(self.mk_color("break"));
+                    ui.label(self.mk_color(";"));
+                });
+            }
+            AstStatement::Continue => {
+                ui.horizontal(|ui| {
+                    ui.label(tab_prefix);
+                    ui.label(self.mk_color("continue"));
+                    ui.label(self.mk_color(";"));
+                });
+            }
+            AstStatement::Label(slot) => {
+                let address = hf.composed_blocks[*slot].identifier.start_address();
+                ui.horizontal(|ui| {
+                    ui.label(tab_prefix);
+                    ui.label(
+                        self.theme
+                            .make_rich(TokenType::Symbol, format!("LAB_{:x}", address.0)),
+                    );
+                    ui.label(self.mk_color(":"));
+                });
+            }
+            AstStatement::Goto(slot) => {
+                let address = hf.composed_blocks[*slot].identifier.start_address();
+                ui.horizontal(|ui| {
+                    ui.label(tab_prefix);
+                    ui.label(self.mk_color("goto"));
+                    let label = ui.label(
+                        self.theme
+                            .make_rich(TokenType::Symbol, format!("LAB_{:x}", address.0)),
+                    );
+                    if label.clicked() {
+                        signals.request_pos(address);
+                    }
+                    if label.hovered() {
+                        label.highlight();
+                        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                    }
+                    ui.label(self.mk_color(";"));
+                });
+            }
+            AstStatement::Switch {
+                sese: pts,
+                selector,
+                cases,
+            } => {
+                let mut switch_rect = Rect::NOTHING;
+                ui.horizontal(|ui| {
+                    ui.label(tab_prefix.clone());
+                    let switch_keyword = ui.label(self.mk_color("switch"));
+                    switch_rect = switch_keyword.rect;
+                    if switch_keyword.clicked() {
+                        signals.request_pos(hf.composed_blocks[pts.0].identifier.start_address());
+                    }
+                    if switch_keyword.hovered() {
+                        switch_keyword.highlight();
+                        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                    }
+                    ui.label(self.mk_color("("));
+                    self.draw_expression(
+                        ui,
+                        signals,
+                        mem,
+                        hf,
+                        selector,
+                        *pts,
+                        selector.get_entry_point(),
+                        false,
+                    );
+                    ui.label(self.mk_color(")"));
+                    ui.label(self.mk_color(" "));
+                    ui.label(self.mk_color("{"));
+                });
+                let case_prefix = self.theme.make_rich(
+                    TokenType::Whitespace,
+                    " ".repeat(((depth + 1) * 2) as usize),
+                );
+                let body_prefix = self.theme.make_rich(
+                    TokenType::Whitespace,
+                    " ".repeat(((depth + 2) * 2) as usize),
+                );
+                for (values, body) in cases {
+                    for value in values {
+                        ui.horizontal(|ui| {
+                            ui.label(case_prefix.clone());
+                            ui.label(self.mk_color("case"));
+                            ui.label(self.theme.make_rich(
+                                TokenType::NumericalLiteral,
+                                if *value < 1024 {
+                                    format!("{value}")
+                                } else {
+                                    format!("0x{value:x}")
+                                },
+                            ));
+                            ui.label(self.mk_color(":"));
+                        });
+                    }
+                    self.draw_at_pos(ui, signals, mem, hf, body, depth + 2);
+                    if body.falls_through() {
+                        ui.horizontal(|ui| {
+                            ui.label(body_prefix.clone());
+                            ui.label(self.mk_color("break"));
+                            ui.label(self.mk_color(";"));
+                        });
+                    }
+                }
+                ui.horizontal(|ui| {
+                    ui.label(tab_prefix);
+                    let end_rect = ui.label(self.mk_color("}")).rect;
+                    if depth != 0 {
+                        ui.painter().line(
+                            vec![
+                                Pos2 {
+                                    x: switch_rect.min.x + 2.,
+                                    y: switch_rect.max.y + 2.,
+                                },
+                                Pos2 {
+                                    x: end_rect.min.x + 2.,
+                                    y: end_rect.min.y - 2.,
+                                },
+                            ],
+                            Stroke::new(1.0, Color32::DARK_GRAY),
+                        );
+                    }
                 });
             }
             AstStatement::Return { sese: pts, result } => {
@@ -480,7 +734,11 @@ impl Decompiler {
                 label.on_hover_text("Deference::Variable");
             }
             _ => {
-                let var = VariableSymbol::Ram(Box::new(e.get_sub_expression(pos)), 4);
+                let addr = e.get_sub_expression(pos);
+                let var = match hf.stack_frame.variable_at(&addr, mem.lang.sp) {
+                    Some(stack_variable) => stack_variable.symbol(mem.lang.sp),
+                    None => VariableSymbol::Ram(Box::new(addr), 4),
+                };
                 if let Cow::Borrowed(sym) = resolve_symbol(mem, &var, hf, ip_block) {
                     let label = self.draw_symbol(ui, signals, &var, false, mem, hf, ip_block);
                     label.on_hover_text("Deference::ComplexExpression");
@@ -702,6 +960,16 @@ impl Decompiler {
                 ui.label(self.mk_color("*"));
                 self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
             }
+            ExpressionOp::Divide(l, r, _, _) => {
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color("/"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+            }
+            ExpressionOp::Remainder(l, r, _, _) => {
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color("%"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+            }
             ExpressionOp::Or(l, r) => {
                 self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
                 ui.label(self.mk_color("|"));
@@ -712,6 +980,16 @@ impl Decompiler {
                 ui.label(self.mk_color("^"));
                 self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
             }
+            ExpressionOp::LogicalAnd(l, r) => {
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color("&&"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+            }
+            ExpressionOp::LogicalOr(l, r) => {
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color("||"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+            }
             ExpressionOp::Not(operand) => {
                 ui.label(self.mk_color("~"));
                 self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
@@ -724,12 +1002,87 @@ impl Decompiler {
                 self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
                 ui.label(self.mk_color(")"));
             }
+            ExpressionOp::Carry(l, r, _, sgn) => {
+                ui.label(self.mk_color(match sgn {
+                    SignedOrUnsiged::Signed => "scarry",
+                    SignedOrUnsiged::Unsigned => "carry",
+                }));
+                ui.label(self.mk_color("("));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color(","));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+                ui.label(self.mk_color(")"));
+            }
+            ExpressionOp::Borrow(l, r, _) => {
+                ui.label(self.mk_color("sborrow"));
+                ui.label(self.mk_color("("));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color(","));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+                ui.label(self.mk_color(")"));
+            }
             ExpressionOp::CountOnes(operand) => {
                 ui.label(self.mk_color("popcount"));
                 ui.label(self.mk_color("("));
                 self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
                 ui.label(self.mk_color(")"));
             }
+            ExpressionOp::SignExtend(operand, _, to) => {
+                ui.label(self.mk_color("("));
+                ui.label(self.mk_color(to.c_type_name(SignedOrUnsiged::Signed)));
+                ui.label(self.mk_color(")"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
+            }
+            ExpressionOp::ZeroExtend(operand, _, to) | ExpressionOp::Truncate(operand, _, to) => {
+                ui.label(self.mk_color("("));
+                ui.label(self.mk_color(to.c_type_name(SignedOrUnsiged::Unsigned)));
+                ui.label(self.mk_color(")"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
+            }
+            ExpressionOp::FloatValue(bits, precision) => {
+                ui.label(self.theme.make_rich(
+                    TokenType::NumericalLiteral,
+                    precision.format_constant(*bits),
+                ));
+            }
+            ExpressionOp::FloatBinary(l, r, op, _) => {
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color(op.symbol().trim()));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+            }
+            ExpressionOp::FloatCompare(l, r, op, _) => {
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *l, is_call);
+                ui.label(self.mk_color(op.symbol().trim()));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *r, is_call);
+            }
+            ExpressionOp::FloatUnary(operand, op, _) => match op.function_name() {
+                Some(name) => {
+                    ui.label(self.theme.make_rich(TokenType::Keyword, name));
+                    ui.label(self.mk_color("("));
+                    self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
+                    ui.label(self.mk_color(")"));
+                }
+                None => {
+                    ui.label(self.theme.make_rich(TokenType::Punctuation, "-"));
+                    self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
+                }
+            },
+            ExpressionOp::IntToFloat(operand, _, precision)
+            | ExpressionOp::FloatToFloat(operand, _, precision) => {
+                ui.label(self.mk_color("("));
+                ui.label(
+                    self.theme
+                        .make_rich(TokenType::Type, precision.c_type_name()),
+                );
+                ui.label(self.mk_color(")"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
+            }
+            ExpressionOp::FloatToInt(operand, size) => {
+                ui.label(self.mk_color("("));
+                ui.label(self.mk_color(size.c_type_name(SignedOrUnsiged::Signed)));
+                ui.label(self.mk_color(")"));
+                self.draw_expression(ui, signals, mem, hf, e, ip_block, *operand, is_call);
+            }
             op => {
                 _ = ui.label(
                     self.theme
@@ -790,3 +1143,23 @@ fn resolve_symbol_mut<'a>(
         section.and_then(|s| ast.scope.get_symbol_mut(s, dst))
     })
 }
+
+mod test {
+    use egui::Style;
+
+    use super::Decompiler;
+
+    #[test]
+    fn test_interned_tokens() {
+        // `mk_color` indexes the map directly, so a missing key panics while drawing
+        let decompiler = Decompiler::new(&Style::default());
+        let source = include_str!("decompiler.rs");
+        for literal in source.split("mk_color(\"").skip(1) {
+            let key = &literal[..literal.find('"').unwrap()];
+            assert!(
+                decompiler.interned_tokens.contains_key(key),
+                "no interned token for {key:?}"
+            );
+        }
+    }
+}
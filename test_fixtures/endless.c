@@ -0,0 +1,15 @@
+// A loop that is never left, with a branch in it, so none of its blocks reach the return
+volatile int ticks;
+
+void spin(int n) {
+    for (;;) {
+        if (n > 0) {
+            ticks++;
+        }
+    }
+}
+
+int main(int argc, char **argv) {
+    spin(argc);
+    return 0;
+}
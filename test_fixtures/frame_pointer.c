@@ -0,0 +1,13 @@
+// Built without the C runtime (see Makefile), keeping the frame pointer and storing outgoing
+// arguments relative to ESP, so the same blocks address the stack through both EBP and ESP.
+int scale(int value, int factor) {
+    return value * factor;
+}
+
+int start(int n) {
+    int total = 0;
+    for (int i = 0; i < n; i++) {
+        total += scale(i, 3);
+    }
+    return total;
+}
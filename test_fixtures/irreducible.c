@@ -0,0 +1,19 @@
+// A loop entered in two places, which none of the structured statements can draw
+int countdown(int n, int skip) {
+    int total = 0;
+    if (skip) {
+        goto middle;
+    }
+top:
+    total += n;
+middle:
+    n--;
+    if (n > 0) {
+        goto top;
+    }
+    return total;
+}
+
+int main(int argc, char **argv) {
+    return countdown(argc, argc > 2);
+}
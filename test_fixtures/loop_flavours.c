@@ -0,0 +1,34 @@
+// Loops testing whether to go on before the body, after it, or from the middle of it
+int halvings(int n) {
+    int steps = 0;
+    while (n > 1) {
+        n = n / 2;
+        steps++;
+    }
+    return steps;
+}
+
+int digits(int n) {
+    int count = 0;
+    do {
+        n = n / 10;
+        count++;
+    } while (n != 0);
+    return count;
+}
+
+int first_negative(int *values, int n) {
+    int found = -1;
+    for (int i = 0; i < n; i++) {
+        if (values[i] < 0) {
+            found = i;
+            break;
+        }
+    }
+    return found;
+}
+
+int main(int argc, char **argv) {
+    int values[3] = {argc, -argc, 0};
+    return halvings(argc) + digits(argc) + first_negative(values, 3);
+}
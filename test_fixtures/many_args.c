@@ -0,0 +1,11 @@
+long sum8(long a, long b, long c, long d, long e, long f, long g, long h) {
+    return a + b + c + d + e + f + g + h;
+}
+
+double scale(double x, long factor) {
+    return x * factor;
+}
+
+int main() {
+    return sum8(1, 2, 3, 4, 5, 6, 7, 8) + (int)scale(1.5, 2);
+}
@@ -0,0 +1,24 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+// Not marked noreturn, so callers keep the code after calls to it
+void fail(const char *message) {
+    puts(message);
+    exit(1);
+}
+
+int check(int value) {
+    if (value < 0) {
+        fail("negative");
+    }
+    return value * 2;
+}
+
+int main(int argc, char **argv) {
+    return check(argc - 2);
+}
+
+// Leaves through a jump to a register, which the analysis can't follow
+void dispatch(void *target) {
+    goto *target;
+}
@@ -0,0 +1,18 @@
+// Each condition is lowered to its own conditional jump
+int both(int a, int b) {
+    if (a > 0 && b > 0) {
+        a = a + b;
+    }
+    return a;
+}
+
+int either(int a, int b) {
+    if (a < 0 || b < 0) {
+        return 0;
+    }
+    return a * b;
+}
+
+int main(int argc, char **argv) {
+    return both(argc, 2) + either(argc, 3);
+}
@@ -0,0 +1,11 @@
+// Built without the C runtime (see Makefile), so it links without 32-bit libraries.
+// `scale` pops its own arguments with `ret 8`, the caller doesn't adjust the stack after calls.
+__attribute__((stdcall)) int scale(int value, int factor) {
+    return value * factor;
+}
+
+int start(int n) {
+    int a = scale(n, 3);
+    int b = scale(a, 5);
+    return a + b;
+}
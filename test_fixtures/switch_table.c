@@ -0,0 +1,16 @@
+int classify(int n) {
+    int result;
+    switch (n) {
+        case 0: result = 10; break;
+        case 1: result = 21; break;
+        case 2: result = 32; break;
+        case 3: result = 43; break;
+        case 4: result = 54; break;
+        default: result = -1; break;
+    }
+    return result;
+}
+
+int main() {
+    return classify(3);
+}
@@ -0,0 +1,25 @@
+#include <stdio.h>
+
+int scale = 3;
+
+// Kept out of line, so calls to these functions stay calls
+__attribute__((noinline)) int step(int x) {
+    printf("step %d\n", x);
+    return x * scale + 1;
+}
+
+// Ends in `jmp step` once the saved register is popped again
+__attribute__((noinline)) int twice(int x) {
+    int y = step(x);
+    return step(y + x);
+}
+
+// Ends in `jmp twice` without touching the stack
+__attribute__((noinline)) int forward(int x) {
+    return twice(x + 1);
+}
+
+int main(int argc, char **argv) {
+    printf("%d\n", forward(argc));
+    return 0;
+}
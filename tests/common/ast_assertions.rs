@@ -29,11 +29,20 @@ where
                 return true;
             }
         }
-        AstStatement::Loop { body, .. } => {
+        AstStatement::While { body, .. }
+        | AstStatement::DoWhile { body, .. }
+        | AstStatement::For { body, .. } => {
             if visit_ast(body, predicate) {
                 return true;
             }
         }
+        AstStatement::Switch { cases, .. } => {
+            for (_, body) in cases {
+                if visit_ast(body, predicate) {
+                    return true;
+                }
+            }
+        }
         AstStatement::Function { body, .. } => {
             if visit_ast(body, predicate) {
                 return true;
@@ -68,9 +77,16 @@ where
             count_statements_recursive(true_statement, predicate, count);
             count_statements_recursive(else_statement, predicate, count);
         }
-        AstStatement::Loop { body, .. } => {
+        AstStatement::While { body, .. }
+        | AstStatement::DoWhile { body, .. }
+        | AstStatement::For { body, .. } => {
             count_statements_recursive(body, predicate, count);
         }
+        AstStatement::Switch { cases, .. } => {
+            for (_, body) in cases {
+                count_statements_recursive(body, predicate, count);
+            }
+        }
         AstStatement::Function { body, .. } => {
             count_statements_recursive(body, predicate, count);
         }
@@ -92,11 +108,9 @@ pub fn assert_has_return(ast: &AbstractSyntaxTree) -> bool {
     })
 }
 
-/// Checks if the AST contains a Loop statement
+/// Checks if the AST contains a loop of any flavour
 pub fn assert_has_loop(ast: &AbstractSyntaxTree) -> bool {
-    visit_ast(ast.entry(), &mut |stmt| {
-        matches!(stmt, AstStatement::Loop { .. })
-    })
+    visit_ast(ast.entry(), &mut |stmt| stmt.is_loop())
 }
 
 
@@ -1,6 +1,7 @@
 use bin_ast::memory::Memory;
 use bin_ast::ir::abstract_syntax_tree::AbstractSyntaxTree;
 use bin_ast::ir::high_function::HighFunction;
+use bin_ast::ir::jump_table;
 use bin_ast::ir::address::Address;
 use bin_ast::loaders;
 use bin_ast::tab_viewer::TabSignals;
@@ -27,14 +28,18 @@ pub fn load_test_binary_and_decompile(
     let mut memory = create_test_memory_for_arch(arch);
     let mut signals = TabSignals::new();
 
-    loaders::load(&path, &mut memory, &mut signals)
+    let id = loaders::load(&path, &mut memory, &mut signals)
         .expect(&format!("Failed to load test binary: {:?}", path));
+    memory
+        .load_compiler_spec("./SLEIGH/Processors/x86/data/languages/x86.ldefs", &id)
+        .expect("Failed to load compiler spec");
 
     // Get the entry point from signals
     let func_addr = get_entry_point_from_signals(&signals);
 
     // Mark instructions at the entry point so IR can be generated
     mark_instructions(func_addr, &mut memory);
+    jump_table::resolve_jump_tables(func_addr, &mut memory);
 
     let hf = HighFunction::from_mem(func_addr, &memory);
     let ast = AbstractSyntaxTree::new(&hf, &memory);
@@ -57,7 +62,7 @@ fn get_entry_point_from_signals(signals: &TabSignals) -> Address {
 
 /// Marks instructions at the given address for analysis
 /// This is similar to the mark_instructions function in main.rs
-fn mark_instructions(addr: Address, memory: &mut Memory) {
+pub fn mark_instructions(addr: Address, memory: &mut Memory) {
     use bin_ast::memory::{LiteralKind, LiteralState};
     use bin_ast::ir;
 
@@ -140,8 +145,11 @@ pub fn load_test_binary(fixture_name: &str, arch: &str) -> Memory {
     let mut memory = create_test_memory_for_arch(arch);
     let mut signals = TabSignals::new();
 
-    loaders::load(&path, &mut memory, &mut signals)
+    let id = loaders::load(&path, &mut memory, &mut signals)
         .expect(&format!("Failed to load test binary: {:?}", path));
+    memory
+        .load_compiler_spec("./SLEIGH/Processors/x86/data/languages/x86.ldefs", &id)
+        .expect("Failed to load compiler spec");
 
     memory
 }
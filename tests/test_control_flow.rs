@@ -42,7 +42,7 @@ fn test_loop_detection() {
     let (_, ast) = load_test_binary_and_decompile("simple_loop", "x86_64");
 
     // Verify loop exists
-    let loop_count = count_statements(&ast, |s| matches!(s, AstStatement::Loop { .. }));
+    let loop_count = count_statements(&ast, |s| s.is_loop());
 
     assert!(
         loop_count > 0,
@@ -59,3 +59,232 @@ fn test_has_return_statement() {
         "Expected Return statement in AST"
     );
 }
+
+#[test]
+fn test_loop_induction_variable() {
+    let mut memory = load_test_binary("simple_loop", "x86_64");
+
+    // sum_n: for (int i = 0; i < n; i++) { sum += i; }
+    let sum_n = bin_ast::ir::address::Address(0x401106);
+    mark_instructions(sum_n, &mut memory);
+    let hf = bin_ast::ir::high_function::HighFunction::from_mem(sum_n, &memory);
+
+    assert_eq!(hf.loops.len(), 1, "Expected exactly 1 natural loop");
+    let counter = hf.loops[0]
+        .induction_variables
+        .iter()
+        .find(|v| v.condition.is_some())
+        .expect("Expected the loop counter to be tested by the loop condition");
+    assert_eq!(counter.step, 1);
+
+    // the `for` header steps `i`, so the body only adds to `sum`
+    let ast = decompile_function(&memory, sum_n);
+    let is_for_with_single_assignment = |s: &AstStatement| match s {
+        AstStatement::For { body, .. } => match body.as_ref() {
+            AstStatement::Block(stmts) => {
+                stmts
+                    .iter()
+                    .filter(|s| matches!(s, AstStatement::Assignment { .. }))
+                    .count()
+                    == 1
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    let for_loops = count_statements(&ast, is_for_with_single_assignment);
+    assert_eq!(for_loops, 1);
+    // `i = 0` moves into the header too, `sum = 0` stays before the loop
+    let zeroings = count_statements(
+        &ast,
+        |s| matches!(s, AstStatement::Assignment { value, .. } if *value == counter.init),
+    );
+    assert_eq!(zeroings, 1);
+}
+
+#[test]
+fn test_switch_jump_table() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AbstractSyntaxTree, address::Address, basic_block::NextBlock,
+        high_function::HighFunction, jump_table::resolve_jump_tables,
+    };
+
+    let mut memory = load_test_binary("switch_table", "x86_64");
+
+    // classify: switch (n) with cases 0..=4 dispatched through a table at 0x402008
+    let classify = Address(0x401106);
+    mark_instructions(classify, &mut memory);
+    assert!(resolve_jump_tables(classify, &mut memory));
+
+    let dispatch = memory.ir.get_by_address(Address(0x40111e)).unwrap();
+    assert_matches!(&dispatch.next, NextBlock::Switch { cases, .. } if cases.len() == 5);
+
+    let hf = HighFunction::from_mem(classify, &memory);
+    let ast = AbstractSyntaxTree::new(&hf, &memory);
+    let case_values: Vec<u64> = hf
+        .composed_blocks
+        .iter()
+        .find_map(|(_, block)| match &block.next {
+            NextBlock::Switch { cases, .. } => Some(cases.iter().map(|(v, _)| *v).collect()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(case_values, vec![0, 1, 2, 3, 4]);
+    assert_eq!(
+        count_statements(&ast, |s| matches!(s, AstStatement::Switch { .. })),
+        1
+    );
+}
+
+#[test]
+fn test_switch_case_edges() {
+    use bin_ast::ir::{
+        address::Address, basic_block::NextBlock, control_flow_graph::LinkKind,
+        high_function::HighFunction, jump_table::resolve_jump_tables,
+    };
+
+    let mut memory = load_test_binary("switch_table", "x86_64");
+    let classify = Address(0x401106);
+    mark_instructions(classify, &mut memory);
+    assert!(resolve_jump_tables(classify, &mut memory));
+
+    let hf = HighFunction::from_mem(classify, &memory);
+    let (dispatch, _) = hf
+        .composed_blocks
+        .iter()
+        .find(|(_, block)| matches!(block.next, NextBlock::Switch { .. }))
+        .unwrap();
+
+    // Every case target gets its own edge labelled with the values that lead to it
+    let mut values: Vec<u64> = hf
+        .cfg
+        .successors(dispatch)
+        .into_iter()
+        .flat_map(|(_, link)| match link {
+            LinkKind::Case(_, values) => values.clone(),
+            _ => panic!("Expected only case edges out of the dispatch, got {link}"),
+        })
+        .collect();
+    values.sort();
+    assert_eq!(values, vec![0, 1, 2, 3, 4]);
+
+    // Case targets are constrained by the selector
+    for (successor, _) in hf.cfg.successors(dispatch) {
+        let identifier = hf.composed_blocks[successor].identifier;
+        assert!(!hf.path_constraint(identifier).unwrap().is_unconstrained());
+    }
+}
+
+#[test]
+fn test_short_circuit_conditions() {
+    use bin_ast::ir::{address::Address, expression::ExpressionOp};
+
+    let mut memory = load_test_binary("short_circuit", "x86_64");
+    let both = Address(0x401106);
+    let either = Address(0x401127);
+    mark_instructions(both, &mut memory);
+    mark_instructions(either, &mut memory);
+
+    // both functions jump on each condition separately, but decompile to a single `if`
+    let ifs = |addr: Address, merged: fn(&ExpressionOp) -> bool| {
+        let ast = decompile_function(&memory, addr);
+        let is_merged = |s: &AstStatement| match s {
+            AstStatement::If { condition, .. } => condition.root_op().is_some_and(merged),
+            _ => false,
+        };
+        (
+            count_statements(&ast, |s| matches!(s, AstStatement::If { .. })),
+            count_statements(&ast, is_merged),
+        )
+    };
+
+    // a > 0 && b > 0
+    let and = |op: &ExpressionOp| matches!(op, ExpressionOp::LogicalAnd(..));
+    assert_eq!(ifs(both, and), (1, 1));
+    // a < 0 || b < 0
+    let or = |op: &ExpressionOp| matches!(op, ExpressionOp::LogicalOr(..));
+    assert_eq!(ifs(either, or), (1, 1));
+}
+
+#[test]
+fn test_loop_flavours() {
+    use bin_ast::ir::address::Address;
+
+    let mut memory = load_test_binary("loop_flavours", "x86_64");
+    let halvings = Address(0x401106);
+    let digits = Address(0x401134);
+    let first_negative = Address(0x40116f);
+    for function in [halvings, digits, first_negative] {
+        mark_instructions(function, &mut memory);
+    }
+    let flavours = |addr: Address| {
+        let ast = decompile_function(&memory, addr);
+        (
+            count_statements(&ast, |s| matches!(s, AstStatement::While { .. })),
+            count_statements(&ast, |s| matches!(s, AstStatement::DoWhile { .. })),
+            count_statements(&ast, |s| matches!(s, AstStatement::For { .. })),
+            count_statements(&ast, |s| matches!(s, AstStatement::Break)),
+        )
+    };
+
+    // the test jumped to before the first iteration is the header
+    assert_eq!(flavours(halvings), (1, 0, 0, 0));
+    // the body is the header, and the test ends it
+    assert_eq!(flavours(digits), (0, 1, 0, 0));
+    // the test on the counter makes a `for`, the other way out is a `break`
+    assert_eq!(flavours(first_negative), (0, 0, 1, 1));
+}
+
+#[test]
+fn test_irreducible_loop_gotos() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AbstractSyntaxTree, address::Address, high_function::HighFunction,
+    };
+
+    let mut memory = load_test_binary("irreducible", "x86_64");
+    // countdown: the loop is entered both at `top` and at `middle`, so it isn't a natural loop
+    let countdown = Address(0x401106);
+    mark_instructions(countdown, &mut memory);
+    let hf = HighFunction::from_mem(countdown, &memory);
+    let ast = AbstractSyntaxTree::new(&hf, &memory);
+
+    assert!(
+        !ast.scope.labels.is_empty(),
+        "Expected the loop to be drawn with a goto"
+    );
+    for target in &ast.scope.labels {
+        let is_label = |s: &AstStatement| matches!(s, AstStatement::Label(l) if l == target);
+        let is_goto = |s: &AstStatement| matches!(s, AstStatement::Goto(l) if l == target);
+        assert_eq!(count_statements(&ast, is_label), 1);
+        assert!(count_statements(&ast, is_goto) > 0);
+    }
+    for slot in hf.composed_blocks.iter_function(hf.cfg.start) {
+        if slot != hf.cfg.single_end() {
+            assert!(ast.drawn.contains(&slot), "Block {slot:?} isn't drawn");
+        }
+    }
+}
+
+#[test]
+fn test_endless_loop_branch() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AbstractSyntaxTree, address::Address, high_function::HighFunction,
+    };
+
+    let mut memory = load_test_binary("endless", "x86_64");
+    // spin: for (;;) { if (n > 0) { ticks++; } }, where no block reaches the return
+    let spin = Address(0x401106);
+    mark_instructions(spin, &mut memory);
+    let hf = HighFunction::from_mem(spin, &memory);
+    let ast = AbstractSyntaxTree::new(&hf, &memory);
+
+    assert_eq!(
+        count_statements(&ast, |s| matches!(s, AstStatement::If { .. })),
+        1
+    );
+    for slot in hf.composed_blocks.iter_function(hf.cfg.start) {
+        if slot != hf.cfg.single_end() {
+            assert!(ast.drawn.contains(&slot), "Block {slot:?} isn't drawn");
+        }
+    }
+}
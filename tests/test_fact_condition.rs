@@ -40,6 +40,40 @@ fn test_fact_function_condition_rendering() {
     }
 }
 
+#[test]
+fn test_fact_function_path_constraints() {
+    use bin_ast::ir::address::Address;
+    use bin_ast::ir::high_function::{HighFunction, PathConstraint};
+
+    let mut memory = load_test_binary("simple_if", "x86_64");
+
+    // simple_if: if (x > 0) { return 1; } else { return -1; }
+    let simple_if = Address(0x401106);
+    mark_instructions_for_test(simple_if, &mut memory);
+
+    let hf = HighFunction::from_mem(simple_if, &memory);
+    assert_eq!(hf.path_constraints.len(), hf.composed_blocks.len());
+    let constraint = |addr: u64| {
+        let block = hf.composed_blocks.get_by_address(Address(addr)).unwrap();
+        hf.path_constraint(block.identifier).unwrap()
+    };
+
+    // The entry and the shared return block always execute, each side of the branch only under
+    // its condition
+    assert!(constraint(0x401106).is_unconstrained());
+    assert!(constraint(0x401123).is_unconstrained());
+
+    let (then, otherwise) = (constraint(0x401117), constraint(0x40111e));
+    assert_eq!(then.conditions().len(), 1);
+    assert_eq!(otherwise.conditions().len(), 1);
+    let negated =
+        |c: &PathConstraint| PathConstraint::default().and_branch(&c.conditions()[0], false);
+    assert!(
+        *otherwise == negated(then) || *then == negated(otherwise),
+        "Expected the sides of the branch to have opposite conditions, got {then} and {otherwise}"
+    );
+}
+
 fn visit_and_print_if_conditions(ast: &bin_ast::ir::abstract_syntax_tree::AbstractSyntaxTree) {
     visit_statement(ast.entry(), 0);
 }
@@ -38,3 +38,590 @@ fn test_decompile_entry_point() {
         "Failed to create AST for entry point function"
     );
 }
+
+#[test]
+fn test_calling_convention_models() {
+    use bin_ast::ir::calling_convention::CallingConvention;
+
+    let memory = create_test_memory_x86_64();
+    assert_eq!(
+        CallingConvention::default_for(&memory.lang),
+        CallingConvention::SystemV
+    );
+    let system_v = CallingConvention::SystemV.model(&memory.lang);
+    let rax = memory.lang.sleigh.get_reg("RAX").and_then(|r| r.get_var());
+    assert_eq!(system_v.return_register(&memory.lang), rax);
+    assert_eq!(system_v.argument_registers.len(), 6);
+
+    // 32-bit Microsoft conventions collapse into the x64 one
+    let stdcall = CallingConvention::Stdcall.model(&memory.lang);
+    assert_eq!(stdcall, CallingConvention::MicrosoftX64.model(&memory.lang));
+    assert_eq!(stdcall.stack_argument_offset, 40);
+    assert_eq!(stdcall.stack_cleanup(2), 8);
+
+    let memory = create_test_memory_x86_32();
+    assert_eq!(
+        CallingConvention::default_for(&memory.lang),
+        CallingConvention::Cdecl
+    );
+    let eax = memory.lang.sleigh.get_reg("EAX").and_then(|r| r.get_var());
+    let cdecl = CallingConvention::Cdecl.model(&memory.lang);
+    assert_eq!(cdecl.return_register(&memory.lang), eax);
+    assert_eq!(cdecl.stack_cleanup(2), 4);
+    let stdcall = CallingConvention::Stdcall.model(&memory.lang);
+    assert_eq!(stdcall.stack_cleanup(2), 12);
+    let ecx = memory
+        .lang
+        .sleigh
+        .get_reg("ECX")
+        .and_then(|r| r.get_var())
+        .unwrap();
+    let thiscall = CallingConvention::Thiscall.model(&memory.lang);
+    assert_eq!(thiscall.argument_registers, vec![String::from("ECX")]);
+    assert!(!thiscall.is_preserved(&memory.lang, ecx));
+    let esi = memory
+        .lang
+        .sleigh
+        .get_reg("ESI")
+        .and_then(|r| r.get_var())
+        .unwrap();
+    assert!(thiscall.is_preserved(&memory.lang, esi));
+}
+
+#[test]
+fn test_call_arguments_in_registers() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AstStatement, address::Address, basic_block::DestinationKind,
+    };
+
+    // main: add(mult(2, 3), 4), arguments passed in EDI and ESI
+    let mut memory = load_test_binary("function_calls", "x86_64");
+    mark_instructions(Address(0x401135), &mut memory);
+    let ast = decompile_function(&memory, Address(0x401135));
+    for callee in [0x40111e, 0x401106] {
+        assert_eq!(
+            count_statements(&ast, |s| matches!(
+                s,
+                AstStatement::Call { destination: DestinationKind::Concrete(d), params, .. }
+                    if *d == Address(callee) && params.len() == 2
+            )),
+            1,
+            "Expected a call with two arguments to {callee:#x}"
+        );
+    }
+}
+
+#[test]
+fn test_calls_clobber_registers() {
+    use bin_ast::ir::{
+        address::Address,
+        basic_block::NextBlock,
+        calling_convention::CallingConvention,
+        expression::{ExpressionOp, VariableSymbol},
+        high_function::HighFunction,
+    };
+
+    let mut memory = load_test_binary("function_calls", "x86_64");
+    assert_eq!(memory.calling_convention, Some(CallingConvention::SystemV));
+    let main = Address(0x401135);
+    mark_instructions(main, &mut memory);
+    let hf = HighFunction::from_mem(main, &memory);
+
+    let r11 = memory
+        .lang
+        .sleigh
+        .get_reg("R11")
+        .and_then(|r| r.get_var())
+        .unwrap();
+    let rbx = memory
+        .lang
+        .sleigh
+        .get_reg("RBX")
+        .and_then(|r| r.get_var())
+        .unwrap();
+    let mut calls = 0;
+    for (_, block) in hf.composed_blocks.iter() {
+        let NextBlock::Call {
+            origin,
+            default_return: Some(default_return),
+            ..
+        } = &block.next
+        else {
+            continue;
+        };
+        calls += 1;
+        // nothing in main writes R11 or RBX, so after the call they hold what the call left
+        let after_call = hf.composed_blocks.get_by_address(*default_return).unwrap();
+        let r11_state = after_call.registers.get(r11).unwrap();
+        assert_matches!(
+            r11_state.root_op(),
+            Some(ExpressionOp::Variable(VariableSymbol::Clobbered { call_from, .. }))
+                if call_from == origin
+        );
+        let rbx_state = after_call.registers.get(rbx);
+        assert!(!rbx_state.is_some_and(|state| state
+            .iter_vars()
+            .any(|v| matches!(v, VariableSymbol::Clobbered { .. }))));
+    }
+    assert_eq!(calls, 2);
+
+    // the ABI of the binary picks the convention, e.g. a PE file for x86-64 uses Microsoft's
+    memory.calling_convention = Some(CallingConvention::MicrosoftX64);
+    let hf = HighFunction::from_mem(main, &memory);
+    assert_eq!(
+        hf.calling_convention,
+        CallingConvention::MicrosoftX64.model(&memory.lang)
+    );
+}
+
+#[test]
+fn test_compiler_spec_conventions() {
+    use bin_ast::ir::compiler_spec::CompilerSpec;
+
+    let ldefs = "./SLEIGH/Processors/x86/data/languages/x86.ldefs";
+
+    let gcc = CompilerSpec::load(ldefs, "x86:LE:64:default", "gcc").unwrap();
+    let system_v = gcc.default_model();
+    assert_eq!(
+        system_v.argument_registers,
+        ["RDI", "RSI", "RDX", "RCX", "R8", "R9"].map(String::from)
+    );
+    assert_eq!(system_v.return_registers, ["RAX", "RDX"].map(String::from));
+    assert_eq!(system_v.return_address_size, 8);
+    assert!(!system_v.callee_cleanup);
+    assert!(system_v.preserved_registers.contains(&String::from("RBX")));
+    assert!(system_v.killed_registers.contains(&String::from("RDI")));
+    assert!(!system_v.killed_registers.contains(&String::from("RBX")));
+
+    let windows = CompilerSpec::load(ldefs, "x86:LE:64:default", "windows").unwrap();
+    let microsoft_x64 = windows.default_model();
+    assert_eq!(
+        microsoft_x64.argument_registers,
+        ["RCX", "RDX", "R8", "R9"].map(String::from)
+    );
+    assert_eq!(microsoft_x64.stack_argument_offset, 40);
+
+    let windows = CompilerSpec::load(ldefs, "x86:LE:32:default", "windows").unwrap();
+    assert_eq!(windows.default_prototype, "__stdcall");
+    assert!(windows.default_model().callee_cleanup);
+    assert_eq!(
+        windows.prototype("__fastcall").unwrap().argument_registers,
+        ["ECX", "EDX"].map(String::from)
+    );
+    assert!(!windows.prototype("__cdecl").unwrap().callee_cleanup);
+
+    assert!(CompilerSpec::load(ldefs, "x86:LE:64:default", "nonexistent").is_err());
+}
+
+#[test]
+fn test_stdcall_stack_delta() {
+    use bin_ast::ir::{address::Address, high_function::HighFunction};
+
+    let mut memory = load_test_binary("stdcall_calls", "x86_32");
+    let scale = Address(0x8049000);
+    let start = Address(0x804900c);
+    // linear decoding from `scale` covers `start` too
+    mark_instructions(scale, &mut memory);
+
+    // `scale` isn't analyzed yet, the delta of its calls comes from its `ret 8`
+    let caller = HighFunction::from_mem(start, &memory);
+    assert_eq!(caller.stack_delta, Some(4));
+
+    let callee = HighFunction::from_mem(scale, &memory);
+    assert_eq!(callee.stack_delta, Some(12));
+    memory.functions.insert(scale, callee);
+
+    // analyzed callees provide their own delta
+    let caller = HighFunction::from_mem(start, &memory);
+    assert_eq!(caller.stack_delta, Some(4));
+}
+
+#[test]
+fn test_function_summaries_at_call_sites() {
+    use bin_ast::ir::{address::Address, high_function::HighFunction};
+
+    let mut memory = load_test_binary("function_calls", "x86_64");
+    let add = Address(0x401106);
+    let mult = Address(0x40111e);
+    let main = Address(0x401135);
+    mark_instructions(main, &mut memory);
+    mark_instructions(add, &mut memory);
+    mark_instructions(mult, &mut memory);
+    let register = |name| {
+        memory
+            .lang
+            .sleigh
+            .get_reg(name)
+            .and_then(|r| r.get_var())
+            .unwrap()
+    };
+    let (rax, rdx, rbp, r11) = (
+        register("RAX"),
+        register("RDX"),
+        register("RBP"),
+        register("R11"),
+    );
+    let clobbers = |hf: &HighFunction, var: pcode::VarNode| {
+        hf.summary
+            .clobbered_registers
+            .iter()
+            .any(|r| r.id == var.id)
+    };
+
+    // add(a, b): loads `a` into EDX and returns in EAX, restoring RBP
+    let callee = HighFunction::from_mem(add, &memory);
+    assert_eq!(callee.summary.argument_count, 2);
+    assert_eq!(callee.summary.return_register.map(|r| r.id), Some(rax.id));
+    assert_eq!(callee.summary.stack_delta, Some(8));
+    assert!(callee.summary.globals_written.is_empty());
+    assert!(clobbers(&callee, rax) && clobbers(&callee, rdx));
+    assert!(!clobbers(&callee, rbp));
+
+    // main never writes R11 itself, the calling convention guesses that its callees may
+    let caller = HighFunction::from_mem(main, &memory);
+    assert!(clobbers(&caller, r11));
+    // once both callees are summarized, only what they really clobber is
+    memory.functions.insert(add, callee);
+    memory
+        .functions
+        .insert(mult, HighFunction::from_mem(mult, &memory));
+    let caller = HighFunction::from_mem(main, &memory);
+    assert!(clobbers(&caller, rdx));
+    assert!(!clobbers(&caller, r11));
+    assert!(caller.summary.return_register.is_some());
+    assert_eq!(memory.callers_of(add), Vec::<Address>::new());
+    memory.functions.insert(main, caller);
+    assert_eq!(memory.callers_of(add), vec![main]);
+}
+
+#[test]
+fn test_system_v_parameters() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AstStatement, address::Address, basic_block::DestinationKind,
+        expression::VariableSymbol, high_function::HighFunction,
+    };
+
+    let mut memory = load_test_binary("many_args", "x86_64");
+    let sum8 = Address(0x401106);
+    let scale = Address(0x401159);
+    let main = Address(0x401181);
+    // linear decoding from `sum8` covers `scale` and `main` too
+    mark_instructions(sum8, &mut memory);
+    let reg = |name: &str| {
+        memory
+            .lang
+            .sleigh
+            .get_reg(name)
+            .and_then(|r| r.get_var())
+            .unwrap()
+    };
+
+    // sum8(a, b, c, d, e, f, g, h): six registers, then two stack slots above the return address
+    let callee = HighFunction::from_mem(sum8, &memory);
+    let registers: Vec<_> = ["RDI", "RSI", "RDX", "RCX", "R8", "R9"]
+        .map(|name| VariableSymbol::Varnode(reg(name)))
+        .into();
+    assert_eq!(callee.parameters.len(), 8);
+    assert_eq!(callee.parameters[..6], registers[..]);
+    assert!(callee.parameters[6..]
+        .iter()
+        .all(|p| matches!(p, VariableSymbol::Ram(_, 8))));
+    assert_eq!(callee.return_register.map(|r| r.id), Some(reg("RAX").id));
+    let ast = decompile_function(&memory, sum8);
+    assert_eq!(
+        count_statements(&ast, |s| matches!(
+            s,
+            AstStatement::Function { args, .. } if args.len() == 8
+        )),
+        1
+    );
+    memory.functions.insert(sum8, callee);
+
+    // scale(double x, long factor): the double comes in XMM0 and goes back in XMM0
+    let callee = HighFunction::from_mem(scale, &memory);
+    assert_eq!(
+        callee.parameters,
+        vec![
+            VariableSymbol::Varnode(reg("RDI")),
+            VariableSymbol::Varnode(reg("XMM0_Qa"))
+        ]
+    );
+    assert_eq!(callee.return_register, Some(reg("XMM0_Qa")));
+    memory.functions.insert(scale, callee);
+
+    // main(): calls pass as many arguments as the callees read
+    let caller = HighFunction::from_mem(main, &memory);
+    assert!(caller.parameters.is_empty());
+    assert_eq!(caller.return_register.map(|r| r.id), Some(reg("RAX").id));
+    let ast = caller.build_ast(&memory);
+    for (callee, count) in [(sum8, 8), (scale, 2)] {
+        assert_eq!(
+            count_statements(&ast, |s| matches!(
+                s,
+                AstStatement::Call { destination: DestinationKind::Concrete(d), params, .. }
+                    if *d == callee && params.len() == count
+            )),
+            1,
+            "Expected a call with {count} arguments to {callee}"
+        );
+    }
+}
+
+#[test]
+fn test_stack_frame_variables() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AstStatement, address::Address, expression::VariableSymbol,
+        high_function::HighFunction, stack_frame::StackVariableKind,
+    };
+
+    // add(a, b) spills EDI and ESI below the saved RBP
+    let mut memory = load_test_binary("function_calls", "x86_64");
+    let add = Address(0x401106);
+    mark_instructions(add, &mut memory);
+    let hf = HighFunction::from_mem(add, &memory);
+    let names: Vec<_> = hf
+        .stack_frame
+        .variables()
+        .iter()
+        .map(|v| v.name())
+        .collect();
+    assert_eq!(names, ["local_10", "local_c"]);
+    assert!(hf
+        .stack_frame
+        .variables()
+        .iter()
+        .all(|v| v.kind == StackVariableKind::Local && v.size == 4));
+
+    // locals are defined in the function scope and can be renamed there
+    let mut ast = hf.build_ast(&memory);
+    let local = hf.stack_frame.variables()[1].symbol(memory.lang.sp);
+    let section = ast.scope.find_owning_section(&local).unwrap();
+    assert_eq!(
+        ast.scope
+            .get_symbol_recursive(section, &local)
+            .unwrap()
+            .name,
+        "local_c"
+    );
+    ast.scope.get_symbol_mut(section, &local).unwrap().name = String::from("a");
+    assert_eq!(
+        ast.scope
+            .get_symbol_recursive(section, &local)
+            .unwrap()
+            .name,
+        "a"
+    );
+
+    // scale(value, factor) reads both of its stdcall parameters above the return address
+    let mut memory = load_test_binary("stdcall_calls", "x86_32");
+    let scale = Address(0x8049000);
+    mark_instructions(scale, &mut memory);
+    let hf = HighFunction::from_mem(scale, &memory);
+    let names: Vec<_> = hf
+        .stack_frame
+        .variables()
+        .iter()
+        .map(|v| v.name())
+        .collect();
+    assert_eq!(names, ["param_4", "param_8"]);
+    let ast = hf.build_ast(&memory);
+    let params: Vec<_> = hf
+        .stack_frame
+        .variables()
+        .iter()
+        .map(|v| v.symbol(memory.lang.sp))
+        .collect();
+    assert_matches!(
+        ast.entry(),
+        AstStatement::Block(statements)
+            if matches!(
+                &statements[..],
+                [AstStatement::Function { args, .. }]
+                    if args == &params && matches!(args[0], VariableSymbol::Ram(_, 4))
+            )
+    );
+}
+
+#[test]
+fn test_frame_pointer_accesses() {
+    use bin_ast::ir::{
+        address::Address,
+        expression::VariableSymbol,
+        high_function::HighFunction,
+        stack_frame::{stack_offset, FramePointer},
+    };
+
+    let mut memory = load_test_binary("frame_pointer", "x86_32");
+    let scale = Address(0x8049000);
+    let start = Address(0x804900c);
+    mark_instructions(scale, &mut memory);
+    let sp = memory.lang.sp;
+    let ebp = memory
+        .lang
+        .sleigh
+        .get_reg("EBP")
+        .and_then(|r| r.get_var())
+        .unwrap();
+
+    // `push ebp; mov ebp, esp`, then the loop addresses locals through EBP and the outgoing
+    // arguments through ESP
+    let hf = HighFunction::from_mem(start, &memory);
+    assert_eq!(
+        hf.frame_pointer,
+        Some(FramePointer {
+            register: ebp,
+            offset: -4
+        })
+    );
+    for addr in hf.memory_read.iter().chain(&hf.memory_written) {
+        assert!(
+            !addr
+                .iter_vars()
+                .any(|v| matches!(v, VariableSymbol::Varnode(r) if r.id == ebp.id)),
+            "{addr} is relative to EBP"
+        );
+    }
+    assert!(hf
+        .memory_written
+        .iter()
+        .all(|addr| stack_offset(addr, sp).is_some()));
+    let names: Vec<_> = hf
+        .stack_frame
+        .variables()
+        .iter()
+        .map(|v| v.name())
+        .collect();
+    assert_eq!(
+        names,
+        ["local_1c", "local_18", "local_c", "local_8", "param_4"]
+    );
+
+    // [EBP + 0x8] and [EBP + 0xc] are the same parameters as [ESP + 0x4] and [ESP + 0x8]
+    let hf = HighFunction::from_mem(scale, &memory);
+    assert_eq!(
+        hf.frame_pointer,
+        Some(FramePointer {
+            register: ebp,
+            offset: -4
+        })
+    );
+    let names: Vec<_> = hf
+        .stack_frame
+        .variables()
+        .iter()
+        .map(|v| v.name())
+        .collect();
+    assert_eq!(names, ["param_4", "param_8"]);
+    let params: Vec<_> = hf
+        .stack_frame
+        .variables()
+        .iter()
+        .map(|v| v.symbol(sp))
+        .collect();
+    assert_eq!(hf.parameters, params);
+}
+
+#[test]
+fn test_noreturn_calls() {
+    use bin_ast::ir::{
+        address::Address,
+        basic_block::{DestinationKind, NextBlock},
+        control_flow_graph::CFGProperties,
+        high_function::HighFunction,
+    };
+
+    let mut memory = load_test_binary("noreturn", "x86_64");
+    let fail = Address(0x401136);
+    let check = Address(0x401158);
+    mark_instructions(fail, &mut memory);
+    let exit = DestinationKind::Concrete(Address(0x401040));
+    assert!(memory.never_returns(&exit));
+
+    // fail(message) ends in `call exit`, right before check() starts
+    let hf = HighFunction::from_mem(fail, &memory);
+    assert!(hf.cfg.properties.contains(CFGProperties::NeverReturns));
+    assert!(hf.summary.never_returns);
+    assert!(hf
+        .composed_blocks
+        .iter()
+        .all(|(_, b)| b.identifier.start_address() < check));
+
+    // the call to fail ends the `value < 0` branch of check
+    let call_to_fail = |hf: &HighFunction| {
+        hf.composed_blocks.iter().find_map(|(_, b)| match &b.next {
+            NextBlock::Call {
+                destination: DestinationKind::Concrete(d),
+                default_return,
+                ..
+            } if *d == fail => Some(*default_return),
+            _ => None,
+        })
+    };
+    let caller = HighFunction::from_mem(check, &memory);
+    assert_eq!(call_to_fail(&caller), Some(Some(Address(0x401173))));
+    memory.functions.insert(fail, hf);
+    let caller = HighFunction::from_mem(check, &memory);
+    assert_eq!(call_to_fail(&caller), Some(None));
+    assert!(!caller.summary.never_returns);
+    assert!(caller.return_register.is_some());
+
+    // dispatch(target) has no return either, but leaves through `jmp` to wherever target is
+    let dispatch = HighFunction::from_mem(Address(0x401198), &memory);
+    assert!(!dispatch
+        .cfg
+        .properties
+        .contains(CFGProperties::NeverReturns));
+    assert!(!dispatch.summary.never_returns);
+}
+
+#[test]
+fn test_tail_calls() {
+    use bin_ast::ir::{
+        abstract_syntax_tree::AstStatement,
+        address::Address,
+        basic_block::{DestinationKind, NextBlock},
+        high_function::HighFunction,
+    };
+
+    let mut memory = load_test_binary("tail_calls", "x86_64");
+    let step = Address(0x401150);
+    let twice = Address(0x401170);
+    let forward = Address(0x401180);
+    // lifted from main, whose call to forward makes it start a block
+    mark_instructions(Address(0x401040), &mut memory);
+
+    let tail_call = |hf: &HighFunction| {
+        hf.composed_blocks.iter().find_map(|(_, b)| match &b.next {
+            NextBlock::TailCall {
+                origin,
+                destination: DestinationKind::Concrete(d),
+            } => Some((*origin, *d)),
+            _ => None,
+        })
+    };
+
+    // twice(x) pops RBX again before `jmp step`, so the jump leaves the function
+    let hf = HighFunction::from_mem(twice, &memory);
+    assert_eq!(tail_call(&hf), Some((Address(0x40117c), step)));
+    assert!(hf
+        .composed_blocks
+        .iter()
+        .all(|(_, b)| (twice..forward).contains(&b.identifier.start_address())));
+    assert_eq!(hf.stack_delta, Some(8));
+    let ast = hf.build_ast(&memory);
+    assert_eq!(
+        count_statements(&ast, |s| matches!(
+            s,
+            AstStatement::Call { destination: DestinationKind::Concrete(d), tail: true, .. }
+                if *d == step
+        )),
+        1
+    );
+    memory.functions.insert(twice, hf);
+
+    // forward(x) jumps to twice with nothing else on the stack
+    let hf = HighFunction::from_mem(forward, &memory);
+    assert_eq!(tail_call(&hf), Some((Address(0x401183), twice)));
+    assert_eq!(hf.composed_blocks.len(), 1);
+    assert_eq!(hf.stack_delta, Some(8));
+}
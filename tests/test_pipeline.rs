@@ -49,3 +49,21 @@ fn test_load_nested_control_x86_64() {
         "Expected AST to have a Block statement at entry"
     );
 }
+
+#[test]
+fn test_plt_symbols_x86_64() {
+    use bin_ast::ir::{address::Address, basic_block::DestinationKind};
+
+    let memory = load_test_binary("noreturn", "x86_64");
+
+    // the first .plt entry jumps to the dynamic linker, the stubs follow it
+    let name = |addr: u64| {
+        memory
+            .symbols
+            .resolve_destination(&DestinationKind::Concrete(Address(addr)))
+            .map(|symbol| symbol.name.clone())
+    };
+    assert_eq!(name(0x401020), None);
+    assert_eq!(name(0x401030).as_deref(), Some("puts"));
+    assert_eq!(name(0x401040).as_deref(), Some("exit"));
+}